@@ -1,9 +1,19 @@
 use action::Action;
 use action::ElementLink;
-pub use draw::DrawState;
+pub use checkpoint::Checkpoint;
+use checkpoint::SolveProgress;
+use deps::is_bit_set;
+pub use draw::{DrawFormat, DrawOptions, DrawState, Viewport};
+pub use generality::{Generality, GeneralityReport};
+pub use json::JsonState;
+pub use lemma::Lemma;
+pub use limits::SearchLimits;
 pub use print::PrintState;
 use random_walk::RandomWalkProcessing;
 use random_walk::RandomWalkSolution;
+pub use solution::{PointRef, ShapeRef, Solution, SolutionStep};
+pub use tool::{InputKind, Tool, TOOLS};
+pub use verify::Verify;
 
 use crate::element::CircleCP;
 use crate::element::CircleCR;
@@ -26,15 +36,31 @@ use std::cmp::Ord;
 use std::collections::BinaryHeap;
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::time::SystemTime;
+use std::ops::Range;
+use std::time::{Duration, SystemTime};
 
 mod action;
+mod checkpoint;
+mod deps;
 mod draw;
+mod generality;
+mod geogebra;
+mod json;
+mod lemma;
+mod limits;
 mod print;
 mod random_walk;
+mod replay;
+mod solution;
+#[cfg(test)]
+mod test_helpers;
+mod tool;
+mod verify;
 
 const GIVEN: i32 = -1;
 const RANDOM_WALK_LIMIT: u32 = 500000000;
+// Fewer indices are checked on the current thread, as splitting them costs more than it saves
+const PARALLEL_MIN_LEN: usize = 256;
 
 #[derive(Debug)]
 struct PointOrigin {
@@ -54,6 +80,66 @@ impl<'a> ShapeOrigin<'a> {
     pub fn get_shape(&self) -> Shape {
         self.element_link.get_shape()
     }
+
+    // Points placed by point actions (or made by macros) have shape origins for their deps
+    // index, with the point as the shape (see Shape::point())
+    pub fn is_placed_point(&self) -> bool {
+        matches!(&self.element_link, ElementLink::Action(action) if action.shape.as_point().is_some())
+    }
+}
+
+// Output files written by solve(); None disables the corresponding output
+pub struct SolveOptions {
+    pub final_svg: Option<String>,
+    pub solution_svg: Option<String>,
+    pub rw_solution_prefix: Option<String>,
+    pub solution_shapes: Option<String>,
+    pub solutions_json: Option<String>,
+    pub state_json: Option<String>,
+    pub geogebra_script: Option<String>,
+    // Step-by-step replay: an HTML page for .html files, an animated SVG otherwise
+    pub replay: Option<String>,
+    // The format of the other drawings is chosen by the extension of their file names
+    pub rw_solution_format: DrawFormat,
+    pub draw_options: DrawOptions,
+    // State saved every checkpoint_interval for resuming the search (see Checkpoint)
+    pub checkpoint: Option<String>,
+    pub checkpoint_interval: Duration,
+    pub limits: SearchLimits,
+    // Threads checking the new actions (rayon's default when None); the search doesn't depend
+    // on their number
+    pub threads: Option<usize>,
+}
+impl Default for SolveOptions {
+    fn default() -> Self {
+        Self {
+            final_svg: Some("final.svg".to_string()),
+            solution_svg: Some("solution.svg".to_string()),
+            rw_solution_prefix: Some("rw_solution_".to_string()),
+            solution_shapes: None,
+            solutions_json: None,
+            state_json: None,
+            geogebra_script: None,
+            replay: None,
+            rw_solution_format: DrawFormat::Svg,
+            draw_options: DrawOptions::default(),
+            checkpoint: None,
+            checkpoint_interval: Duration::from_secs(600),
+            limits: SearchLimits::default(),
+            threads: None,
+        }
+    }
+}
+impl SolveOptions {
+    // Writes no drawing (the other outputs are off by default)
+    pub fn without_files() -> Self {
+        Self {
+            final_svg: None,
+            solution_svg: None,
+            rw_solution_prefix: None,
+            ..Self::default()
+        }
+    }
 }
 
 pub struct Computation<'a> {
@@ -68,9 +154,20 @@ pub struct Computation<'a> {
     found_shapes: HashSet2<Shape>,
     queue: BinaryHeap<Action>,
     deps_combinations: Vec<Vec<u32>>,
-    deps_indices_by_hashes: HashMap<u64, Vec<i32>>,
+    deps_indices_by_hashes: HashMap<u64, Vec<u32>>,
+    deps_buffer: Vec<u32>,
     shape_to_find_mask_by_shape: HashMap2<Shape, u32>,
     solution_deps: Option<u64>,
+    // The problem's action_count, unless lowered by solve_iteratively()
+    action_count: u32,
+    // The problem's multimatch, unless forced by solve_iteratively()
+    multimatch: bool,
+    // Whether the last solve() explored all actions within action_count
+    exhausted: bool,
+    // Set by load_checkpoint() for solve() to continue from
+    resumed: Option<SolveProgress>,
+    // The limit of SolveOptions::limits that stopped the last solve(), if any
+    stop_reason: Option<String>,
 }
 impl<'a> Computation<'a> {
     pub fn new(problem: &'a ProblemDefinition) -> Self {
@@ -89,199 +186,85 @@ impl<'a> Computation<'a> {
             queue: BinaryHeap::new(),
             deps_combinations: vec![vec![]],
             deps_indices_by_hashes,
+            deps_buffer: vec![],
             shape_to_find_mask_by_shape: HashMap2::new(),
             solution_deps: None,
+            action_count: problem.action_count,
+            multimatch: problem.multimatch,
+            exhausted: false,
+            resumed: None,
+            stop_reason: None,
         }
     }
 
-    fn get_deps_count(&self, deps: u64) -> u32 {
-        let lower_mask = deps & ((1u64 << 40) - 1);
-        if deps == lower_mask {
-            lower_mask.count_ones()
-        } else {
-            self.deps_combinations[(deps >> 40) as usize].len_u32() + lower_mask.count_ones()
+    // None for given elements
+    fn get_origin_action_type(&self, index: usize) -> Option<ActionType> {
+        match &self.shape_origins[index].element_link {
+            ElementLink::GivenElement { .. } => None,
+            ElementLink::Action(action) => Some(action.action_type),
         }
     }
 
-    fn print_deps(&self, deps: u64) -> String {
-        let part2: Vec<u32> = self.deps_combinations[(deps >> 40) as usize]
-            .iter()
-            .map(|x| x + 39)
-            .collect();
-        let mut part1: Vec<u32> = (0..40)
-            .filter_map(|i| if deps & (1 << i) != 0 { Some(i) } else { None })
-            .collect();
-        part1.extend(part2);
-        format!("{:?}", part1)
-    }
-
-    fn get_combined_deps_count(&self, deps1: u64, deps2: u64) -> u32 {
-        let combined_mask = deps1 | deps2;
-        let lower_mask = combined_mask & ((1u64 << 40) - 1);
-        if combined_mask == lower_mask {
-            return lower_mask.count_ones();
-        }
-        let deps_vec_1 = &self.deps_combinations[(deps1 >> 40) as usize];
-        let deps_vec_2 = &self.deps_combinations[(deps2 >> 40) as usize];
-        let mut same_count = 0;
-        for i1 in deps_vec_1 {
-            for i2 in deps_vec_2 {
-                if i1 == i2 {
-                    same_count += 1;
-                }
-            }
-        }
-        lower_mask.count_ones() + deps_vec_1.len_u32() + deps_vec_2.len_u32() - same_count
+    // Cost of the action creating the shape origin, 0 for given elements
+    fn get_origin_cost(&self, index: usize) -> u32 {
+        self.get_origin_action_type(index)
+            .map_or(0, |action_type| self.problem.get_action_cost(action_type))
     }
 
-    fn _get_deps_combined_with_index_count(&self, deps: u64, index: i32) -> u32 {
-        if index < 40 {
-            self.get_deps_count(deps | (1 << index))
-        } else if deps < (1u64 << 40) {
-            self.get_deps_count(deps) + 1
-        } else {
-            let deps_vec = &self.deps_combinations[(deps >> 40) as usize];
-            if deps_vec.contains(&(index as u32)) {
-                self.get_deps_count(deps)
-            } else {
-                self.get_deps_count(deps) + 1
-            }
-        }
-    }
-
-    #[allow(dead_code)]
-    fn get_combined_three_deps_count(&self, deps1: u64, deps2: u64, deps3: u64) -> u32 {
-        let combined_mask = deps1 | deps2 | deps3;
-        let lower_mask = combined_mask & ((1u64 << 40) - 1);
-        if combined_mask == lower_mask {
-            return lower_mask.count_ones();
-        }
-        let deps_vec_1 = &self.deps_combinations[(deps1 >> 40) as usize];
-        let deps_vec_2 = &self.deps_combinations[(deps2 >> 40) as usize];
-        let deps_vec_3 = &self.deps_combinations[(deps3 >> 40) as usize];
-        let mut same_two_count = 0;
-        let mut all_three_count = 0;
-        for i1 in deps_vec_1 {
-            for i2 in deps_vec_2 {
-                if i1 == i2 {
-                    same_two_count += 1;
-                    for i3 in deps_vec_3 {
-                        if i3 == i1 {
-                            all_three_count += 1
-                        }
-                    }
-                }
-            }
-        }
-        for i1 in deps_vec_1 {
-            for i2 in deps_vec_3 {
-                if i1 == i2 {
-                    same_two_count += 1;
-                }
-            }
-        }
-        for i1 in deps_vec_2 {
-            for i2 in deps_vec_3 {
-                if i1 == i2 {
-                    same_two_count += 1;
-                }
-            }
-        }
-        lower_mask.count_ones() + deps_vec_1.len_u32() + deps_vec_2.len_u32() + deps_vec_3.len_u32()
-            - same_two_count
-            + all_three_count
-    }
-
-    fn get_index_of_deps_combination(&mut self, high_indices: &[u32]) -> i32 {
-        let mut sum = 0u32;
-        let mut sum2 = 0u32;
-        for i in high_indices {
-            sum += *i;
-            sum2 += *i * *i;
-        }
-        let hash = ((sum as u64) << 32u64) | (sum2 as u64);
-        let deps_indices = self.deps_indices_by_hashes.get(&hash);
-        match deps_indices {
-            None => {
-                let new_index = self.deps_combinations.len_i32();
-                self.deps_indices_by_hashes.insert(hash, vec![new_index]);
-                self.deps_combinations.push(high_indices.to_vec());
-                new_index
-            }
-            Some(indices) => {
-                let mut found_index = -1;
-                for index in indices {
-                    if high_indices == self.deps_combinations[*index as usize] {
-                        found_index = *index;
-                        break;
-                    }
-                }
-                if found_index < 0 {
-                    let new_index = self.deps_combinations.len_i32();
-                    self.deps_indices_by_hashes
-                        .get_mut(&hash)
-                        .unwrap()
-                        .push(new_index);
-                    self.deps_combinations.push(high_indices.to_vec());
-                    new_index
-                } else {
-                    found_index
-                }
-            }
+    // Total cost of the actions in the union of the deps; 0 without a cost budget, as the
+    // costs are only needed for it
+    fn get_deps_union_cost(&self, deps_list: &[u64]) -> u32 {
+        if self.problem.cost_budget.is_none() {
+            return 0;
         }
+        self.get_deps_union_indices(deps_list)
+            .iter()
+            .map(|i| self.get_origin_cost(*i as usize))
+            .sum()
     }
 
-    pub fn combine_deps(&mut self, deps1: u64, deps2: u64, index: Option<i32>) -> u64 {
-        let mut buffer: [u32; 1000] = [0; 1000];
-        let index_mask = match index {
-            Some(value) if value < 40 => 1 << value,
-            Some(_value) => 1 << 40,
-            None => 0,
-        };
-        let combined_mask = deps1 | deps2 | index_mask;
-        let lower_mask = combined_mask & ((1u64 << 40) - 1);
-        if combined_mask == lower_mask {
-            return lower_mask;
-        }
-        if (deps1 >> 40) == (deps2 >> 40) && index_mask < (1 << 40) {
-            return lower_mask | (deps1 & !((1u64 << 40) - 1));
-        }
-        let deps_vec_1 = &self.deps_combinations[(deps1 >> 40) as usize];
-        let deps_vec_2 = &self.deps_combinations[(deps2 >> 40) as usize];
-        let mut size = deps_vec_1.len() + deps_vec_2.len();
-        buffer[0..deps_vec_1.len()].copy_from_slice(deps_vec_1.as_slice());
-        buffer[deps_vec_1.len()..size].copy_from_slice(deps_vec_2.as_slice());
-        if index.is_some() {
-            buffer[size] = (index.unwrap() as u32) - 39u32;
-            size += 1;
-        }
-        buffer[0..size].sort_unstable();
-        let mut j = 0;
-        for i in 0..size {
-            if i == 0 || buffer[i] != buffer[i - 1] {
-                buffer[j] = buffer[i];
-                j += 1;
-            }
+    // Uses of the groups of ProblemDefinition::action_limits by the actions in the union of
+    // the deps (empty without limits)
+    fn get_deps_union_usage(&self, deps_list: &[u64]) -> Vec<u32> {
+        if self.problem.action_limits.is_empty() {
+            return vec![];
         }
+        let indices = self.get_deps_union_indices(deps_list);
+        self.problem.get_action_usage(
+            indices
+                .iter()
+                .filter_map(|i| self.get_origin_action_type(*i as usize)),
+        )
+    }
 
-        let index = self.get_index_of_deps_combination(&buffer[0..j]);
-        lower_mask | ((index as u64) << 40)
+    // Whether a new construction of the shape at the index can share its deps index: the
+    // deps are then counted with the action of the first construction
+    fn can_share_deps_index(&self, index: usize, action_type: ActionType) -> bool {
+        let previous_type = self.get_origin_action_type(index);
+        let same_cost = self.problem.cost_budget.is_none()
+            || self.get_origin_cost(index) == self.problem.get_action_cost(action_type);
+        same_cost
+            && self.problem.action_limits.iter().all(|(group, _)| {
+                previous_type.is_some_and(|value| group.contains(value))
+                    == group.contains(action_type)
+            })
     }
 
-    fn requires_deps(&self, action_deps: &[u64; 3], deps: u64) -> bool {
-        if deps == (deps & ((1u64 << 40) - 1)) {
-            (action_deps[0] & deps) == deps
-                || (action_deps[1] & deps) == deps
-                || (action_deps[2] & deps) == deps
-        } else {
-            for i in 0..3 {
-                if self.get_combined_deps_count(action_deps[i], deps)
-                    == self.get_deps_count(action_deps[i])
-                {
-                    return true;
-                }
-            }
-            false
+    // Whether a construction of the given cost leaves enough of the cost budget to add the
+    // cheapest action for each of the reserved shapes
+    fn fits_cost_budget(&self, cost: u32, reserved: u32) -> bool {
+        match self.problem.cost_budget {
+            Some(budget) => cost + reserved * self.problem.get_min_action_cost() <= budget,
+            None => true,
+        }
+    }
+
+    // What's left of the search bound after the inputs of an action: of the cost budget if
+    // there's one, of action_count otherwise
+    fn get_remaining_budget(&self, deps_count: u32, deps_cost: u32) -> i32 {
+        match self.problem.cost_budget {
+            Some(budget) => budget as i32 - deps_cost as i32,
+            None => self.action_count as i32 - deps_count as i32,
         }
     }
 
@@ -324,72 +307,6 @@ impl<'a> Computation<'a> {
         }
     }
 
-    // Order bits of all_deps and use indices corresponding to deps as bits for the result
-    fn compress(&self, deps: u64, all_deps: u64) -> u64 {
-        let deps_combination = &self.deps_combinations[(deps >> 40) as usize];
-        let index_set: HashSet<u32> = HashSet::from_iter(deps_combination.iter().cloned());
-        let mut index = 0;
-        let mut result = 0u64;
-        for i in 0..40 {
-            if (all_deps & (1 << i)) != 0 {
-                if deps & (1 << i) != 0 {
-                    result |= 1 << index;
-                }
-                index += 1;
-            }
-        }
-        for i in &self.deps_combinations[(all_deps >> 40) as usize] {
-            if index_set.contains(i) {
-                result |= 1 << index;
-            }
-            index += 1;
-        }
-        result
-    }
-
-    fn decompress(&mut self, union: u64, all_deps: u64) -> u64 {
-        let mut high_indices = [0; 100];
-        let mut high_index_count = 0;
-        let mut index = 0;
-        let mut lower_mask = 0u64;
-        for i in 0..40 {
-            if (all_deps & (1 << i)) != 0 {
-                if union & (1 << index) != 0 {
-                    lower_mask |= 1 << i;
-                }
-                index += 1;
-            }
-        }
-        for i in &self.deps_combinations[(all_deps >> 40) as usize] {
-            if union & (1 << index) != 0 {
-                high_indices[high_index_count] = *i;
-                high_index_count += 1;
-            }
-            index += 1;
-        }
-        let dep_combination_index =
-            self.get_index_of_deps_combination(&high_indices[0..high_index_count]);
-        ((dep_combination_index as u64) << 40) | lower_mask
-    }
-
-    fn find_shortest_deps_union(deps_lists: &Vec<Vec<u64>>, union_so_far: u64, index: u32) -> u64 {
-        if index == deps_lists.len_u32() {
-            return union_so_far;
-        }
-        let mut min_len = 64;
-        let mut best_deps = 0;
-        for deps in &deps_lists[index as usize] {
-            let to_check =
-                Self::find_shortest_deps_union(deps_lists, union_so_far | deps, index + 1);
-            let len = to_check.count_ones();
-            if len < min_len {
-                min_len = len;
-                best_deps = to_check;
-            }
-        }
-        best_deps
-    }
-
     fn check_multimatch_solution_found(&mut self) {
         if self.points_to_find.len() > 0 || self.shapes_to_find.len() > 0 {
             return;
@@ -445,14 +362,45 @@ impl<'a> Computation<'a> {
         //         println!("{:b}", deps);
         //     }
         // }
-        let shortest_union = Self::find_shortest_deps_union(&deps_lists, 0, 0);
+        // Costs of the bits in the order used by compress(); all 1 without a cost budget
+        let costs: Vec<u32> = if self.problem.cost_budget.is_some() {
+            self.get_deps_indices(all_relevant_deps)
+                .iter()
+                .map(|i| self.get_origin_cost(*i as usize))
+                .collect()
+        } else {
+            vec![1; self.get_deps_count(all_relevant_deps) as usize]
+        };
+        // Action types of the bits, for the action limits
+        let action_types: Vec<Option<ActionType>> = self
+            .get_deps_indices(all_relevant_deps)
+            .iter()
+            .map(|i| self.get_origin_action_type(*i as usize))
+            .collect();
+        let problem = self.problem;
+        let fits = |union: &[u64]| {
+            let usage = problem.get_action_usage(
+                (0..action_types.len())
+                    .filter(|i| is_bit_set(union, *i))
+                    .filter_map(|i| action_types[i]),
+            );
+            problem.is_within_action_limits(&usage)
+        };
+        let Some(shortest_union) =
+            Self::find_shortest_deps_union(&deps_lists, &[], 0, &costs, &fits)
+        else {
+            println!("No union of the deps is within the action limits");
+            return;
+        };
         println!(
             "Deps in the shortest union: {}",
-            shortest_union.count_ones()
+            Self::get_compressed_count(&shortest_union)
         );
 
-        if shortest_union.count_ones() <= self.problem.action_count {
-            self.solution_deps = Some(self.decompress(shortest_union, all_relevant_deps));
+        if Self::get_compressed_count(&shortest_union) <= self.action_count
+            && self.fits_cost_budget(Self::get_compressed_cost(&shortest_union, &costs), 0)
+        {
+            self.solution_deps = Some(self.decompress(&shortest_union, all_relevant_deps));
             println!(
                 "Solution deps: {}",
                 self.print_deps(self.solution_deps.unwrap())
@@ -482,7 +430,7 @@ impl<'a> Computation<'a> {
 
     fn register_point(&mut self, point: Point, shape_origin_indices: [i32; 2]) {
         let seen_before = self.points.contains_key(point);
-        if seen_before && !self.problem.multimatch {
+        if seen_before && !self.multimatch {
             return;
         }
         if !seen_before && self.points_to_find.contains(point) {
@@ -508,11 +456,11 @@ impl<'a> Computation<'a> {
             0
         };
         let combined_deps = self.combine_deps(deps1, deps2, None);
-        if self.get_deps_count(combined_deps) > self.problem.action_count {
+        if self.get_deps_count(combined_deps) > self.action_count {
             println!(
                 "Shouldn't happen: deps count {} is above action count {}",
                 self.get_deps_count(combined_deps),
-                self.problem.action_count
+                self.action_count
             );
             return;
         }
@@ -528,73 +476,44 @@ impl<'a> Computation<'a> {
             found_shape_mask,
             next: -1,
         });
-        if self.problem.multimatch && self.found_points.contains(point) {
+        if self.multimatch && self.found_points.contains(point) {
             self.check_multimatch_solution_found();
         }
-        for i in 0..index {
-            let maybe_actions = Action::check_action_two_points(self, i, index);
-            for maybe_action in maybe_actions {
-                match maybe_action {
-                    Some(action) => self.queue.push(action),
-                    None => (),
-                }
-            }
-        }
+        self.queue_checked_actions(0..index, |comp, i, actions| {
+            let maybe_actions = Action::check_action_two_points(comp, i, index);
+            actions.extend(maybe_actions.into_iter().flatten());
+        });
         if self.problem.has_point_and_line_actions() {
-            for i in 0..self.shape_origins.len_i32() {
-                let shape1 = self.shape_origins[i as usize].get_shape();
-                if shape1.get_direction().is_none() {
-                    continue;
+            self.queue_checked_actions(0..self.shape_origins.len_i32(), |comp, i, actions| {
+                let shape1 = comp.shape_origins[i as usize].get_shape();
+                if shape1.get_direction().is_some() {
+                    let maybe_actions = Action::check_action_point_and_line(comp, index, i);
+                    actions.extend(maybe_actions.into_iter().flatten());
                 }
-                let maybe_actions = Action::check_action_point_and_line(self, index, i);
-                for maybe_action in maybe_actions {
-                    match maybe_action {
-                        Some(action) => self.queue.push(action),
-                        None => (),
-                    }
-                }
-            }
+            });
         }
         if self.problem.has_three_point_actions() {
-            for i1 in 0..index {
+            self.queue_checked_actions(0..index, |comp, i1, actions| {
                 for i2 in (i1 + 1)..index {
-                    let maybe_actions = Action::check_action_three_points(self, i1, i2, index);
-                    for maybe_action in maybe_actions {
-                        match maybe_action {
-                            Some(action) => self.queue.push(action),
-                            None => (),
-                        }
-                    }
+                    let maybe_actions = Action::check_action_three_points(comp, i1, i2, index);
+                    actions.extend(maybe_actions.into_iter().flatten());
                 }
-            }
+            });
         }
         if self.problem.has_two_point_and_line_actions() {
-            for i1 in 0..index {
-                for i2 in 0..self.shape_origins.len_i32() {
-                    let shape1 = self.shape_origins[i2 as usize].get_shape();
+            self.queue_checked_actions(0..index, |comp, i1, actions| {
+                for i2 in 0..comp.shape_origins.len_i32() {
+                    let shape1 = comp.shape_origins[i2 as usize].get_shape();
                     if shape1.get_direction().is_none() {
                         continue;
                     }
                     let maybe_actions =
-                        Action::check_action_two_point_and_line(self, i1, index, i2);
-                    for maybe_action in maybe_actions {
-                        match maybe_action {
-                            Some(action) => self.queue.push(action),
-                            None => (),
-                        }
-                    }
+                        Action::check_action_two_point_and_line(comp, i1, index, i2);
+                    actions.extend(maybe_actions.into_iter().flatten());
                 }
-            }
-        }
-
-        if self.queue.len() > 100000000 {
-            println!("Reducing queue size");
-            let mut new_queue = BinaryHeap::new();
-            for _i in 1..50000 {
-                new_queue.push(self.queue.pop().unwrap());
-            }
-            self.queue = new_queue;
+            });
         }
+        self.queue_generic_actions(index, true);
     }
 
     fn update_shape_seen_before(&mut self, shape: Shape, index: i32, deps: u64) -> bool {
@@ -631,7 +550,7 @@ impl<'a> Computation<'a> {
         let shape = element_link.get_shape();
         let previous_index = self.shapes.get(shape);
         let seen_before = previous_index.is_some();
-        if seen_before && !self.problem.multimatch {
+        if seen_before && !self.multimatch {
             return;
         }
         if !seen_before && self.shapes_to_find.contains(shape) {
@@ -642,7 +561,16 @@ impl<'a> Computation<'a> {
             }
         }
         let current_index = self.shape_origins.len_i32();
-        let index = previous_index.unwrap_or(current_index);
+        // With weighted costs or action limits, another construction of a shape that counts
+        // differently gets its own deps index, so that the costs and usage of deps stay exact
+        let index = match (previous_index, &element_link) {
+            (Some(previous), ElementLink::Action(action))
+                if !self.can_share_deps_index(previous as usize, action.action_type) =>
+            {
+                current_index
+            }
+            _ => previous_index.unwrap_or(current_index),
+        };
         let (combined_deps_with_index, found_shape_mask) = match &element_link {
             ElementLink::GivenElement { .. } => (0, 0),
             ElementLink::Action(action) => action.process(self, index),
@@ -667,11 +595,14 @@ impl<'a> Computation<'a> {
         }
         for i in 0..current_index {
             let shape_origin = &self.shape_origins[i as usize];
+            if shape_origin.is_placed_point() {
+                continue;
+            }
             let deps_count =
                 self.get_combined_deps_count(combined_deps_with_index, shape_origin.deps);
             let combined_mask = shape_origin.found_shape_mask | found_shape_mask;
             let reserved = self.shape_to_find_mask_by_shape.len_u32() - combined_mask.count_ones();
-            if deps_count + reserved <= self.problem.action_count {
+            if deps_count + reserved <= self.action_count {
                 let maybe_points = self.shape_origins[i as usize]
                     .get_shape()
                     .find_intersection_points(&shape);
@@ -683,31 +614,144 @@ impl<'a> Computation<'a> {
                 }
             }
         }
+        let point_count = self.point_origins.len_i32();
         if shape.get_direction().is_some() && self.problem.has_point_and_line_actions() {
-            for i in 0..self.point_origins.len_i32() {
-                let maybe_actions = Action::check_action_point_and_line(self, i, current_index);
-                for maybe_action in maybe_actions {
-                    match maybe_action {
-                        Some(action) => self.queue.push(action),
-                        None => (),
-                    }
-                }
-            }
+            self.queue_checked_actions(0..point_count, |comp, i, actions| {
+                let maybe_actions = Action::check_action_point_and_line(comp, i, current_index);
+                actions.extend(maybe_actions.into_iter().flatten());
+            });
         }
         if shape.get_direction().is_some() && self.problem.has_two_point_and_line_actions() {
-            for i1 in 0..self.point_origins.len_i32() {
-                for i2 in (i1 + 1)..self.point_origins.len_i32() {
+            self.queue_checked_actions(0..point_count, |comp, i1, actions| {
+                for i2 in (i1 + 1)..point_count {
                     let maybe_actions =
-                        Action::check_action_two_point_and_line(self, i1, i2, current_index);
-                    for maybe_action in maybe_actions {
-                        match maybe_action {
-                            Some(action) => self.queue.push(action),
-                            None => (),
-                        }
-                    }
+                        Action::check_action_two_point_and_line(comp, i1, i2, current_index);
+                    actions.extend(maybe_actions.into_iter().flatten());
                 }
+            });
+        }
+        let maybe_actions = Action::check_action_place_point(self, current_index, 0);
+        self.queue.extend(maybe_actions.into_iter().flatten());
+        self.queue_generic_actions(current_index, false);
+    }
+
+    // Queues the actions checked for the indices, in parallel when there are enough of them;
+    // they are queued in the order of the indices, so that the search is the same with any
+    // number of threads
+    fn queue_checked_actions(
+        &mut self,
+        indices: Range<i32>,
+        check: impl Fn(&Self, i32, &mut Vec<Action>) + Sync,
+    ) {
+        if indices.len() < PARALLEL_MIN_LEN {
+            let mut actions = vec![];
+            for i in indices {
+                check(self, i, &mut actions);
+            }
+            self.queue.extend(actions);
+            return;
+        }
+        let chunks: Vec<Vec<Action>> = indices
+            .into_par_iter()
+            .with_min_len(PARALLEL_MIN_LEN / 4)
+            .fold(Vec::new, |mut actions, i| {
+                check(self, i, &mut actions);
+                actions
+            })
+            .collect();
+        self.queue.extend(chunks.into_iter().flatten());
+    }
+
+    // Queues the tools and lemmas using the new point (or shape); the other inputs of the same
+    // kind have lower indices, so that each combination of inputs is tried once
+    fn queue_generic_actions(&mut self, index: i32, is_point: bool) {
+        for action_type in self.problem.action_types.iter() {
+            if let Some(kinds) = self.problem.get_input_kinds(*action_type) {
+                let new = (index, is_point);
+                self.queue_generic_inputs(*action_type, kinds, new, &mut [-1; 3], (0, false));
+            }
+        }
+    }
+
+    // Fills the inputs from the k-th on, then checks the action if the new item is used
+    fn queue_generic_inputs(
+        &mut self,
+        action_type: ActionType,
+        (kinds, symmetric): (&[InputKind], bool),
+        (index, is_point): (i32, bool),
+        inputs: &mut [i32; 3],
+        (k, uses_new): (usize, bool),
+    ) {
+        if k == kinds.len() {
+            if uses_new {
+                let maybe_actions = Action::check_action_generic(self, action_type, *inputs);
+                self.queue.extend(maybe_actions.into_iter().flatten());
+            }
+            return;
+        }
+        let point_count = kinds
+            .iter()
+            .filter(|kind| **kind == InputKind::Point)
+            .count();
+        let input_is_point = k < point_count;
+        let same_kind = if input_is_point { 0..k } else { point_count..k };
+        let candidates = match (input_is_point == is_point, input_is_point) {
+            (true, _) => 0..index + 1,
+            (false, true) => 0..self.point_origins.len_i32(),
+            (false, false) => 0..self.shape_origins.len_i32(),
+        };
+        // Symmetric inputs are tried in increasing order only
+        let min = match k.checked_sub(1) {
+            Some(previous) if symmetric && kinds[previous] == kinds[k] => inputs[previous] + 1,
+            _ => 0,
+        };
+        for i in candidates.start.max(min)..candidates.end {
+            let is_new = input_is_point == is_point && i == index;
+            if (is_new && uses_new) || inputs[same_kind.clone()].contains(&i) {
+                continue;
+            }
+            let fits = if input_is_point {
+                let point = self.point_origins[i as usize].point;
+                !inputs[same_kind.clone()]
+                    .iter()
+                    .any(|j| self.point_origins[*j as usize].point == point)
+            } else {
+                InputKind::of(&self.shape_origins[i as usize].get_shape()) == kinds[k]
+            };
+            if fits {
+                inputs[k] = i;
+                self.queue_generic_inputs(
+                    action_type,
+                    (kinds, symmetric),
+                    (index, is_point),
+                    inputs,
+                    (k + 1, uses_new || is_new),
+                );
             }
         }
+        inputs[k] = -1;
+    }
+
+    // The placed point is registered as the intersection of its shape origin and the shape it
+    // lies on (GIVEN for free points and points made by macros)
+    fn register_placed_point(&mut self, action: Action) {
+        let point = action.shape.as_point().unwrap();
+        let shape_index = if matches!(action.action_type, ActionType::PointActionType(_)) {
+            let maybe_actions = action.check_action_place_next_point(self);
+            self.queue.extend(maybe_actions.into_iter().flatten());
+            action.get_shape_indices().first().copied().unwrap_or(GIVEN)
+        } else {
+            GIVEN
+        };
+        let index = self.shape_origins.len_i32();
+        let (deps, found_shape_mask) = action.process(self, index);
+        self.shape_origins.push(ShapeOrigin {
+            element_link: ElementLink::Action(action),
+            deps,
+            found_shape_mask,
+            next: -1,
+        });
+        self.register_point(point, [index, shape_index]);
     }
 
     fn register_given_element(&mut self, element: &'a Element) {
@@ -737,16 +781,60 @@ impl<'a> Computation<'a> {
                 }
             };
         }
+        let maybe_actions = Action::check_action_place_point(self, GIVEN, 0);
+        self.queue.extend(maybe_actions.into_iter().flatten());
+    }
+
+    // Returns the solutions found (all of them with find_all_solutions, the ones improving in
+    // E-moves with minimize_e_moves, otherwise the first one);
+    // random walk solutions are only drawn
+    pub fn solve(&mut self, options: &SolveOptions) -> Vec<Solution> {
+        match options.threads {
+            Some(threads) => match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+                Ok(pool) => pool.install(|| self.solve_in_current_pool(options)),
+                Err(e) => {
+                    println!("Can't start {} threads: {}", threads, e);
+                    vec![]
+                }
+            },
+            None => self.solve_in_current_pool(options),
+        }
     }
 
-    pub fn solve(&'a mut self) {
-        let time = SystemTime::now();
-        let mut rw_queue = Vec::new();
-        for i in 0..1000000 {
+    fn solve_in_current_pool(&mut self, options: &SolveOptions) -> Vec<Solution> {
+        let progress = self.resumed.take().unwrap_or_default();
+        let time = SystemTime::now() - progress.elapsed;
+        let mut rw_queue = progress.rw_queue;
+        let mut solutions: Vec<Solution> = progress
+            .solutions
+            .iter()
+            .map(|(deps, elapsed)| self.build_solution(*deps, *elapsed))
+            .collect();
+        let mut checkpoint_time = SystemTime::now();
+        for i in progress.iteration.. {
+            if let Some(reason) = self.get_exceeded_limit(&options.limits, i, time) {
+                self.print_limit_report(&reason, i, time);
+                // Saved to be continued (e.g. with higher limits) with --resume
+                if let Some(filename) = &options.checkpoint {
+                    let progress = self.get_progress(i, time, &rw_queue, &solutions);
+                    match self.save_checkpoint(filename, &progress) {
+                        Ok(()) => println!("Checkpoint saved to {}", filename),
+                        Err(message) => println!("{}", message),
+                    }
+                }
+                self.stop_reason = Some(reason);
+                break;
+            }
             if self.queue.is_empty() {
                 // self.print_state();
-                self.draw_state("final.svg".to_string(), 5.0, HashSet::new());
+                if let Some(filename) = &options.final_svg {
+                    self.draw_state(filename.clone(), &options.draw_options, HashSet::new());
+                }
+                if let Some(filename) = &options.state_json {
+                    self.save_state_json(filename.clone());
+                }
                 println!("All actions explored");
+                self.exhausted = rw_queue.is_empty();
                 break;
             }
             let mut action = self.queue.pop().unwrap();
@@ -769,17 +857,49 @@ impl<'a> Computation<'a> {
                 self.queue.push(action);
                 continue;
             }
-            self.register_shape(ElementLink::Action(action));
-            if self.solution_deps.is_some() {
+            if action.shape.as_point().is_some() {
+                self.register_placed_point(action);
+            } else {
+                self.register_shape(ElementLink::Action(action));
+            }
+            if let Some(deps) = self.solution_deps {
+                let solution = self.build_solution(deps, time.elapsed().unwrap());
+                let improves = solutions
+                    .last()
+                    .is_none_or(|best: &Solution| solution.e_moves < best.e_moves);
+                if self.problem.minimize_e_moves && !improves {
+                    self.solution_deps = None;
+                    continue;
+                }
                 println!("=== Printing solution! ===");
                 self.print_solution();
-                self.draw_solution("solution.svg".to_string(), 5.0);
+                if let Some(filename) = &options.solution_svg {
+                    self.draw_solution(filename.clone(), &options.draw_options);
+                }
+                if let Some(filename) = &options.solution_shapes {
+                    self.save_solution_shapes(filename.clone());
+                }
                 println!(
                     "Solution found in {} seconds",
                     time.elapsed().unwrap().as_secs()
                 );
-                if !self.problem.find_all_solutions {
-                    return;
+                solutions.push(solution);
+                if let Some(filename) = &options.solutions_json {
+                    Self::save_solutions_json(&solutions, filename.clone());
+                }
+                if let Some(filename) = &options.geogebra_script {
+                    let solution = solutions.last().unwrap();
+                    solution.save_geogebra_script(self.problem, filename.clone());
+                }
+                if let Some(filename) = &options.replay {
+                    let solution = solutions.last().unwrap();
+                    solution.save_replay(self.problem, filename.clone(), &options.draw_options);
+                }
+                if let Some(filename) = &options.state_json {
+                    self.save_state_json(filename.clone());
+                }
+                if !self.problem.find_all_solutions && !self.problem.minimize_e_moves {
+                    return solutions;
                 }
                 self.solution_deps = None;
             }
@@ -788,6 +908,16 @@ impl<'a> Computation<'a> {
             //     self.draw_state(format!("image{}.svg", i), 5.0, HashSet::new());
             // }
             if i % 10 == 0 {
+                if let Some(filename) = &options.checkpoint {
+                    if checkpoint_time.elapsed().unwrap() >= options.checkpoint_interval {
+                        let progress = self.get_progress(i + 1, time, &rw_queue, &solutions);
+                        match self.save_checkpoint(filename, &progress) {
+                            Ok(()) => println!("Checkpoint saved to {}", filename),
+                            Err(message) => println!("{}", message),
+                        }
+                        checkpoint_time = SystemTime::now();
+                    }
+                }
                 if false {
                     println!(
                         "Loop {} ({}/{})",
@@ -811,7 +941,9 @@ impl<'a> Computation<'a> {
                 );
             }
         }
-        if !rw_queue.is_empty() {
+        // The random walks only run if no limit stopped the search, and until its time limit
+        // (they don't take more memory as they go)
+        if !rw_queue.is_empty() && self.stop_reason.is_none() {
             println!("Found {} elements in the random walk queue", rw_queue.len());
             let random_walk_parent = self.create_random_walk_parent();
             let random_walks = self.prepare_random_walks(&random_walk_parent, rw_queue);
@@ -820,22 +952,198 @@ impl<'a> Computation<'a> {
                 time.elapsed().unwrap().as_secs(),
             );
             let limit = RANDOM_WALK_LIMIT / random_walks.len_u32();
+            let deadline = options.limits.get_deadline(time);
             let rw_results: Vec<RandomWalkSolution> = random_walks
                 .par_iter()
                 // .iter()
-                .filter_map(|rw| rw.run_iterations(limit))
+                .filter_map(|rw| rw.run_iterations(limit, deadline))
                 .collect();
+            if rw_results.is_empty() && deadline.is_some_and(|d| SystemTime::now() >= d) {
+                let reason = limits::get_time_limit_reason(options.limits.max_time.unwrap());
+                println!("--- Random walks stopped by the {} ---", reason);
+                self.stop_reason = Some(reason);
+            }
             println!(
                 "Ended random walks, time: {}",
                 time.elapsed().unwrap().as_secs(),
             );
-            for i in 0..rw_results.len_u32() {
-                Self::draw_shapes(
-                    &rw_results[i as usize].shapes,
-                    format!("rw_solution_{}.svg", i),
-                    5.0,
-                );
+            if let Some(prefix) = &options.rw_solution_prefix {
+                for i in 0..rw_results.len_u32() {
+                    Self::draw_shapes(
+                        &rw_results[i as usize].shapes,
+                        format!("{}{}.{}", prefix, i, options.rw_solution_format.extension()),
+                        &options.draw_options,
+                    );
+                }
             }
         }
+        solutions
+    }
+
+    // What solve() saves in checkpoints besides the Computation
+    fn get_progress(
+        &self,
+        iteration: u32,
+        time: SystemTime,
+        rw_queue: &[Action],
+        solutions: &[Solution],
+    ) -> SolveProgress {
+        SolveProgress {
+            iteration,
+            elapsed: time.elapsed().unwrap(),
+            rw_queue: rw_queue.to_vec(),
+            solutions: solutions
+                .iter()
+                .map(|solution| (solution.deps, solution.elapsed))
+                .collect(),
+        }
+    }
+
+    // Solves with action_count = 1, 2,... up to the problem's one and returns the solutions of
+    // the first count having some: if the counts below are explored exhaustively (with
+    // multimatch, forced so that no construction is dropped), these solutions are minimal in
+    // actions
+    pub fn solve_iteratively(
+        problem: &'a ProblemDefinition,
+        options: &SolveOptions,
+    ) -> Vec<Solution> {
+        // The first count not explored exhaustively, and why
+        let mut unexplored: Option<(u32, String)> = None;
+        for action_count in 1..=problem.action_count {
+            println!("=== Solving with {} actions ===", action_count);
+            let mut computation = Computation::new(problem);
+            computation.action_count = action_count;
+            computation.multimatch = true;
+            computation.initialize_queue();
+            let solutions = computation.solve(options);
+            if !solutions.is_empty() {
+                match &unexplored {
+                    None => println!("No solution uses fewer than {} actions", action_count),
+                    Some((count, reason)) => println!(
+                        "Solutions with fewer than {} actions may exist: the search with {} \
+                         actions stopped {}",
+                        action_count, count, reason
+                    ),
+                }
+                return solutions;
+            }
+            if computation.exhausted {
+                println!("No solution with {} actions", action_count);
+                continue;
+            }
+            let reason = match &computation.stop_reason {
+                Some(reason) => format!("before the {}", reason),
+                None => "before all constructions were explored".to_string(),
+            };
+            println!("No solution found with {} actions {}", action_count, reason);
+            unexplored.get_or_insert((action_count, reason));
+        }
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computation::test_helpers::run_search;
+    use crate::problems::ActionGroup;
+
+    #[test]
+    fn test_minimize_e_moves() {
+        let mut problem =
+            ProblemDefinition::get_problem_by_id("1.3-midpoint-with-midperp").unwrap();
+        problem.action_count = 4;
+        problem.multimatch = true;
+        problem.minimize_e_moves = true;
+        let solutions = crate::solve(&problem, &SolveOptions::without_files());
+        assert!(solutions
+            .windows(2)
+            .all(|pair| pair[1].e_moves < pair[0].e_moves));
+        assert_eq!(solutions.last().unwrap().e_moves, 4);
+    }
+
+    #[test]
+    fn test_cost_budget() {
+        let mut problem =
+            ProblemDefinition::get_problem_by_id("2.8-tangent-to-circle-at-point-with-perp")
+                .unwrap();
+        problem.set_e_move_costs();
+        problem.cost_budget = Some(4);
+        let options = SolveOptions::without_files();
+        let solutions = crate::solve(&problem, &options);
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].cost, 4);
+
+        // Line (1) + Perp (3) doesn't fit
+        problem.cost_budget = Some(3);
+        assert!(crate::solve(&problem, &options).is_empty());
+    }
+
+    #[test]
+    fn test_action_limits() {
+        let mut problem =
+            ProblemDefinition::get_problem_by_id("1.3-midpoint-with-midperp").unwrap();
+        problem.action_count = 4;
+        problem.multimatch = true;
+        let mid_perp = ActionType::TwoPointActionType(TwoPointActionType::MidPerp);
+        problem.action_limits = vec![(ActionGroup::Action(mid_perp), 0)].into();
+        let options = SolveOptions::without_files();
+        let solutions = crate::solve(&problem, &options);
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].step_count, 4);
+        assert!(solutions[0]
+            .steps
+            .iter()
+            .all(|step| step.action_type != mid_perp));
+
+        // Two two-point actions are only enough with MidPerp
+        problem.action_limits = vec![(ActionGroup::TwoPointActions, 2)].into();
+        let solutions = crate::solve(&problem, &options);
+        assert_eq!(solutions[0].step_count, 2);
+        problem.action_limits = vec![(ActionGroup::TwoPointActions, 1)].into();
+        assert!(crate::solve(&problem, &options).is_empty());
+    }
+
+    #[test]
+    fn test_iterative_deepening() {
+        // With an action to spare, the solution found uses the fewest actions (multimatch is
+        // forced on)
+        let mut problem = ProblemDefinition::get_problem_by_id("7.5-herons").unwrap();
+        problem.action_count = 5;
+        problem.multimatch = false;
+        problem.iterative_deepening = true;
+        let options = SolveOptions::without_files();
+        let solutions = crate::solve(&problem, &options);
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].step_count, 4);
+
+        problem.action_count = 3;
+        assert!(crate::solve(&problem, &options).is_empty());
+    }
+
+    #[test]
+    fn test_parallel_search() {
+        // The state after the same iterations doesn't depend on the number of threads
+        let problem = ProblemDefinition::get_problem_by_id("6.10-symmetry-of-four-lines").unwrap();
+        let run = |threads| {
+            let options = SolveOptions {
+                limits: SearchLimits {
+                    max_iterations: 60,
+                    ..SearchLimits::default()
+                },
+                threads: Some(threads),
+                ..SolveOptions::without_files()
+            };
+            let (comp, _) = run_search(&problem, &options);
+            let points: Vec<Point> = comp.point_origins.iter().map(|o| o.point).collect();
+            (
+                points,
+                comp.shape_origins.len(),
+                comp.queue.into_sorted_vec(),
+            )
+        };
+        let (points, shape_count, queue) = run(1);
+        assert!(points.len() > PARALLEL_MIN_LEN);
+        assert_eq!(run(3), (points, shape_count, queue));
     }
 }
@@ -1,9 +1,19 @@
-use std::{cmp::Ordering, fmt::Display};
+use std::{cmp::Ordering, f64::consts::PI, fmt::Display};
 
-use crate::{element::BisectorCVV, fint::FInt, problems::TwoPointAndLineActionType};
+use crate::{
+    element::BisectorCVV,
+    fint::FInt,
+    problems::{PointActionType, TwoPointAndLineActionType},
+};
 
 use super::*;
 
+// Generic positions of placed points: the k-th point placed on a shape (or in the plane) uses
+// the k-th entry (see Action::create_placed_point())
+const PLACEMENTS: [f64; 8] = [
+    0.2347234, -0.4823445, 0.6180339, -0.3719137, 0.1414213, -0.5772156, 0.7320508, -0.2718281,
+];
+
 pub enum ElementLink<'a> {
     GivenElement { element: &'a Element, shape: Shape },
     Action(Action),
@@ -30,15 +40,20 @@ impl<'a> ElementLink<'a> {
     }
 }
 
+// For point actions, point_index_2 is the ordinal of the point on the shape (or in the plane)
+// and extra_index the shape (GIVEN for free points). Tools and lemmas have their point inputs
+// followed by their shape inputs in point_index_1, point_index_2 and extra_index.
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct Action {
     pub priority: i32,
     pub deps_count: u32,
+    // Weighted cost of the inputs (see ProblemDefinition::cost_budget), 0 without a budget
+    pub deps_cost: u32,
     pub shape: Shape,
     pub action_type: ActionType,
-    point_index_1: i32,
-    point_index_2: i32,
-    extra_index: i32,
+    pub(super) point_index_1: i32,
+    pub(super) point_index_2: i32,
+    pub(super) extra_index: i32,
 }
 impl PartialOrd for Action {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
@@ -95,7 +110,7 @@ impl Action {
         }
     }
 
-    fn create_point_and_line_element(
+    pub fn create_point_and_line_element(
         point: &Point,
         line: &Shape,
         action_type: PointAndLineActionType,
@@ -113,7 +128,7 @@ impl Action {
         }
     }
 
-    fn create_three_point_element(
+    pub fn create_three_point_element(
         point1: &Point,
         point2: &Point,
         point3: &Point,
@@ -172,7 +187,7 @@ impl Action {
         }
     }
 
-    fn create_two_point_and_line_element(
+    pub fn create_two_point_and_line_element(
         point1: &Point,
         point2: &Point,
         line: &Shape,
@@ -247,6 +262,69 @@ impl Action {
         }
     }
 
+    // A point on the shape (None for a free point) at the placement, whose values are in
+    // [-1, 1]: the angle / PI on circles, the offset along lines from the point closest to the
+    // origin, and the position between the ends on rays (up to 2 away) and segments. Free
+    // points are at (placement[0], placement[1]).
+    pub fn create_placed_point(shape: Option<&Shape>, placement: [f64; 2]) -> Point {
+        let [p, q] = placement;
+        match shape {
+            None => Point(FInt::new(p), FInt::new(q)),
+            Some(Shape::Point(point)) => *point,
+            Some(Shape::Circle(circle)) => {
+                let r = circle.r2.sqrt();
+                let (sin, cos) = (p * PI).sin_cos();
+                Point(
+                    circle.c.0 + r * FInt::new(cos),
+                    circle.c.1 + r * FInt::new(sin),
+                )
+            }
+            Some(Shape::Line(line)) => {
+                let norm2 = line.nx.sqr() + line.ny.sqr();
+                let k = FInt::new(p) / norm2.sqrt();
+                Point(
+                    (line.nx * line.d) / norm2 - line.ny * k,
+                    (line.ny * line.d) / norm2 + line.nx * k,
+                )
+            }
+            Some(Shape::Ray(ray)) => {
+                let k = FInt::new(1.0 + p) / (ray.v.0.sqr() + ray.v.1.sqr()).sqrt();
+                Point(ray.a.0 + ray.v.0 * k, ray.a.1 + ray.v.1 * k)
+            }
+            Some(Shape::Segment(segment)) => {
+                let k = FInt::new(0.5 + 0.5 * p);
+                Point(
+                    segment.a.0 + (segment.b.0 - segment.a.0) * k,
+                    segment.a.1 + (segment.b.1 - segment.a.1) * k,
+                )
+            }
+        }
+    }
+
+    pub fn get_placement(ordinal: u32) -> [f64; 2] {
+        let count = PLACEMENTS.len();
+        [
+            PLACEMENTS[ordinal as usize % count],
+            PLACEMENTS[(ordinal as usize + 3) % count],
+        ]
+    }
+
+    // The placement of the point for point actions
+    pub fn get_point_placement(&self) -> Option<[f64; 2]> {
+        match self.action_type {
+            ActionType::PointActionType(_) => Some(Self::get_placement(self.point_index_2 as u32)),
+            _ => None,
+        }
+    }
+
+    // The action placing the next point on the same shape (or in the plane)
+    pub fn check_action_place_next_point(
+        &self,
+        comp: &Computation,
+    ) -> [Option<Self>; PointActionType::Last as usize] {
+        Self::check_action_place_point(comp, self.extra_index, self.point_index_2 as u32 + 1)
+    }
+
     pub fn process(&self, comp: &mut Computation, index: i32) -> (u64, u32) {
         let combined_deps_with_index;
         let mut found_shape_mask;
@@ -298,6 +376,37 @@ impl Action {
                     | point_origin_2.found_shape_mask
                     | shape_origin.found_shape_mask;
             }
+            ActionType::PointActionType(_) => {
+                let (deps, mask) = if self.extra_index >= 0 {
+                    let shape_origin = &comp.shape_origins[self.extra_index as usize];
+                    (shape_origin.deps, shape_origin.found_shape_mask)
+                } else {
+                    (0, 0)
+                };
+                combined_deps_with_index = comp.combine_deps(deps, 0, Some(index));
+                found_shape_mask = mask;
+            }
+            ActionType::ToolActionType(_) | ActionType::LemmaActionType(_) => {
+                let inputs: Vec<(u64, u32)> = self
+                    .get_point_indices()
+                    .iter()
+                    .map(|i| &comp.point_origins[*i as usize])
+                    .map(|origin| (origin.deps, origin.found_shape_mask))
+                    .chain(
+                        self.get_shape_indices()
+                            .iter()
+                            .map(|i| &comp.shape_origins[*i as usize])
+                            .map(|origin| (origin.deps, origin.found_shape_mask)),
+                    )
+                    .collect();
+                let deps2 = inputs.get(1).map_or(0, |input| input.0);
+                let mut deps = comp.combine_deps(inputs[0].0, deps2, Some(index));
+                for input in inputs.iter().skip(2) {
+                    deps = comp.combine_deps(deps, input.0, None);
+                }
+                combined_deps_with_index = deps;
+                found_shape_mask = inputs.iter().fold(0, |mask, input| mask | input.1);
+            }
         }
         match comp.shape_to_find_mask_by_shape.get(self.shape) {
             None => (),
@@ -316,15 +425,23 @@ impl Action {
         let point_origin_1 = &comp.point_origins[i1 as usize];
         let point_origin_2 = &comp.point_origins[i2 as usize];
         let deps_count = comp.get_combined_deps_count(point_origin_1.deps, point_origin_2.deps);
+        let deps_cost = comp.get_deps_union_cost(&[point_origin_1.deps, point_origin_2.deps]);
+        let usage = comp.get_deps_union_usage(&[point_origin_1.deps, point_origin_2.deps]);
         let found_shape_count =
             (point_origin_1.found_shape_mask | point_origin_2.found_shape_mask).count_ones();
         let reserved = comp.shape_to_find_mask_by_shape.len_u32() - found_shape_count;
         const NONE: Option<Action> = None;
         let mut results = [NONE; TwoPointActionType::Last as usize];
-        if deps_count + reserved > comp.problem.action_count {
+        if deps_count + reserved > comp.action_count || !comp.fits_cost_budget(deps_cost, reserved)
+        {
             return results;
         }
-        for action_type in comp.problem.action_types {
+        for action_type in comp
+            .problem
+            .action_types
+            .iter()
+            .filter(|action_type| comp.problem.fits_action_limits(&usage, **action_type))
+        {
             let maybe_action = match action_type {
                 ActionType::TwoPointActionType(two_point_action_type) => {
                     let element = Self::create_two_point_element(
@@ -333,12 +450,13 @@ impl Action {
                         *two_point_action_type,
                     );
                     let new_shape = element.get_shape().unwrap();
-                    if !comp.problem.multimatch && comp.shapes.contains_key(new_shape) {
+                    if !comp.multimatch && comp.shapes.contains_key(new_shape) {
                         None
                     } else {
                         Some(Action {
                             priority: 0,
                             deps_count,
+                            deps_cost,
                             shape: new_shape,
                             action_type: *action_type,
                             point_index_1: i1,
@@ -362,15 +480,23 @@ impl Action {
         let point_origin = &comp.point_origins[i_point as usize];
         let line_origin = &comp.shape_origins[i_line as usize];
         let deps_count = comp.get_combined_deps_count(point_origin.deps, line_origin.deps);
+        let deps_cost = comp.get_deps_union_cost(&[point_origin.deps, line_origin.deps]);
+        let usage = comp.get_deps_union_usage(&[point_origin.deps, line_origin.deps]);
         let found_shape_count =
             (point_origin.found_shape_mask | line_origin.found_shape_mask).count_ones();
         let reserved = comp.shape_to_find_mask_by_shape.len_u32() - found_shape_count;
         const NONE: Option<Action> = None;
         let mut results = [NONE; PointAndLineActionType::Last as usize];
-        if deps_count + reserved > comp.problem.action_count {
+        if deps_count + reserved > comp.action_count || !comp.fits_cost_budget(deps_cost, reserved)
+        {
             return results;
         }
-        for action_type in comp.problem.action_types {
+        for action_type in comp
+            .problem
+            .action_types
+            .iter()
+            .filter(|action_type| comp.problem.fits_action_limits(&usage, **action_type))
+        {
             let maybe_action = match action_type {
                 ActionType::PointAndLineActionType(point_and_line_action_type) => {
                     let element = Self::create_point_and_line_element(
@@ -379,12 +505,13 @@ impl Action {
                         *point_and_line_action_type,
                     );
                     let new_shape = element.get_shape().unwrap();
-                    if !comp.problem.multimatch && comp.shapes.contains_key(new_shape) {
+                    if !comp.multimatch && comp.shapes.contains_key(new_shape) {
                         None
                     } else {
                         Some(Action {
                             priority: 0,
                             deps_count,
+                            deps_cost,
                             shape: new_shape,
                             action_type: *action_type,
                             point_index_1: i_point,
@@ -414,6 +541,16 @@ impl Action {
             point_origin_2.deps,
             point_origin_3.deps,
         );
+        let deps_cost = comp.get_deps_union_cost(&[
+            point_origin_1.deps,
+            point_origin_2.deps,
+            point_origin_3.deps,
+        ]);
+        let usage = comp.get_deps_union_usage(&[
+            point_origin_1.deps,
+            point_origin_2.deps,
+            point_origin_3.deps,
+        ]);
         let found_shape_count = (point_origin_1.found_shape_mask
             | point_origin_2.found_shape_mask
             | point_origin_3.found_shape_mask)
@@ -421,10 +558,16 @@ impl Action {
         let reserved = comp.shape_to_find_mask_by_shape.len_u32() - found_shape_count;
         const NONE: Option<Action> = None;
         let mut results = [NONE; ThreePointActionType::Last as usize];
-        if deps_count + reserved > comp.problem.action_count {
+        if deps_count + reserved > comp.action_count || !comp.fits_cost_budget(deps_cost, reserved)
+        {
             return results;
         }
-        for action_type in comp.problem.action_types {
+        for action_type in comp
+            .problem
+            .action_types
+            .iter()
+            .filter(|action_type| comp.problem.fits_action_limits(&usage, **action_type))
+        {
             let maybe_action = match action_type {
                 ActionType::ThreePointActionType(three_point_action_type) => {
                     match Self::create_three_point_element(
@@ -436,12 +579,13 @@ impl Action {
                         None => None,
                         Some(element) => {
                             let new_shape = element.get_shape().unwrap();
-                            if !comp.problem.multimatch && comp.shapes.contains_key(new_shape) {
+                            if !comp.multimatch && comp.shapes.contains_key(new_shape) {
                                 None
                             } else {
                                 Some(Action {
                                     priority: 0,
                                     deps_count,
+                                    deps_cost,
                                     shape: new_shape,
                                     action_type: *action_type,
                                     point_index_1: i1,
@@ -473,6 +617,13 @@ impl Action {
             point_origin_2.deps,
             line_origin.deps,
         );
+        let deps_cost =
+            comp.get_deps_union_cost(&[point_origin_1.deps, point_origin_2.deps, line_origin.deps]);
+        let usage = comp.get_deps_union_usage(&[
+            point_origin_1.deps,
+            point_origin_2.deps,
+            line_origin.deps,
+        ]);
         let found_shape_count = (point_origin_1.found_shape_mask
             | point_origin_2.found_shape_mask
             | line_origin.found_shape_mask)
@@ -480,10 +631,16 @@ impl Action {
         let reserved = comp.shape_to_find_mask_by_shape.len_u32() - found_shape_count;
         const NONE: Option<Action> = None;
         let mut results = [NONE; TwoPointAndLineActionType::Last as usize];
-        if deps_count + reserved > comp.problem.action_count {
+        if deps_count + reserved > comp.action_count || !comp.fits_cost_budget(deps_cost, reserved)
+        {
             return results;
         }
-        for action_type in comp.problem.action_types {
+        for action_type in comp
+            .problem
+            .action_types
+            .iter()
+            .filter(|action_type| comp.problem.fits_action_limits(&usage, **action_type))
+        {
             let maybe_action = match action_type {
                 ActionType::TwoPointAndLineActionType(two_point_and_line_action_type) => {
                     match Self::create_two_point_and_line_element(
@@ -495,12 +652,13 @@ impl Action {
                         None => None,
                         Some(element) => {
                             let new_shape = element.get_shape().unwrap();
-                            if !comp.problem.multimatch && comp.shapes.contains_key(new_shape) {
+                            if !comp.multimatch && comp.shapes.contains_key(new_shape) {
                                 None
                             } else {
                                 Some(Action {
                                     priority: 0,
                                     deps_count,
+                                    deps_cost,
                                     shape: new_shape,
                                     action_type: *action_type,
                                     point_index_1: i1,
@@ -518,6 +676,100 @@ impl Action {
         results
     }
 
+    // The action making the shape of a tool or lemma, unless it's known already (without
+    // multimatch)
+    fn create_macro_action(
+        comp: &Computation,
+        shape: Option<Shape>,
+        action_type: ActionType,
+        (deps_count, deps_cost): (u32, u32),
+        [point_index_1, point_index_2, extra_index]: [i32; 3],
+    ) -> Option<Self> {
+        let shape = shape?;
+        let known = match shape.as_point() {
+            Some(point) => comp.points.contains_key(point),
+            None => comp.shapes.contains_key(shape),
+        };
+        if !comp.multimatch && known {
+            return None;
+        }
+        Some(Action {
+            priority: 0,
+            deps_count,
+            deps_cost,
+            shape,
+            action_type,
+            point_index_1,
+            point_index_2,
+            extra_index,
+        })
+    }
+
+    // The inputs are checked by Computation::queue_generic_actions(); the tool or lemma is
+    // applied to see whether the result exists
+    pub fn check_action_generic(
+        comp: &Computation,
+        action_type: ActionType,
+        inputs: [i32; 3],
+    ) -> [Option<Self>; 1] {
+        let (point_count, shape_count) = action_type.get_input_counts().unwrap();
+        let input_count = point_count + shape_count;
+        let (deps, masks): (Vec<u64>, Vec<u32>) = inputs[..input_count]
+            .iter()
+            .enumerate()
+            .map(|(k, i)| match k < point_count {
+                true => {
+                    let origin = &comp.point_origins[*i as usize];
+                    (origin.deps, origin.found_shape_mask)
+                }
+                false => {
+                    let origin = &comp.shape_origins[*i as usize];
+                    (origin.deps, origin.found_shape_mask)
+                }
+            })
+            .unzip();
+        let deps_count = comp.get_combined_three_deps_count(
+            deps[0],
+            deps.get(1).copied().unwrap_or(0),
+            deps.get(2).copied().unwrap_or(0),
+        );
+        let deps_cost = comp.get_deps_union_cost(&deps);
+        let usage = comp.get_deps_union_usage(&deps);
+        let found_shape_count = masks.iter().fold(0, |mask, x| mask | x).count_ones();
+        let reserved = comp.shape_to_find_mask_by_shape.len_u32() - found_shape_count;
+        let mut results = [None];
+        if deps_count + reserved > comp.action_count
+            || !comp.fits_cost_budget(deps_cost, reserved)
+            || !comp.problem.fits_action_limits(&usage, action_type)
+        {
+            return results;
+        }
+        let points: Vec<Point> = inputs[..point_count]
+            .iter()
+            .map(|i| comp.point_origins[*i as usize].point)
+            .collect();
+        let shapes: Vec<Shape> = inputs[point_count..input_count]
+            .iter()
+            .map(|i| comp.shape_origins[*i as usize].get_shape())
+            .collect();
+        let shape = comp.problem.construct(action_type, &points, &shapes);
+        let maybe_action =
+            Self::create_macro_action(comp, shape, action_type, (deps_count, deps_cost), inputs);
+        comp.check_action_and_add_to_results(maybe_action, &mut results);
+        results
+    }
+
+    // Point and shape indices of the inputs of a tool or lemma
+    fn get_generic_inputs(&self) -> (Vec<i32>, Vec<i32>) {
+        let inputs = [self.point_index_1, self.point_index_2, self.extra_index];
+        let (point_count, shape_count) = self.action_type.get_input_counts().unwrap();
+        let input_count = point_count + shape_count;
+        (
+            inputs[..point_count].to_vec(),
+            inputs[point_count..input_count].to_vec(),
+        )
+    }
+
     pub fn compute_priority(&self, comp: &Computation) -> i32 {
         self.action_type.compute_priority(
             comp,
@@ -525,10 +777,63 @@ impl Action {
             self.point_index_2,
             self.extra_index,
             &self.shape,
-            self.deps_count,
+            (self.deps_count, self.deps_cost),
         )
     }
 
+    // Placing the point with the given ordinal on the shape (GIVEN for a free point)
+    pub fn check_action_place_point(
+        comp: &Computation,
+        i_shape: i32,
+        ordinal: u32,
+    ) -> [Option<Self>; PointActionType::Last as usize] {
+        let (deps, found_shape_mask) = if i_shape >= 0 {
+            let shape_origin = &comp.shape_origins[i_shape as usize];
+            (shape_origin.deps, shape_origin.found_shape_mask)
+        } else {
+            (0, 0)
+        };
+        let deps_count = comp.get_deps_count(deps);
+        let deps_cost = comp.get_deps_union_cost(&[deps]);
+        let usage = comp.get_deps_union_usage(&[deps]);
+        let reserved = comp.shape_to_find_mask_by_shape.len_u32() - found_shape_mask.count_ones();
+        const NONE: Option<Action> = None;
+        let mut results = [NONE; PointActionType::Last as usize];
+        if deps_count + reserved > comp.action_count || !comp.fits_cost_budget(deps_cost, reserved)
+        {
+            return results;
+        }
+        let action_type = ActionType::PointActionType(if i_shape >= 0 {
+            PointActionType::OnShape
+        } else {
+            PointActionType::Free
+        });
+        if !comp.problem.has_action(action_type)
+            || !comp.problem.fits_action_limits(&usage, action_type)
+        {
+            return results;
+        }
+        let shape = (i_shape >= 0).then(|| comp.shape_origins[i_shape as usize].get_shape());
+        let point = Self::create_placed_point(shape.as_ref(), Self::get_placement(ordinal));
+        // Placements repeat after a while, and then the point is known already
+        let maybe_action = if !point.well_formed() || comp.points.contains_key(point) {
+            None
+        } else {
+            Some(Action {
+                priority: 0,
+                deps_count,
+                deps_cost,
+                shape: Shape::point(point),
+                action_type,
+                point_index_1: -1,
+                point_index_2: ordinal as i32,
+                extra_index: i_shape,
+            })
+        };
+        comp.check_action_and_add_to_results(maybe_action, &mut results);
+        results
+    }
+
     pub fn get_point_indices(&self) -> Vec<i32> {
         match self.action_type {
             ActionType::TwoPointActionType(_) => vec![self.point_index_1, self.point_index_2],
@@ -539,6 +844,10 @@ impl Action {
             ActionType::TwoPointAndLineActionType(_) => {
                 vec![self.point_index_1, self.point_index_2]
             }
+            ActionType::PointActionType(_) => vec![],
+            ActionType::ToolActionType(_) | ActionType::LemmaActionType(_) => {
+                self.get_generic_inputs().0
+            }
         }
     }
 
@@ -550,6 +859,11 @@ impl Action {
             ActionType::TwoPointAndLineActionType(_) => {
                 vec![self.extra_index]
             }
+            ActionType::PointActionType(_) if self.extra_index >= 0 => vec![self.extra_index],
+            ActionType::PointActionType(_) => vec![],
+            ActionType::ToolActionType(_) | ActionType::LemmaActionType(_) => {
+                self.get_generic_inputs().1
+            }
         }
     }
 
@@ -575,6 +889,27 @@ impl Action {
                 comp.point_origins[self.point_index_2 as usize].deps,
                 comp.point_origins[self.extra_index as usize].deps,
             ],
+            ActionType::PointActionType(_) if self.extra_index >= 0 => {
+                [comp.shape_origins[self.extra_index as usize].deps, 0, 0]
+            }
+            ActionType::PointActionType(_) => [0, 0, 0],
+            ActionType::ToolActionType(_) | ActionType::LemmaActionType(_) => {
+                let (point_indices, shape_indices) = self.get_generic_inputs();
+                let mut deps = [0; 3];
+                for (k, input_deps) in point_indices
+                    .iter()
+                    .map(|i| comp.point_origins[*i as usize].deps)
+                    .chain(
+                        shape_indices
+                            .iter()
+                            .map(|i| comp.shape_origins[*i as usize].deps),
+                    )
+                    .enumerate()
+                {
+                    deps[k] = input_deps;
+                }
+                deps
+            }
         }
     }
 
@@ -584,6 +919,9 @@ impl Action {
             ActionType::PointAndLineActionType(value) => value as usize,
             ActionType::ThreePointActionType(value) => value as usize,
             ActionType::TwoPointAndLineActionType(value) => value as usize,
+            ActionType::PointActionType(value) => value as usize,
+            // Tools and lemmas are checked one action type at a time (see check_action_generic())
+            ActionType::ToolActionType(_) | ActionType::LemmaActionType(_) => 0,
         }
     }
 }
@@ -596,7 +934,7 @@ trait PriorityComputation {
         point_index_2: i32,
         extra_index: i32,
         shape: &Shape,
-        deps_count: u32,
+        deps_count_and_cost: (u32, u32),
     ) -> i32;
 }
 impl PriorityComputation for ActionType {
@@ -607,22 +945,32 @@ impl PriorityComputation for ActionType {
         point_index_2: i32,
         extra_index: i32,
         shape: &Shape,
-        deps_count: u32,
+        (deps_count, deps_cost): (u32, u32),
     ) -> i32 {
         match self {
-            Self::TwoPointActionType(value) => {
-                value.compute_priority(comp, point_index_1, point_index_2, -1, shape, deps_count)
-            }
-            Self::PointAndLineActionType(value) => {
-                value.compute_priority(comp, point_index_1, -1, extra_index, shape, deps_count)
-            }
+            Self::TwoPointActionType(value) => value.compute_priority(
+                comp,
+                point_index_1,
+                point_index_2,
+                -1,
+                shape,
+                (deps_count, deps_cost),
+            ),
+            Self::PointAndLineActionType(value) => value.compute_priority(
+                comp,
+                point_index_1,
+                -1,
+                extra_index,
+                shape,
+                (deps_count, deps_cost),
+            ),
             Self::ThreePointActionType(value) => value.compute_priority(
                 comp,
                 point_index_1,
                 point_index_2,
                 extra_index,
                 shape,
-                deps_count,
+                (deps_count, deps_cost),
             ),
             Self::TwoPointAndLineActionType(value) => value.compute_priority(
                 comp,
@@ -630,8 +978,24 @@ impl PriorityComputation for ActionType {
                 point_index_2,
                 extra_index,
                 shape,
-                deps_count,
+                (deps_count, deps_cost),
             ),
+            Self::PointActionType(value) => {
+                value.compute_priority(comp, -1, -1, extra_index, shape, (deps_count, deps_cost))
+            }
+            // Tools and lemmas follow the same rules, over their inputs
+            Self::ToolActionType(_) | Self::LemmaActionType(_) => {
+                let inputs = [point_index_1, point_index_2, extra_index];
+                let (point_count, shape_count) = self.get_input_counts().unwrap();
+                compute_macro_priority(
+                    comp,
+                    self,
+                    &inputs[..point_count],
+                    &inputs[point_count..point_count + shape_count],
+                    shape,
+                    (deps_count, deps_cost),
+                )
+            }
         }
     }
 }
@@ -649,7 +1013,7 @@ impl PriorityComputation for TwoPointActionType {
         point_index_2: i32,
         _extra_index: i32,
         shape: &Shape,
-        deps_count: u32,
+        (deps_count, deps_cost): (u32, u32),
     ) -> i32 {
         match comp.problem.random_walk_at_n_actions {
             Some(n) => {
@@ -669,10 +1033,17 @@ impl PriorityComputation for TwoPointActionType {
             None => (),
         }
         let reserved = comp.shape_to_find_mask_by_shape.len_u32() - found_shape_mask.count_ones();
-        if deps_count + reserved >= comp.problem.action_count {
+        if deps_count + reserved >= comp.action_count {
+            return -1;
+        }
+        let cost = deps_cost
+            + comp
+                .problem
+                .get_action_cost(ActionType::TwoPointActionType(self));
+        if !comp.fits_cost_budget(cost, reserved) {
             return -1;
         }
-        let mut priority: i32 = 2 * ((comp.problem.action_count as i32) - (deps_count as i32));
+        let mut priority: i32 = 2 * comp.get_remaining_budget(deps_count, deps_cost);
         if deps_count <= 2 && comp.problem.prioritize_low_action_count_shapes {
             priority += (3 - (deps_count as i32)) * 50;
         }
@@ -737,7 +1108,7 @@ impl PriorityComputation for PointAndLineActionType {
         _point_index_2: i32,
         extra_index: i32,
         shape: &Shape,
-        deps_count: u32,
+        (deps_count, deps_cost): (u32, u32),
     ) -> i32 {
         match comp.problem.random_walk_at_n_actions {
             Some(n) => {
@@ -757,10 +1128,17 @@ impl PriorityComputation for PointAndLineActionType {
             None => (),
         }
         let reserved = comp.shape_to_find_mask_by_shape.len_u32() - found_shape_mask.count_ones();
-        if deps_count + reserved >= comp.problem.action_count {
+        if deps_count + reserved >= comp.action_count {
             return -1;
         }
-        let mut priority: i32 = 2 * ((comp.problem.action_count as i32) - (deps_count as i32));
+        let cost = deps_cost
+            + comp
+                .problem
+                .get_action_cost(ActionType::PointAndLineActionType(self));
+        if !comp.fits_cost_budget(cost, reserved) {
+            return -1;
+        }
+        let mut priority: i32 = 2 * comp.get_remaining_budget(deps_count, deps_cost);
         if deps_count <= 2 && comp.problem.prioritize_low_action_count_shapes {
             priority += (3 - (deps_count as i32)) * 50;
         }
@@ -830,7 +1208,7 @@ impl PriorityComputation for ThreePointActionType {
         point_index_2: i32,
         extra_index: i32,
         shape: &Shape,
-        deps_count: u32,
+        (deps_count, deps_cost): (u32, u32),
     ) -> i32 {
         match comp.problem.random_walk_at_n_actions {
             Some(n) => {
@@ -857,10 +1235,17 @@ impl PriorityComputation for ThreePointActionType {
             None => (),
         }
         let reserved = comp.shape_to_find_mask_by_shape.len_u32() - found_shape_mask.count_ones();
-        if deps_count + reserved >= comp.problem.action_count {
+        if deps_count + reserved >= comp.action_count {
             return -1;
         }
-        let mut priority: i32 = 2 * ((comp.problem.action_count as i32) - (deps_count as i32));
+        let cost = deps_cost
+            + comp
+                .problem
+                .get_action_cost(ActionType::ThreePointActionType(self));
+        if !comp.fits_cost_budget(cost, reserved) {
+            return -1;
+        }
+        let mut priority: i32 = 2 * comp.get_remaining_budget(deps_count, deps_cost);
         if deps_count <= 2 && comp.problem.prioritize_low_action_count_shapes {
             priority += (3 - (deps_count as i32)) * 50;
         }
@@ -917,7 +1302,7 @@ impl PriorityComputation for TwoPointAndLineActionType {
         point_index_2: i32,
         extra_index: i32,
         shape: &Shape,
-        deps_count: u32,
+        (deps_count, deps_cost): (u32, u32),
     ) -> i32 {
         match comp.problem.random_walk_at_n_actions {
             Some(n) => {
@@ -939,10 +1324,17 @@ impl PriorityComputation for TwoPointAndLineActionType {
             None => (),
         }
         let reserved = comp.shape_to_find_mask_by_shape.len_u32() - found_shape_mask.count_ones();
-        if deps_count + reserved >= comp.problem.action_count {
+        if deps_count + reserved >= comp.action_count {
             return -1;
         }
-        let mut priority: i32 = 2 * ((comp.problem.action_count as i32) - (deps_count as i32));
+        let cost = deps_cost
+            + comp
+                .problem
+                .get_action_cost(ActionType::TwoPointAndLineActionType(self));
+        if !comp.fits_cost_budget(cost, reserved) {
+            return -1;
+        }
+        let mut priority: i32 = 2 * comp.get_remaining_budget(deps_count, deps_cost);
         if deps_count <= 2 && comp.problem.prioritize_low_action_count_shapes {
             priority += (3 - (deps_count as i32)) * 50;
         }
@@ -979,3 +1371,130 @@ impl PriorityComputation for TwoPointAndLineActionType {
         priority
     }
 }
+impl PriorityComputation for PointActionType {
+    // A generic point isn't related to the elements to find, so there's only the base priority
+    // Base priority = 2 * (num_actions - (dep count of the shape))
+    fn compute_priority(
+        self,
+        comp: &Computation,
+        _point_index_1: i32,
+        _point_index_2: i32,
+        extra_index: i32,
+        _shape: &Shape,
+        (deps_count, deps_cost): (u32, u32),
+    ) -> i32 {
+        // The random walk doesn't place points
+        if let Some(n) = comp.problem.random_walk_at_n_actions {
+            if deps_count + 2 >= n {
+                return -1;
+            }
+        }
+        let found_shape_mask = if extra_index >= 0 {
+            comp.shape_origins[extra_index as usize].found_shape_mask
+        } else {
+            0
+        };
+        let reserved = comp.shape_to_find_mask_by_shape.len_u32() - found_shape_mask.count_ones();
+        if deps_count + reserved >= comp.action_count {
+            return -1;
+        }
+        let cost = deps_cost
+            + comp
+                .problem
+                .get_action_cost(ActionType::PointActionType(self));
+        if !comp.fits_cost_budget(cost, reserved) {
+            return -1;
+        }
+        let mut priority: i32 = 2 * comp.get_remaining_budget(deps_count, deps_cost);
+        if deps_count <= 2 && comp.problem.prioritize_low_action_count_shapes {
+            priority += (3 - (deps_count as i32)) * 50;
+        }
+        priority
+    }
+}
+// Priority rules shared by the tools and lemmas:
+// - if an input point is in points_to_find, +1
+// - if an input shape is in found_shapes, +1
+// - if an input point lies on a shape in an unregistered shapes_to_find, +5
+// - if the resulting shape is in shapes_to_find, +20 (for a point: in points_to_find, +20)
+// - if the resulting shape passes through an unregistered point in points_to_find, +5
+// Base priority = 2 * (num_actions - (dep count of the inputs))
+fn compute_macro_priority(
+    comp: &Computation,
+    action_type: ActionType,
+    point_indices: &[i32],
+    shape_indices: &[i32],
+    shape: &Shape,
+    (deps_count, deps_cost): (u32, u32),
+) -> i32 {
+    if let Some(n) = comp.problem.random_walk_at_n_actions {
+        if deps_count >= n - 1 {
+            return -1;
+        }
+    }
+    let points: Vec<Point> = point_indices
+        .iter()
+        .map(|i| comp.point_origins[*i as usize].point)
+        .collect();
+    let shapes: Vec<Shape> = shape_indices
+        .iter()
+        .map(|i| comp.shape_origins[*i as usize].get_shape())
+        .collect();
+    let mut found_shape_mask = point_indices
+        .iter()
+        .map(|i| comp.point_origins[*i as usize].found_shape_mask)
+        .chain(
+            shape_indices
+                .iter()
+                .map(|i| comp.shape_origins[*i as usize].found_shape_mask),
+        )
+        .fold(0, |mask, x| mask | x);
+    if let Some(mask) = comp.shape_to_find_mask_by_shape.get(*shape) {
+        found_shape_mask |= mask;
+    }
+    let reserved = comp.shape_to_find_mask_by_shape.len_u32() - found_shape_mask.count_ones();
+    if deps_count + reserved >= comp.action_count {
+        return -1;
+    }
+    let cost = deps_cost + comp.problem.get_action_cost(action_type);
+    if !comp.fits_cost_budget(cost, reserved) {
+        return -1;
+    }
+    let mut priority: i32 = 2 * comp.get_remaining_budget(deps_count, deps_cost);
+    if deps_count <= 2 && comp.problem.prioritize_low_action_count_shapes {
+        priority += (3 - (deps_count as i32)) * 50;
+    }
+    for point in &points {
+        if comp.found_points.contains(*point) {
+            priority += 1;
+        }
+        for shape_to_find in &comp.shapes_to_find {
+            if shape_to_find.contains_point(point) {
+                priority += 5;
+            }
+        }
+    }
+    for input_shape in &shapes {
+        if comp.found_shapes.contains(*input_shape) {
+            priority += 1;
+        }
+    }
+    match shape.as_point() {
+        Some(point) => {
+            if comp.points_to_find.contains(point) {
+                priority += 20;
+            }
+        }
+        None => {
+            if comp.shapes_to_find.contains(*shape) {
+                priority += 20;
+            }
+            for point in &comp.points_to_find {
+                if shape.contains_point(point) {
+                    priority += 5;
+                }
+            }
+        }
+    }
+    priority
+}
@@ -0,0 +1,579 @@
+use std::collections::BinaryHeap;
+use std::fs::{read, rename, write};
+use std::time::Duration;
+
+use crate::computation::action::{Action, ElementLink};
+use crate::computation::{PointOrigin, ShapeOrigin};
+use crate::shape::{Point, Shape};
+use crate::Computation;
+use private::*;
+
+// What Computation::solve() keeps besides the Computation, for resuming a search
+#[derive(Default)]
+pub(super) struct SolveProgress {
+    pub iteration: u32,
+    pub elapsed: Duration,
+    pub rw_queue: Vec<Action>,
+    // Deps and times of the solutions found so far (see Computation::build_solution())
+    pub solutions: Vec<(u64, Duration)>,
+}
+
+pub trait Checkpoint {
+    // Loads the state saved by solve() with SolveOptions::checkpoint into a new Computation (in
+    // place of initialize_queue()), so that solve() continues where the saved run was
+    fn load_checkpoint(&mut self, filename: &str) -> Result<(), String>;
+}
+impl<'a> Checkpoint for Computation<'a> {
+    fn load_checkpoint(&mut self, filename: &str) -> Result<(), String> {
+        let data = read(filename).map_err(|e| format!("Can't read {}: {}", filename, e))?;
+        let problem = self.problem;
+        let mut reader = Reader::new(problem, &data)?;
+        if (reader.u32()?, reader.u64()?) != (problem.action_count, get_fingerprint(problem)) {
+            return Err(format!("{} was saved for another problem", filename));
+        }
+        self.action_count = reader.u32()?;
+        self.solution_deps = match reader.u8()? {
+            0 => None,
+            _ => Some(reader.u64()?),
+        };
+        for _ in 0..reader.len()? {
+            self.point_origins.push(PointOrigin {
+                point: reader.point()?,
+                deps: reader.u64()?,
+                shape_origin_indices: [reader.i32()?, reader.i32()?],
+                found_shape_mask: reader.u32()?,
+                next: reader.i32()?,
+            });
+        }
+        for _ in 0..reader.len()? {
+            let deps = reader.u64()?;
+            let element_link = match reader.u8()? {
+                0 => {
+                    let element = problem
+                        .given_elements
+                        .get(reader.u32()? as usize)
+                        .ok_or("Wrong given element index in the checkpoint")?;
+                    let shape = element.get_shape().unwrap();
+                    ElementLink::GivenElement { element, shape }
+                }
+                _ => ElementLink::Action(reader.action()?),
+            };
+            self.shape_origins.push(ShapeOrigin {
+                deps,
+                element_link,
+                found_shape_mask: reader.u32()?,
+                next: reader.i32()?,
+            });
+        }
+        for _ in 0..reader.len()? {
+            self.points.insert_if_new(reader.point()?, reader.i32()?);
+        }
+        for _ in 0..reader.len()? {
+            self.shapes.insert_if_new(reader.shape()?, reader.i32()?);
+        }
+        for _ in 0..reader.len()? {
+            let shape = reader.shape()?;
+            self.shape_to_find_mask_by_shape
+                .insert_if_new(shape, reader.u32()?);
+        }
+        for set in [&mut self.points_to_find, &mut self.found_points] {
+            for _ in 0..reader.len()? {
+                set.insert(reader.point()?);
+            }
+        }
+        for set in [&mut self.shapes_to_find, &mut self.found_shapes] {
+            for _ in 0..reader.len()? {
+                set.insert(reader.shape()?);
+            }
+        }
+        // The saved vector is a valid heap, which from() keeps as it is
+        let queue = (0..reader.len()?)
+            .map(|_| reader.action())
+            .collect::<Result<Vec<Action>, String>>()?;
+        self.queue = BinaryHeap::from(queue);
+        let combinations = (0..reader.len()?)
+            .map(|_| (0..reader.len()?).map(|_| reader.u32()).collect())
+            .collect::<Result<Vec<Vec<u32>>, String>>()?;
+        self.set_deps_combinations(combinations);
+        let iteration = reader.u32()?;
+        let elapsed = reader.duration()?;
+        let rw_queue = (0..reader.len()?)
+            .map(|_| reader.action())
+            .collect::<Result<Vec<Action>, String>>()?;
+        let solutions = (0..reader.len()?)
+            .map(|_| Ok((reader.u64()?, reader.duration()?)))
+            .collect::<Result<Vec<(u64, Duration)>, String>>()?;
+        self.resumed = Some(SolveProgress {
+            iteration,
+            elapsed,
+            rw_queue,
+            solutions,
+        });
+        Ok(())
+    }
+}
+
+impl<'a> Computation<'a> {
+    // Written to a temporary file first, so that a run stopped while saving keeps the previous
+    // checkpoint
+    pub(super) fn save_checkpoint(
+        &self,
+        filename: &str,
+        progress: &SolveProgress,
+    ) -> Result<(), String> {
+        let problem = self.problem;
+        let mut writer = Writer::new(problem);
+        writer.u32(problem.action_count);
+        writer.u64(get_fingerprint(problem));
+        writer.u32(self.action_count);
+        match self.solution_deps {
+            None => writer.u8(0),
+            Some(deps) => {
+                writer.u8(1);
+                writer.u64(deps);
+            }
+        }
+        writer.len(self.point_origins.len());
+        for origin in &self.point_origins {
+            writer.point(&origin.point);
+            writer.u64(origin.deps);
+            writer.i32(origin.shape_origin_indices[0]);
+            writer.i32(origin.shape_origin_indices[1]);
+            writer.u32(origin.found_shape_mask);
+            writer.i32(origin.next);
+        }
+        writer.len(self.shape_origins.len());
+        for origin in &self.shape_origins {
+            writer.u64(origin.deps);
+            match &origin.element_link {
+                ElementLink::GivenElement { element, .. } => {
+                    let index = problem
+                        .given_elements
+                        .iter()
+                        .position(|given| std::ptr::eq(given, *element))
+                        .unwrap();
+                    writer.u8(0);
+                    writer.len(index);
+                }
+                ElementLink::Action(action) => {
+                    writer.u8(1);
+                    writer.action(action);
+                }
+            }
+            writer.u32(origin.found_shape_mask);
+            writer.i32(origin.next);
+        }
+        writer.len(self.points.len() as usize);
+        for (point, index) in self.points.iter() {
+            writer.point(point);
+            writer.i32(*index);
+        }
+        writer.len(self.shapes.len() as usize);
+        for (shape, index) in self.shapes.iter() {
+            writer.shape(shape);
+            writer.i32(*index);
+        }
+        writer.len(self.shape_to_find_mask_by_shape.len() as usize);
+        for (shape, mask) in self.shape_to_find_mask_by_shape.iter() {
+            writer.shape(shape);
+            writer.u32(*mask);
+        }
+        for set in [&self.points_to_find, &self.found_points] {
+            let points: Vec<&Point> = set.iter().collect();
+            writer.len(points.len());
+            for point in points {
+                writer.point(point);
+            }
+        }
+        for set in [&self.shapes_to_find, &self.found_shapes] {
+            let shapes: Vec<&Shape> = set.iter().collect();
+            writer.len(shapes.len());
+            for shape in shapes {
+                writer.shape(shape);
+            }
+        }
+        writer.len(self.queue.len());
+        for action in self.queue.as_slice() {
+            writer.action(action);
+        }
+        writer.len(self.deps_combinations.len());
+        for combination in &self.deps_combinations {
+            writer.len(combination.len());
+            for index in combination {
+                writer.u32(*index);
+            }
+        }
+        writer.u32(progress.iteration);
+        writer.duration(progress.elapsed);
+        writer.len(progress.rw_queue.len());
+        for action in &progress.rw_queue {
+            writer.action(action);
+        }
+        writer.len(progress.solutions.len());
+        for (deps, elapsed) in &progress.solutions {
+            writer.u64(*deps);
+            writer.duration(*elapsed);
+        }
+        let temp_filename = format!("{}.tmp", filename);
+        write(&temp_filename, writer.finish())
+            .and_then(|_| rename(&temp_filename, filename))
+            .map_err(|e| format!("Can't write {}: {}", filename, e))
+    }
+}
+
+mod private {
+    use crate::computation::action::Action;
+    use crate::element::Element;
+    use crate::fint::FInt;
+    use crate::problems::{ActionType, ProblemDefinition};
+    use crate::shape::{Circle, Line, Point, Ray, Segment, Shape};
+    use std::time::Duration;
+
+    const MAGIC: &[u8; 8] = b"EUCCKPT4";
+
+    // Hash of what the saved queue depends on: the elements, the action types and lemmas, and
+    // the options pruning or ordering the search, so that a checkpoint is only loaded for the
+    // problem and the rules it was saved with
+    pub fn get_fingerprint(problem: &ProblemDefinition) -> u64 {
+        let mut writer = Writer::new(problem);
+        for elements in [&problem.given_elements, &problem.elements_to_find] {
+            writer.len(elements.len());
+            for element in elements.iter() {
+                match element {
+                    Element::Point(point) => writer.shape(&Shape::point(*point)),
+                    _ => writer.shape(&element.get_shape().unwrap()),
+                }
+            }
+        }
+        writer.len(problem.action_types.len());
+        for action_type in problem.action_types.iter() {
+            writer.str(&problem.get_action_type_name(*action_type));
+        }
+        writer.len(problem.action_costs.len());
+        for (action_type, cost) in problem.action_costs.iter() {
+            writer.str(&problem.get_action_type_name(*action_type));
+            writer.u32(*cost);
+        }
+        writer.len(problem.action_limits.len());
+        for (group, limit) in problem.action_limits.iter() {
+            writer.str(&format!("{:?}", group));
+            writer.u32(*limit);
+        }
+        writer.len(problem.lemmas.len());
+        for lemma in problem.lemmas.iter() {
+            writer.str(&lemma.name);
+            writer.len(lemma.solution.steps.len());
+            for step in &lemma.solution.steps {
+                writer.shape(&step.shape);
+            }
+        }
+        for option in [problem.cost_budget, problem.random_walk_at_n_actions] {
+            writer.u32(option.map_or(0, |value| value + 1));
+        }
+        for flag in [
+            problem.multimatch,
+            problem.minimize_e_moves,
+            problem.prioritize_low_action_count_shapes,
+        ] {
+            writer.u8(flag as u8);
+        }
+        // FNV-1a, which unlike DefaultHasher doesn't change between Rust versions
+        writer.data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    // Binary encoding of the state: numbers are little-endian and intervals are written bit for
+    // bit, so that the search continues with exactly the same shapes. Action types are written
+    // as indices into a table of their names, which follows the magic.
+    pub struct Writer<'p> {
+        problem: &'p ProblemDefinition,
+        data: Vec<u8>,
+        action_types: Vec<ActionType>,
+    }
+    impl<'p> Writer<'p> {
+        pub fn new(problem: &'p ProblemDefinition) -> Self {
+            Self {
+                problem,
+                data: vec![],
+                action_types: vec![],
+            }
+        }
+
+        pub fn finish(self) -> Vec<u8> {
+            let mut header = Writer::new(self.problem);
+            header.data.extend_from_slice(MAGIC);
+            header.len(self.action_types.len());
+            for action_type in &self.action_types {
+                header.str(&self.problem.get_action_type_name(*action_type));
+            }
+            header.data.extend(self.data);
+            header.data
+        }
+
+        pub fn u8(&mut self, value: u8) {
+            self.data.push(value);
+        }
+
+        pub fn u32(&mut self, value: u32) {
+            self.data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        pub fn i32(&mut self, value: i32) {
+            self.data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        pub fn u64(&mut self, value: u64) {
+            self.data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        pub fn len(&mut self, value: usize) {
+            self.u32(value as u32);
+        }
+
+        pub fn str(&mut self, value: &str) {
+            self.len(value.len());
+            self.data.extend_from_slice(value.as_bytes());
+        }
+
+        pub fn duration(&mut self, value: Duration) {
+            self.u64(value.as_secs());
+            self.u32(value.subsec_nanos());
+        }
+
+        pub fn fint(&mut self, value: FInt) {
+            let (lower, upper) = value.get_bounds();
+            self.u64(lower.to_bits());
+            self.u64(upper.to_bits());
+        }
+
+        pub fn point(&mut self, point: &Point) {
+            self.fint(point.0);
+            self.fint(point.1);
+        }
+
+        pub fn shape(&mut self, shape: &Shape) {
+            match shape {
+                Shape::Line(line) => {
+                    self.u8(0);
+                    self.fint(line.nx);
+                    self.fint(line.ny);
+                    self.fint(line.d);
+                }
+                Shape::Ray(ray) => {
+                    self.u8(1);
+                    self.point(&ray.a);
+                    self.point(&ray.v);
+                }
+                Shape::Segment(segment) => {
+                    self.u8(2);
+                    self.point(&segment.a);
+                    self.point(&segment.b);
+                }
+                Shape::Circle(circle) => {
+                    self.u8(3);
+                    self.point(&circle.c);
+                    self.fint(circle.r2);
+                }
+                Shape::Point(point) => {
+                    self.u8(4);
+                    self.point(point);
+                }
+            }
+        }
+
+        pub fn action(&mut self, action: &Action) {
+            let index = match self
+                .action_types
+                .iter()
+                .position(|t| *t == action.action_type)
+            {
+                Some(index) => index,
+                None => {
+                    self.action_types.push(action.action_type);
+                    self.action_types.len() - 1
+                }
+            };
+            self.len(index);
+            self.i32(action.priority);
+            self.u32(action.deps_count);
+            self.u32(action.deps_cost);
+            self.shape(&action.shape);
+            self.i32(action.point_index_1);
+            self.i32(action.point_index_2);
+            self.i32(action.extra_index);
+        }
+    }
+
+    pub struct Reader<'d> {
+        data: &'d [u8],
+        position: usize,
+        action_types: Vec<ActionType>,
+    }
+    impl<'d> Reader<'d> {
+        pub fn new(problem: &ProblemDefinition, data: &'d [u8]) -> Result<Self, String> {
+            if !data.starts_with(MAGIC) {
+                return Err("Not a checkpoint file".to_string());
+            }
+            let mut reader = Reader {
+                data,
+                position: MAGIC.len(),
+                action_types: vec![],
+            };
+            for _ in 0..reader.len()? {
+                let length = reader.len()?;
+                let name = String::from_utf8(reader.bytes(length)?.to_vec())
+                    .map_err(|_| "Wrong action type name in the checkpoint")?;
+                reader.action_types.push(problem.parse_action_type(&name)?);
+            }
+            Ok(reader)
+        }
+
+        fn bytes(&mut self, count: usize) -> Result<&'d [u8], String> {
+            let bytes = self
+                .data
+                .get(self.position..self.position + count)
+                .ok_or("The checkpoint is truncated")?;
+            self.position += count;
+            Ok(bytes)
+        }
+
+        pub fn u8(&mut self) -> Result<u8, String> {
+            Ok(self.bytes(1)?[0])
+        }
+
+        pub fn u32(&mut self) -> Result<u32, String> {
+            Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+        }
+
+        pub fn i32(&mut self) -> Result<i32, String> {
+            Ok(i32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+        }
+
+        pub fn u64(&mut self) -> Result<u64, String> {
+            Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+        }
+
+        pub fn len(&mut self) -> Result<usize, String> {
+            Ok(self.u32()? as usize)
+        }
+
+        pub fn duration(&mut self) -> Result<Duration, String> {
+            Ok(Duration::new(self.u64()?, self.u32()?))
+        }
+
+        pub fn fint(&mut self) -> Result<FInt, String> {
+            let lower = f64::from_bits(self.u64()?);
+            let upper = f64::from_bits(self.u64()?);
+            Ok(FInt::new_with_bounds(lower, upper))
+        }
+
+        pub fn point(&mut self) -> Result<Point, String> {
+            Ok(Point(self.fint()?, self.fint()?))
+        }
+
+        pub fn shape(&mut self) -> Result<Shape, String> {
+            Ok(match self.u8()? {
+                0 => Shape::Line(Line {
+                    nx: self.fint()?,
+                    ny: self.fint()?,
+                    d: self.fint()?,
+                }),
+                1 => Shape::Ray(Ray {
+                    a: self.point()?,
+                    v: self.point()?,
+                }),
+                2 => Shape::Segment(Segment {
+                    a: self.point()?,
+                    b: self.point()?,
+                }),
+                3 => Shape::Circle(Circle {
+                    c: self.point()?,
+                    r2: self.fint()?,
+                }),
+                4 => Shape::Point(self.point()?),
+                _ => return Err("Wrong shape in the checkpoint".to_string()),
+            })
+        }
+
+        pub fn action(&mut self) -> Result<Action, String> {
+            let index = self.len()?;
+            let action_type = *self
+                .action_types
+                .get(index)
+                .ok_or("Wrong action type index in the checkpoint")?;
+            Ok(Action {
+                priority: self.i32()?,
+                deps_count: self.u32()?,
+                deps_cost: self.u32()?,
+                shape: self.shape()?,
+                action_type,
+                point_index_1: self.i32()?,
+                point_index_2: self.i32()?,
+                extra_index: self.i32()?,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::ProblemDefinition;
+    use crate::SolveOptions;
+
+    #[test]
+    fn test_resume() {
+        let mut problem = ProblemDefinition::get_problem_by_id("7.5-herons").unwrap();
+        problem.multimatch = true;
+        let filename = std::env::temp_dir()
+            .join(format!("euc-checkpoint-{}", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let options = SolveOptions {
+            checkpoint: Some(filename.clone()),
+            checkpoint_interval: Duration::ZERO,
+            ..SolveOptions::without_files()
+        };
+        let solutions = crate::solve(&problem, &options);
+        assert_eq!(solutions.len(), 1);
+
+        // Continuing from the last checkpoint before the solution gives the same solution
+        let mut computation = Computation::new(&problem);
+        computation.load_checkpoint(&filename).unwrap();
+        assert!(computation.resumed.as_ref().unwrap().iteration > 1);
+        let resumed = computation.solve(&SolveOptions {
+            checkpoint: None,
+            ..options
+        });
+        assert_eq!(resumed.len(), solutions.len());
+        assert_eq!(resumed[0].deps, solutions[0].deps);
+        assert_eq!(resumed[0].steps, solutions[0].steps);
+
+        // Problems with other elements or action types are rejected, even with the same counts
+        let other = ProblemDefinition::get_problem_by_id("1.3-midpoint").unwrap();
+        assert!(Computation::new(&other).load_checkpoint(&filename).is_err());
+        let mut moved = ProblemDefinition::get_problem_by_id("7.5-herons").unwrap();
+        moved.given_elements.swap(0, 1);
+        assert!(Computation::new(&moved).load_checkpoint(&filename).is_err());
+        let mut fewer_tools = ProblemDefinition::get_problem_by_id("7.5-herons").unwrap();
+        fewer_tools.action_types = problem.action_types[1..].to_vec().into();
+        assert!(Computation::new(&fewer_tools)
+            .load_checkpoint(&filename)
+            .is_err());
+
+        // So is the same problem searched under other rules
+        let mut single_match = ProblemDefinition::get_problem_by_id("7.5-herons").unwrap();
+        single_match.multimatch = false;
+        assert!(Computation::new(&single_match)
+            .load_checkpoint(&filename)
+            .is_err());
+        let mut budgeted = ProblemDefinition::get_problem_by_id("7.5-herons").unwrap();
+        budgeted.multimatch = true;
+        budgeted.cost_budget = Some(10);
+        assert!(Computation::new(&budgeted)
+            .load_checkpoint(&filename)
+            .is_err());
+        std::fs::remove_file(&filename).unwrap();
+    }
+}
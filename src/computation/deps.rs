@@ -0,0 +1,299 @@
+use crate::computation::Computation;
+use crate::VecLengths;
+pub(super) use private::is_bit_set;
+use private::*;
+
+// Deps (the shape origins an element depends on) are u64 handles: the low DIRECT_BITS bits are
+// the shape origins with these indices, the high bits index into deps_combinations, the
+// interned sorted lists of the other indices (0 being the empty list). Small problems stay on
+// plain bit operations, while the number of shape origins and of combinations is only bounded
+// by u32.
+pub(super) const DIRECT_BITS: u32 = 32;
+const DIRECT_MASK: u64 = (1u64 << DIRECT_BITS) - 1;
+
+impl<'a> Computation<'a> {
+    pub(super) fn get_deps_count(&self, deps: u64) -> u32 {
+        (deps & DIRECT_MASK).count_ones() + self.get_deps_combination(deps).len() as u32
+    }
+
+    pub(super) fn get_deps_indices(&self, deps: u64) -> Vec<u32> {
+        (0..DIRECT_BITS)
+            .filter(|i| deps & (1 << i) != 0)
+            .chain(self.get_deps_combination(deps).iter().copied())
+            .collect()
+    }
+
+    // Shape origin indices in the union of the deps
+    pub(super) fn get_deps_union_indices(&self, deps_list: &[u64]) -> Vec<u32> {
+        let lower_mask = deps_list.iter().fold(0, |mask, deps| mask | deps) & DIRECT_MASK;
+        let mut high_indices: Vec<u32> = deps_list
+            .iter()
+            .flat_map(|deps| self.get_deps_combination(*deps))
+            .copied()
+            .collect();
+        high_indices.sort_unstable();
+        high_indices.dedup();
+        (0..DIRECT_BITS)
+            .filter(|i| lower_mask & (1 << i) != 0)
+            .chain(high_indices)
+            .collect()
+    }
+
+    pub(super) fn print_deps(&self, deps: u64) -> String {
+        format!("{:?}", self.get_deps_indices(deps))
+    }
+
+    pub(super) fn get_combined_deps_count(&self, deps1: u64, deps2: u64) -> u32 {
+        let lower_mask = (deps1 | deps2) & DIRECT_MASK;
+        let (deps_vec_1, deps_vec_2) = (
+            self.get_deps_combination(deps1),
+            self.get_deps_combination(deps2),
+        );
+        lower_mask.count_ones() + get_union_count(deps_vec_1, deps_vec_2)
+    }
+
+    pub(super) fn get_combined_three_deps_count(&self, deps1: u64, deps2: u64, deps3: u64) -> u32 {
+        let lower_mask = (deps1 | deps2 | deps3) & DIRECT_MASK;
+        let mut union = vec![];
+        merge_into(
+            self.get_deps_combination(deps1),
+            self.get_deps_combination(deps2),
+            &mut union,
+        );
+        lower_mask.count_ones() + get_union_count(&union, self.get_deps_combination(deps3))
+    }
+
+    // Replaces the combinations (e.g. by the ones of a checkpoint) and indexes them
+    pub(super) fn set_deps_combinations(&mut self, combinations: Vec<Vec<u32>>) {
+        self.deps_indices_by_hashes.clear();
+        for (index, combination) in combinations.iter().enumerate() {
+            self.deps_indices_by_hashes
+                .entry(get_hash(combination))
+                .or_default()
+                .push(index as u32);
+        }
+        self.deps_combinations = combinations;
+    }
+
+    fn get_deps_combination(&self, deps: u64) -> &[u32] {
+        &self.deps_combinations[(deps >> DIRECT_BITS) as usize]
+    }
+
+    fn get_index_of_deps_combination(&mut self, high_indices: &[u32]) -> u64 {
+        let hash = get_hash(high_indices);
+        if let Some(indices) = self.deps_indices_by_hashes.get(&hash) {
+            for index in indices {
+                if high_indices == self.deps_combinations[*index as usize] {
+                    return *index as u64;
+                }
+            }
+        }
+        let new_index = u32::try_from(self.deps_combinations.len())
+            .expect("Too many deps combinations for the deps encoding");
+        self.deps_indices_by_hashes
+            .entry(hash)
+            .or_default()
+            .push(new_index);
+        self.deps_combinations.push(high_indices.to_vec());
+        new_index as u64
+    }
+
+    pub fn combine_deps(&mut self, deps1: u64, deps2: u64, index: Option<i32>) -> u64 {
+        let index_mask = match index {
+            Some(value) if (value as u32) < DIRECT_BITS => 1 << value,
+            _ => 0,
+        };
+        let high_index = index
+            .map(|value| value as u32)
+            .filter(|value| *value >= DIRECT_BITS);
+        let lower_mask = (deps1 | deps2 | index_mask) & DIRECT_MASK;
+        let (high1, high2) = (deps1 & !DIRECT_MASK, deps2 & !DIRECT_MASK);
+        if high_index.is_none() && (high1 == high2 || high2 == 0) {
+            return lower_mask | high1;
+        }
+        if high_index.is_none() && high1 == 0 {
+            return lower_mask | high2;
+        }
+        // The buffer is kept between the calls to avoid allocations
+        let mut buffer = std::mem::take(&mut self.deps_buffer);
+        buffer.clear();
+        merge_into(
+            self.get_deps_combination(deps1),
+            self.get_deps_combination(deps2),
+            &mut buffer,
+        );
+        if let Some(value) = high_index {
+            if let Err(position) = buffer.binary_search(&value) {
+                buffer.insert(position, value);
+            }
+        }
+        let index = self.get_index_of_deps_combination(&buffer);
+        self.deps_buffer = buffer;
+        lower_mask | (index << DIRECT_BITS)
+    }
+
+    pub(super) fn requires_deps(&self, action_deps: &[u64; 3], deps: u64) -> bool {
+        if deps & !DIRECT_MASK == 0 {
+            action_deps
+                .iter()
+                .any(|action_deps| action_deps & deps == deps)
+        } else {
+            action_deps.iter().any(|action_deps| {
+                self.get_combined_deps_count(*action_deps, deps)
+                    == self.get_deps_count(*action_deps)
+            })
+        }
+    }
+
+    // Bitset over the indices of all_deps (in the order of get_deps_indices()) with the indices
+    // of deps set
+    pub(super) fn compress(&self, deps: u64, all_deps: u64) -> Vec<u64> {
+        let deps_combination = self.get_deps_combination(deps);
+        let indices = self.get_deps_indices(all_deps);
+        let mut result = vec![0u64; indices.len().div_ceil(64)];
+        for (k, i) in indices.iter().enumerate() {
+            let contained = match *i < DIRECT_BITS {
+                true => deps & (1 << i) != 0,
+                false => deps_combination.binary_search(i).is_ok(),
+            };
+            if contained {
+                result[k / 64] |= 1 << (k % 64);
+            }
+        }
+        result
+    }
+
+    // The deps with the indices of all_deps set in the bitset made by compress()
+    pub(super) fn decompress(&mut self, union: &[u64], all_deps: u64) -> u64 {
+        let mut lower_mask = 0u64;
+        let mut high_indices = vec![];
+        for (k, i) in self.get_deps_indices(all_deps).into_iter().enumerate() {
+            if !is_bit_set(union, k) {
+                continue;
+            }
+            if i < DIRECT_BITS {
+                lower_mask |= 1 << i;
+            } else {
+                high_indices.push(i);
+            }
+        }
+        let index = self.get_index_of_deps_combination(&high_indices);
+        (index << DIRECT_BITS) | lower_mask
+    }
+
+    // Sum of the costs of the set bits of compressed deps
+    pub(super) fn get_compressed_cost(deps: &[u64], costs: &[u32]) -> u32 {
+        (0..costs.len())
+            .filter(|i| is_bit_set(deps, *i))
+            .map(|i| costs[i])
+            .sum()
+    }
+
+    pub(super) fn get_compressed_count(deps: &[u64]) -> u32 {
+        deps.iter().map(|word| word.count_ones()).sum()
+    }
+
+    // The cheapest union of one deps from each list that is accepted by fits (None if there's
+    // none); costs are per bit of the compressed deps
+    pub(super) fn find_shortest_deps_union(
+        deps_lists: &Vec<Vec<Vec<u64>>>,
+        union_so_far: &[u64],
+        index: u32,
+        costs: &[u32],
+        fits: &dyn Fn(&[u64]) -> bool,
+    ) -> Option<Vec<u64>> {
+        if index == deps_lists.len_u32() {
+            return Some(union_so_far.to_vec()).filter(|union| fits(union));
+        }
+        deps_lists[index as usize]
+            .iter()
+            .filter_map(|deps| {
+                let union: Vec<u64> = (0..deps.len().max(union_so_far.len()))
+                    .map(|k| union_so_far.get(k).unwrap_or(&0) | deps.get(k).unwrap_or(&0))
+                    .collect();
+                Self::find_shortest_deps_union(deps_lists, &union, index + 1, costs, fits)
+            })
+            .min_by_key(|union| Self::get_compressed_cost(union, costs))
+    }
+}
+
+mod private {
+    pub fn is_bit_set(bits: &[u64], k: usize) -> bool {
+        bits.get(k / 64)
+            .is_some_and(|word| word & (1 << (k % 64)) != 0)
+    }
+
+    // Appends the sorted union of the sorted lists to result
+    pub fn merge_into(a: &[u32], b: &[u32], result: &mut Vec<u32>) {
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            let (x, y) = (a[i], b[j]);
+            result.push(x.min(y));
+            i += (x <= y) as usize;
+            j += (y <= x) as usize;
+        }
+        result.extend_from_slice(&a[i..]);
+        result.extend_from_slice(&b[j..]);
+    }
+
+    // Size of the union of the sorted lists
+    pub fn get_union_count(a: &[u32], b: &[u32]) -> u32 {
+        let (mut i, mut j, mut same) = (0, 0, 0);
+        while i < a.len() && j < b.len() {
+            same += (a[i] == b[j]) as u32;
+            let (x, y) = (a[i], b[j]);
+            i += (x <= y) as usize;
+            j += (y <= x) as usize;
+        }
+        (a.len() + b.len()) as u32 - same
+    }
+
+    // 0 for the empty list, which is registered at the start
+    pub fn get_hash(indices: &[u32]) -> u64 {
+        indices.iter().fold(0u64, |hash, i| {
+            (hash.rotate_left(5) ^ (*i as u64)).wrapping_mul(0x517c_c1b7_2722_0a95)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::ProblemDefinition;
+
+    #[test]
+    fn test_deps_encoding() {
+        let problem = ProblemDefinition::get_problem_by_id("1.3-midpoint-with-midperp").unwrap();
+        let mut comp = Computation::new(&problem);
+
+        // Thousands of shape origins, each one depending on the previous ones
+        let mut deps = 0;
+        for index in 0..3000 {
+            deps = comp.combine_deps(deps, 0, Some(index));
+        }
+        assert_eq!(comp.get_deps_count(deps), 3000);
+        assert_eq!(comp.get_deps_indices(deps), (0..3000).collect::<Vec<u32>>());
+        let (a, b) = (
+            comp.combine_deps(0, 0, Some(5)),
+            comp.combine_deps(0, 0, Some(2500)),
+        );
+        assert_eq!(comp.combine_deps(deps, a, None), deps);
+        assert_eq!(comp.combine_deps(deps, b, Some(7)), deps);
+        assert_eq!(comp.get_combined_deps_count(a, b), 2);
+        assert!(comp.requires_deps(&[0, deps, 0], b));
+        assert!(!comp.requires_deps(&[a, 0, 0], b));
+
+        // Interned: the same indices give the same deps
+        let ab = comp.combine_deps(a, b, None);
+        assert_eq!(comp.combine_deps(b, a, None), ab);
+        assert_eq!(
+            ab >> DIRECT_BITS,
+            comp.combine_deps(0, b, None) >> DIRECT_BITS
+        );
+
+        // More than 64 relevant shapes for the multimatch unions
+        let compressed = comp.compress(ab, deps);
+        assert_eq!(Computation::get_compressed_count(&compressed), 2);
+        assert_eq!(comp.decompress(&compressed, deps), ab);
+    }
+}
@@ -1,82 +1,223 @@
 use std::collections::HashSet;
-use std::fs::read_to_string;
-use std::str::FromStr;
+use std::path::Path;
 
-use crate::shape::Shape;
+use strum_macros::{EnumString, IntoStaticStr};
+
+use crate::computation::action::ElementLink;
+use crate::computation::print::PrintStateHelper;
+use crate::computation::verify::read_shapes;
+use crate::element::Element;
+use crate::shape::{Point, Shape};
 use crate::Computation;
 use crate::VecLengths;
 use private::*;
-use svg::Document;
+
+pub(super) use private::{Canvas, SvgCanvas, COLORS, HIGHLIGHT};
+
+// Output format of the drawings; by default it's chosen by the file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, IntoStaticStr, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum DrawFormat {
+    #[default]
+    Svg,
+    // A tikzpicture environment, to be included into a LaTeX document
+    Tikz,
+    Asymptote,
+}
+impl DrawFormat {
+    // .tex/.tikz -> TikZ, .asy -> Asymptote, anything else -> SVG
+    pub fn from_filename(filename: &str) -> Self {
+        match Path::new(filename).extension().and_then(|s| s.to_str()) {
+            Some("tex") | Some("tikz") => DrawFormat::Tikz,
+            Some("asy") => DrawFormat::Asymptote,
+            _ => DrawFormat::Svg,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DrawFormat::Svg => "svg",
+            DrawFormat::Tikz => "tex",
+            DrawFormat::Asymptote => "asy",
+        }
+    }
+}
+
+// The drawn square: its center and half-width
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub cx: f64,
+    pub cy: f64,
+    pub hw: f64,
+}
+impl Viewport {
+    pub fn centered(hw: f64) -> Self {
+        Self {
+            cx: 0.0,
+            cy: 0.0,
+            hw,
+        }
+    }
+
+    // The smallest square containing the points and the shapes (circles entirely, lines by
+    // their point closest to the origin, rays by their start), with a margin
+    pub fn fit(points: &[Point], shapes: &[Shape]) -> Self {
+        let mut coords: Vec<(f64, f64)> = points
+            .iter()
+            .map(|point| (point.0.midpoint(), point.1.midpoint()))
+            .collect();
+        for shape in shapes {
+            coords.extend(get_extreme_points(shape));
+        }
+        if coords.is_empty() {
+            return Self::centered(5.0);
+        }
+        let min_x = coords.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+        let max_x = coords.iter().map(|c| c.0).fold(f64::NEG_INFINITY, f64::max);
+        let min_y = coords.iter().map(|c| c.1).fold(f64::INFINITY, f64::min);
+        let max_y = coords.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max);
+        let hw = f64::max(max_x - min_x, max_y - min_y) / 2.0;
+        Self {
+            cx: (min_x + max_x) / 2.0,
+            cy: (min_y + max_y) / 2.0,
+            hw: hw * 1.2 + 0.2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DrawOptions {
+    // Half-width of the drawn square centered at the origin; None fits the square to the
+    // given elements, the elements to find and the drawn solution
+    pub half_width: Option<f64>,
+    // Cuts lines and rays at the border of the drawn square
+    pub clip_lines: bool,
+    // Labels the points and shapes with their names (as printed by PrintState)
+    pub labels: bool,
+}
+impl DrawOptions {
+    pub fn get_viewport(&self, points: &[Point], shapes: &[Shape]) -> Viewport {
+        match self.half_width {
+            Some(hw) => Viewport::centered(hw),
+            None => Viewport::fit(points, shapes),
+        }
+    }
+}
 
 pub trait DrawState {
-    fn draw_state(&mut self, filename: String, hw: f64, only_included_in_deps: HashSet<u64>);
-    fn draw_solution(&mut self, filename: String, hw: f64);
-    fn draw_shapes(shapes: &Vec<Shape>, filename: String, hw: f64);
-    fn draw_shapes_from_file(input_filename: String, filename: String, hw: f64);
+    fn draw_state(
+        &mut self,
+        filename: String,
+        options: &DrawOptions,
+        only_included_in_deps: HashSet<u64>,
+    );
+    fn draw_solution(&mut self, filename: String, options: &DrawOptions);
+    fn draw_shapes(shapes: &Vec<Shape>, filename: String, options: &DrawOptions);
+    fn draw_shapes_from_file(
+        input_filename: String,
+        filename: String,
+        options: &DrawOptions,
+    ) -> Result<(), String>;
 }
 impl<'a> DrawState for Computation<'a> {
-    fn draw_state(&mut self, filename: String, hw: f64, only_included_in_deps: HashSet<u64>) {
-        let colors = [
-            "darkgray",
-            "blue",
-            "green",
-            "red",
-            "purple",
-            "brown",
-            "deepskyblue",
-            "darkcyan",
-            "maroon",
-            "lightpink",
-        ];
-        let mut document = Document::new().set("viewBox", (0, 0, 800, 800));
-
+    // When only_included_in_deps is given (i.e. a solution is drawn), the constructed shapes
+    // are labeled with the step numbers and colored per step; otherwise the color depends on
+    // the number of deps
+    fn draw_state(
+        &mut self,
+        filename: String,
+        options: &DrawOptions,
+        only_included_in_deps: HashSet<u64>,
+    ) {
+        let mut shape_indices = Vec::new();
         for i in 0..self.shape_origins.len_i32() {
-            let mut include = only_included_in_deps.is_empty();
-            for deps in &only_included_in_deps {
-                let origin = &self.shape_origins[i as usize];
-                if self.combine_deps(*deps, origin.deps, None) == *deps {
-                    include = true;
-                    break;
-                }
+            let origin_deps = self.shape_origins[i as usize].deps;
+            if only_included_in_deps.is_empty()
+                || self.is_included(origin_deps, &only_included_in_deps)
+            {
+                shape_indices.push(i);
             }
-            if !include {
-                continue;
+        }
+        let mut point_indices = Vec::new();
+        for i in 0..self.point_origins.len_i32() {
+            let origin_deps = self.point_origins[i as usize].deps;
+            if self.is_included(origin_deps, &only_included_in_deps) {
+                point_indices.push(i);
             }
+        }
+
+        let mut points: Vec<Point> = point_indices
+            .iter()
+            .map(|i| self.point_origins[*i as usize].point)
+            .collect();
+        let mut shapes: Vec<Shape> = Vec::new();
+        if !only_included_in_deps.is_empty() {
+            shapes.extend(
+                shape_indices
+                    .iter()
+                    .map(|i| self.shape_origins[*i as usize].get_shape()),
+            );
+        }
+        for element in self
+            .problem
+            .given_elements
+            .iter()
+            .chain(self.problem.elements_to_find.iter())
+        {
+            match element {
+                Element::Point(point) => points.push(*point),
+                _ => shapes.push(element.get_shape().unwrap()),
+            }
+        }
+        let viewport = options.get_viewport(&points, &shapes);
+        let mut canvas = create_canvas(
+            DrawFormat::from_filename(&filename),
+            viewport,
+            options.clip_lines,
+        );
+
+        let mut step = 0;
+        for i in shape_indices {
             let shape_origin = &self.shape_origins[i as usize];
             let deps_count = self.get_deps_count(shape_origin.deps);
-            let color = colors[(deps_count % 10) as usize];
             let stroke_width = if deps_count <= 2 {
                 3
             } else {
                 ((deps_count as i32) / 10) + 1
             };
-            document =
-                Self::draw_shape(document, &shape_origin.get_shape(), hw, color, stroke_width);
-        }
-        for i in 0..self.point_origins.len_i32() {
-            let mut include = false;
-            for deps in &only_included_in_deps {
-                let origin = &self.point_origins[i as usize];
-                if self.combine_deps(*deps, origin.deps, None) == *deps {
-                    include = true;
-                    break;
+            let name = if options.labels {
+                Some(self.get_shape_name(i))
+            } else {
+                None
+            };
+            let (color, label) = match shape_origin.element_link {
+                ElementLink::Action(_) if !only_included_in_deps.is_empty() => {
+                    step += 1;
+                    let label = match name {
+                        Some(name) => format!("{}. {}", step, name),
+                        None => step.to_string(),
+                    };
+                    (COLORS[step % 10], Some(label))
                 }
-            }
-            if !include {
-                continue;
-            }
+                _ => (COLORS[(deps_count % 10) as usize], name),
+            };
+            canvas.draw_shape(
+                &shape_origin.get_shape(),
+                color,
+                stroke_width,
+                label.as_deref(),
+            );
+        }
+        for i in point_indices {
             let point_origin = &self.point_origins[i as usize];
             let deps_count = self.get_deps_count(point_origin.deps);
-            let color = colors[(deps_count % 10) as usize];
-            document = document.add(
-                svg::node::element::Circle::new()
-                    .set("cx", Self::to_svg(point_origin.point.0, hw))
-                    .set("cy", Self::to_svg_flip(point_origin.point.1, hw))
-                    .set("r", 2)
-                    .set("fill", "black")
-                    .set("stroke", color)
-                    .set("stroke-width", 2),
-            );
+            let color = COLORS[(deps_count % 10) as usize];
+            let label = if options.labels {
+                Some(self.get_point_name(i))
+            } else {
+                None
+            };
+            canvas.draw_point(&point_origin.point, color, 2, label.as_deref());
         }
 
         let mut shapes_to_find = Vec::new();
@@ -84,7 +225,7 @@ impl<'a> DrawState for Computation<'a> {
             shapes_to_find.push(*shape);
         });
         for shape in shapes_to_find {
-            document = Self::draw_shape(document, &shape, hw, "black", 3);
+            canvas.draw_shape(&shape, BLACK, 3, None);
         }
 
         let mut points_to_find = Vec::new();
@@ -95,166 +236,704 @@ impl<'a> DrawState for Computation<'a> {
             points_to_find.push(*point);
         });
         for point in points_to_find {
-            document = document.add(
-                svg::node::element::Circle::new()
-                    .set("cx", Self::to_svg(point.0, hw))
-                    .set("cy", Self::to_svg_flip(point.1, hw))
-                    .set("r", 2)
-                    .set("fill", "black")
-                    .set("stroke", "black")
-                    .set("stroke-width", 3),
-            );
+            canvas.draw_point(&point, BLACK, 3, None);
         }
-        svg::save(filename, &document).unwrap();
+        canvas.save(filename);
     }
 
-    fn draw_solution(&mut self, filename: String, hw: f64) {
+    fn draw_solution(&mut self, filename: String, options: &DrawOptions) {
         let deps_list = match self.solution_deps {
             None => self.get_solution_deps_list(),
             Some(deps) => HashSet::from([deps]),
         };
-        self.draw_state(filename, hw, deps_list)
+        self.draw_state(filename, options, deps_list)
     }
 
-    fn draw_shapes_from_file(input_filename: String, filename: String, hw: f64) {
-        let shapes: Vec<Shape> = read_to_string(input_filename)
-            .unwrap()
-            .lines()
-            .filter_map(|s| Shape::from_str(s).ok())
-            .collect();
-        Self::draw_shapes(&shapes, filename, hw);
+    fn draw_shapes_from_file(
+        input_filename: String,
+        filename: String,
+        options: &DrawOptions,
+    ) -> Result<(), String> {
+        let shapes = read_shapes(&input_filename)?;
+        Self::draw_shapes(&shapes, filename, options);
+        Ok(())
     }
 
-    fn draw_shapes(shapes: &Vec<Shape>, filename: String, hw: f64) {
-        let colors = [
-            "darkgray",
-            "blue",
-            "green",
-            "red",
-            "purple",
-            "brown",
-            "deepskyblue",
-            "darkcyan",
-            "maroon",
-            "lightpink",
-        ];
-        let mut document = Document::new().set("viewBox", (0, 0, 800, 800));
+    fn draw_shapes(shapes: &Vec<Shape>, filename: String, options: &DrawOptions) {
+        let viewport = options.get_viewport(&[], shapes);
+        let mut canvas = create_canvas(
+            DrawFormat::from_filename(&filename),
+            viewport,
+            options.clip_lines,
+        );
 
         for i in 0..shapes.len() {
-            let color = colors[(i % 10) as usize];
-            let stroke_width = if i <= shapes.len() - 3 { 3 } else { 2 };
+            let color = COLORS[(i % 10) as usize];
+            let stroke_width = if i + 3 <= shapes.len() { 3 } else { 2 };
+            let label = (i + 1).to_string();
 
-            document = Self::draw_shape(document, &shapes[i], hw, color, stroke_width);
+            canvas.draw_shape(&shapes[i], color, stroke_width, Some(&label));
         }
-        svg::save(filename, &document).unwrap();
+        canvas.save(filename);
     }
 }
 
 mod private {
-    use svg::Document;
+    use std::collections::HashSet;
+    use std::fs::write;
+
+    use svg::node::element::Group;
+    use svg::{Document, Node};
 
-    use crate::shape::Shape;
+    use super::{DrawFormat, Viewport};
+    use crate::shape::{Point, Shape};
     use crate::Computation;
-    use crate::FInt;
 
     pub trait DrawStateHelper {
-        fn to_svg(value: FInt, hw: f64) -> i32;
-        fn to_svg_flip(value: FInt, hw: f64) -> i32;
+        fn is_included(&mut self, deps: u64, only_included_in_deps: &HashSet<u64>) -> bool;
+    }
+    impl<'a> DrawStateHelper for Computation<'a> {
+        // Whether the element is needed for one of the given deps
+        fn is_included(&mut self, deps: u64, only_included_in_deps: &HashSet<u64>) -> bool {
+            only_included_in_deps
+                .iter()
+                .any(|included| self.combine_deps(*included, deps, None) == *included)
+        }
+    }
+
+    pub struct Color {
+        pub name: &'static str,
+        pub rgb: (u8, u8, u8),
+    }
+
+    pub const BLACK: &Color = &Color {
+        name: "black",
+        rgb: (0, 0, 0),
+    };
+
+    // Used to show the inputs of a step
+    pub const HIGHLIGHT: &Color = &Color {
+        name: "orange",
+        rgb: (255, 165, 0),
+    };
+
+    // The SVG names are used in all formats (TikZ and Asymptote get them defined from RGB)
+    pub const COLORS: [&Color; 10] = [
+        &Color {
+            name: "darkgray",
+            rgb: (169, 169, 169),
+        },
+        &Color {
+            name: "blue",
+            rgb: (0, 0, 255),
+        },
+        &Color {
+            name: "green",
+            rgb: (0, 128, 0),
+        },
+        &Color {
+            name: "red",
+            rgb: (255, 0, 0),
+        },
+        &Color {
+            name: "purple",
+            rgb: (128, 0, 128),
+        },
+        &Color {
+            name: "brown",
+            rgb: (165, 42, 42),
+        },
+        &Color {
+            name: "deepskyblue",
+            rgb: (0, 191, 255),
+        },
+        &Color {
+            name: "darkcyan",
+            rgb: (0, 139, 139),
+        },
+        &Color {
+            name: "maroon",
+            rgb: (128, 0, 0),
+        },
+        &Color {
+            name: "lightpink",
+            rgb: (255, 182, 193),
+        },
+    ];
+
+    // Stroke widths are given in SVG pixels (the SVG image is 800 pixels wide)
+    pub trait Canvas {
         fn draw_shape(
-            document: Document,
+            &mut self,
             shape: &Shape,
-            hw: f64,
-            color: &str,
+            color: &Color,
             stroke_width: i32,
-        ) -> Document;
+            label: Option<&str>,
+        );
+        fn draw_point(
+            &mut self,
+            point: &Point,
+            color: &Color,
+            stroke_width: i32,
+            label: Option<&str>,
+        );
+        fn save(&self, filename: String);
     }
-    impl<'a> DrawStateHelper for Computation<'a> {
-        fn to_svg(value: FInt, hw: f64) -> i32 {
-            // (-hw, hw) -> (0, 800)
-            ((value.midpoint() + hw) * (400.0 / hw)) as i32
+
+    pub fn create_canvas(
+        format: DrawFormat,
+        viewport: Viewport,
+        clip_lines: bool,
+    ) -> Box<dyn Canvas> {
+        match format {
+            DrawFormat::Svg => Box::new(SvgCanvas::new(viewport, clip_lines)),
+            DrawFormat::Tikz => Box::new(TikzCanvas::new(viewport, clip_lines)),
+            DrawFormat::Asymptote => Box::new(AsymptoteCanvas::new(viewport, clip_lines)),
+        }
+    }
+
+    pub type Coords = (f64, f64);
+
+    // Points that have to be inside the drawing for the shape to be seen
+    pub fn get_extreme_points(shape: &Shape) -> Vec<Coords> {
+        match shape {
+            Shape::Line(line) => {
+                vec![((line.nx * line.d).midpoint(), (line.ny * line.d).midpoint())]
+            }
+            Shape::Ray(ray) => vec![(ray.a.0.midpoint(), ray.a.1.midpoint())],
+            Shape::Segment(segment) => vec![
+                (segment.a.0.midpoint(), segment.a.1.midpoint()),
+                (segment.b.0.midpoint(), segment.b.1.midpoint()),
+            ],
+            Shape::Circle(circle) => {
+                let (x, y) = (circle.c.0.midpoint(), circle.c.1.midpoint());
+                let r = circle.r2.sqrt().midpoint();
+                vec![(x - r, y - r), (x + r, y + r)]
+            }
+            Shape::Point(point) => vec![(point.0.midpoint(), point.1.midpoint())],
+        }
+    }
+
+    // The point of the line closest to the center of the viewport
+    fn get_closest_point(shape: &Shape, viewport: &Viewport) -> Coords {
+        let Shape::Line(line) = shape else {
+            panic!("Can't happen");
+        };
+        let (nx, ny, d) = (line.nx.midpoint(), line.ny.midpoint(), line.d.midpoint());
+        let t = nx * viewport.cx + ny * viewport.cy - d;
+        (viewport.cx - t * nx, viewport.cy - t * ny)
+    }
+
+    // The part of a line, ray or segment to draw: without clipping, lines and rays extend far
+    // beyond the viewport. None if nothing is left after clipping.
+    pub fn get_endpoints(
+        shape: &Shape,
+        viewport: &Viewport,
+        clip: bool,
+    ) -> Option<(Coords, Coords)> {
+        let far = 3.0 * viewport.hw;
+        let (a, b) = match shape {
+            Shape::Line(line) => {
+                let (px, py) = get_closest_point(shape, viewport);
+                let (nx, ny) = (line.nx.midpoint(), line.ny.midpoint());
+                (
+                    (px - far * ny, py + far * nx),
+                    (px + far * ny, py - far * nx),
+                )
+            }
+            Shape::Ray(ray) => {
+                let a = (ray.a.0.midpoint(), ray.a.1.midpoint());
+                let far = far + (a.0 - viewport.cx).hypot(a.1 - viewport.cy);
+                (
+                    a,
+                    (
+                        a.0 + far * ray.v.0.midpoint(),
+                        a.1 + far * ray.v.1.midpoint(),
+                    ),
+                )
+            }
+            Shape::Segment(segment) => (
+                (segment.a.0.midpoint(), segment.a.1.midpoint()),
+                (segment.b.0.midpoint(), segment.b.1.midpoint()),
+            ),
+            Shape::Circle(_) | Shape::Point(_) => panic!("Can't happen"),
+        };
+        if clip {
+            clip_segment(a, b, viewport)
+        } else {
+            Some((a, b))
+        }
+    }
+
+    // Liang-Barsky clipping of the segment (a, b) to the viewport
+    fn clip_segment(a: Coords, b: Coords, viewport: &Viewport) -> Option<(Coords, Coords)> {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let mut t0: f64 = 0.0;
+        let mut t1: f64 = 1.0;
+        let checks = [
+            (-dx, a.0 - (viewport.cx - viewport.hw)),
+            (dx, (viewport.cx + viewport.hw) - a.0),
+            (-dy, a.1 - (viewport.cy - viewport.hw)),
+            (dy, (viewport.cy + viewport.hw) - a.1),
+        ];
+        for (p, q) in checks {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return None;
+                }
+            } else if p < 0.0 {
+                t0 = t0.max(q / p);
+            } else {
+                t1 = t1.min(q / p);
+            }
+        }
+        if t0 > t1 {
+            return None;
+        }
+        Some((
+            (a.0 + t0 * dx, a.1 + t0 * dy),
+            (a.0 + t1 * dx, a.1 + t1 * dy),
+        ))
+    }
+
+    // Labels go to the point of a line closest to the center of the viewport, next to the
+    // start of a ray, at the middle of a segment and at the top of a circle
+    fn get_label_position(shape: &Shape, viewport: &Viewport) -> Coords {
+        match shape {
+            Shape::Line(_) => get_closest_point(shape, viewport),
+            Shape::Ray(ray) => (
+                (ray.a.0 + ray.v.0).midpoint(),
+                (ray.a.1 + ray.v.1).midpoint(),
+            ),
+            Shape::Segment(segment) => (
+                ((segment.a.0 + segment.b.0).midpoint()) / 2.0,
+                ((segment.a.1 + segment.b.1).midpoint()) / 2.0,
+            ),
+            Shape::Circle(circle) => (
+                circle.c.0.midpoint(),
+                (circle.c.1 + circle.r2.sqrt()).midpoint(),
+            ),
+            Shape::Point(point) => (point.0.midpoint(), point.1.midpoint()),
+        }
+    }
+
+    pub struct SvgCanvas {
+        document: Document,
+        // While a group is open, the drawn elements are added to it
+        group: Option<Group>,
+        viewport: Viewport,
+        clip_lines: bool,
+    }
+    impl SvgCanvas {
+        pub fn new(viewport: Viewport, clip_lines: bool) -> Self {
+            Self {
+                document: Document::new().set("viewBox", (0, 0, 800, 800)),
+                group: None,
+                viewport,
+                clip_lines,
+            }
+        }
+
+        pub fn start_group(&mut self, group: Group) {
+            self.end_group();
+            self.group = Some(group);
+        }
+
+        pub fn end_group(&mut self) {
+            if let Some(group) = self.group.take() {
+                self.document.append(group);
+            }
+        }
+
+        pub fn into_document(mut self) -> Document {
+            self.end_group();
+            self.document
+        }
+
+        fn scale(&self, value: f64) -> i32 {
+            (value * (400.0 / self.viewport.hw)) as i32
         }
 
-        fn to_svg_flip(value: FInt, hw: f64) -> i32 {
-            // (-hw, hw) -> (800, 0)
-            800 - Self::to_svg(value, hw)
+        fn to_svg(&self, value: f64) -> i32 {
+            // (cx - hw, cx + hw) -> (0, 800)
+            self.scale(value - self.viewport.cx + self.viewport.hw)
         }
 
+        fn to_svg_flip(&self, value: f64) -> i32 {
+            // (cy - hw, cy + hw) -> (800, 0)
+            800 - self.scale(value - self.viewport.cy + self.viewport.hw)
+        }
+
+        fn add<T: Into<Box<dyn Node>>>(&mut self, node: T) {
+            match &mut self.group {
+                Some(group) => group.append(node),
+                None => self.document.append(node),
+            }
+        }
+
+        fn add_label(&mut self, (x, y): Coords, label: &str, color: &Color) {
+            self.add(
+                svg::node::element::Text::new(label)
+                    .set("x", self.to_svg(x) + 4)
+                    .set("y", self.to_svg_flip(y) - 4)
+                    .set("font-size", 16)
+                    .set("fill", color.name),
+            );
+        }
+    }
+    impl Canvas for SvgCanvas {
         fn draw_shape(
-            document: Document,
+            &mut self,
             shape: &Shape,
-            hw: f64,
-            color: &str,
+            color: &Color,
             stroke_width: i32,
-        ) -> Document {
+            label: Option<&str>,
+        ) {
+            if let Shape::Point(point) = shape {
+                return self.draw_point(point, color, stroke_width, label);
+            }
             match shape {
-                Shape::Line(line) => document.add(
-                    svg::node::element::Line::new()
-                        .set(
-                            "x1",
-                            Self::to_svg(line.nx * line.d - FInt::new(3.0 * hw) * line.ny, hw),
-                        )
-                        .set(
-                            "y1",
-                            Self::to_svg_flip(line.ny * line.d + FInt::new(3.0 * hw) * line.nx, hw),
-                        )
-                        .set(
-                            "x2",
-                            Self::to_svg(line.nx * line.d + FInt::new(3.0 * hw) * line.ny, hw),
-                        )
-                        .set(
-                            "y2",
-                            Self::to_svg_flip(line.ny * line.d - FInt::new(3.0 * hw) * line.nx, hw),
-                        )
-                        .set("fill", "none")
-                        .set("stroke", color)
-                        .set("stroke-width", stroke_width),
-                ),
-                Shape::Ray(ray) => document.add(
-                    svg::node::element::Line::new()
-                        .set("x1", Self::to_svg(ray.a.0, hw))
-                        .set("y1", Self::to_svg_flip(ray.a.1, hw))
-                        .set(
-                            "x2",
-                            Self::to_svg(ray.a.0 + FInt::new(3.0 * hw) * ray.v.0, hw),
-                        )
-                        .set(
-                            "y2",
-                            Self::to_svg_flip(ray.a.1 + FInt::new(3.0 * hw) * ray.v.1, hw),
-                        )
-                        .set("fill", "none")
-                        .set("stroke", color)
-                        .set("stroke-width", stroke_width),
-                ),
-                Shape::Segment(segment) => document.add(
-                    svg::node::element::Line::new()
-                        .set("x1", Self::to_svg(segment.a.0, hw))
-                        .set("y1", Self::to_svg_flip(segment.a.1, hw))
-                        .set("x2", Self::to_svg(segment.b.0, hw))
-                        .set("y2", Self::to_svg_flip(segment.b.1, hw))
-                        .set("fill", "none")
-                        .set("stroke", color)
-                        .set("stroke-width", stroke_width),
-                ),
-                Shape::Circle(circle) => document
-                    .add(
-                        svg::node::element::Circle::new()
-                            .set("cx", Self::to_svg(circle.c.0, hw))
-                            .set("cy", Self::to_svg_flip(circle.c.1, hw))
-                            .set("r", Self::to_svg(circle.r2.sqrt() - FInt::new(hw), hw))
-                            .set("fill", "none")
-                            .set("stroke", color)
-                            .set("stroke-width", stroke_width),
-                    )
-                    .add(
-                        svg::node::element::Circle::new()
-                            .set("cx", Self::to_svg(circle.c.0, hw))
-                            .set("cy", Self::to_svg_flip(circle.c.1, hw))
-                            .set("r", 2)
+                Shape::Circle(circle) => {
+                    let cx = self.to_svg(circle.c.0.midpoint());
+                    let cy = self.to_svg_flip(circle.c.1.midpoint());
+                    let r = self.scale(circle.r2.sqrt().midpoint());
+                    for r in [r, 2] {
+                        self.add(
+                            svg::node::element::Circle::new()
+                                .set("cx", cx)
+                                .set("cy", cy)
+                                .set("r", r)
+                                .set("fill", "none")
+                                .set("stroke", color.name)
+                                .set("stroke-width", stroke_width),
+                        );
+                    }
+                }
+                _ => {
+                    let Some((a, b)) = get_endpoints(shape, &self.viewport, self.clip_lines) else {
+                        return;
+                    };
+                    self.add(
+                        svg::node::element::Line::new()
+                            .set("x1", self.to_svg(a.0))
+                            .set("y1", self.to_svg_flip(a.1))
+                            .set("x2", self.to_svg(b.0))
+                            .set("y2", self.to_svg_flip(b.1))
                             .set("fill", "none")
-                            .set("stroke", color)
+                            .set("stroke", color.name)
                             .set("stroke-width", stroke_width),
-                    ),
+                    );
+                }
+            }
+            if let Some(label) = label {
+                self.add_label(get_label_position(shape, &self.viewport), label, color);
+            }
+        }
+
+        fn draw_point(
+            &mut self,
+            point: &Point,
+            color: &Color,
+            stroke_width: i32,
+            label: Option<&str>,
+        ) {
+            let (x, y) = (point.0.midpoint(), point.1.midpoint());
+            self.add(
+                svg::node::element::Circle::new()
+                    .set("cx", self.to_svg(x))
+                    .set("cy", self.to_svg_flip(y))
+                    .set("r", 2)
+                    .set("fill", "black")
+                    .set("stroke", color.name)
+                    .set("stroke-width", stroke_width),
+            );
+            if let Some(label) = label {
+                self.add_label((x, y), label, color);
+            }
+        }
+
+        fn save(&self, filename: String) {
+            svg::save(filename, &self.document).unwrap();
+        }
+    }
+
+    // The picture is 10cm wide, so one SVG pixel is 1/8 mm, or about 0.36pt
+    const PT_PER_SVG_PIXEL: f64 = 0.3557;
+
+    pub struct TikzCanvas {
+        color_definitions: Vec<String>,
+        colors: Vec<&'static str>,
+        lines: Vec<String>,
+        viewport: Viewport,
+        clip_lines: bool,
+    }
+    impl TikzCanvas {
+        fn new(viewport: Viewport, clip_lines: bool) -> Self {
+            Self {
+                color_definitions: vec![],
+                colors: vec![],
+                lines: vec![],
+                viewport,
+                clip_lines,
+            }
+        }
+
+        // Defines the color on the first use (not all of the SVG names are known to xcolor)
+        fn use_color(&mut self, color: &Color) -> &'static str {
+            if !self.colors.contains(&color.name) {
+                self.colors.push(color.name);
+                let (r, g, b) = color.rgb;
+                self.color_definitions.push(format!(
+                    "\\definecolor{{{}}}{{RGB}}{{{},{},{}}}",
+                    color.name, r, g, b
+                ));
+            }
+            color.name
+        }
+
+        fn add_label(&mut self, (x, y): Coords, label: &str, color_name: &str) {
+            self.lines.push(format!(
+                "\\node[{}, above right, font=\\small] at ({:.4}, {:.4}) {{{}}};",
+                color_name, x, y, label
+            ));
+        }
+    }
+    impl Canvas for TikzCanvas {
+        fn draw_shape(
+            &mut self,
+            shape: &Shape,
+            color: &Color,
+            stroke_width: i32,
+            label: Option<&str>,
+        ) {
+            if let Shape::Point(point) = shape {
+                return self.draw_point(point, color, stroke_width, label);
+            }
+            let name = self.use_color(color);
+            let style = format!(
+                "{}, line width={:.2}pt",
+                name,
+                stroke_width as f64 * PT_PER_SVG_PIXEL
+            );
+            match shape {
+                Shape::Circle(circle) => self.lines.push(format!(
+                    "\\draw[{}] ({:.4}, {:.4}) circle[radius={:.4}];",
+                    style,
+                    circle.c.0.midpoint(),
+                    circle.c.1.midpoint(),
+                    circle.r2.sqrt().midpoint()
+                )),
+                _ => {
+                    let Some((a, b)) = get_endpoints(shape, &self.viewport, self.clip_lines) else {
+                        return;
+                    };
+                    self.lines.push(format!(
+                        "\\draw[{}] ({:.4}, {:.4}) -- ({:.4}, {:.4});",
+                        style, a.0, a.1, b.0, b.1
+                    ));
+                }
+            }
+            if let Some(label) = label {
+                self.add_label(get_label_position(shape, &self.viewport), label, name);
+            }
+        }
+
+        fn draw_point(
+            &mut self,
+            point: &Point,
+            color: &Color,
+            stroke_width: i32,
+            label: Option<&str>,
+        ) {
+            let name = self.use_color(color);
+            let (x, y) = (point.0.midpoint(), point.1.midpoint());
+            self.lines.push(format!(
+                "\\filldraw[black, draw={}, line width={:.2}pt] ({:.4}, {:.4}) circle[radius=1.5pt];",
+                name,
+                stroke_width as f64 * PT_PER_SVG_PIXEL,
+                x,
+                y
+            ));
+            if let Some(label) = label {
+                self.add_label((x, y), label, name);
             }
         }
+
+        fn save(&self, filename: String) {
+            let Viewport { cx, cy, hw } = self.viewport;
+            let mut lines = vec![format!(
+                "\\begin{{tikzpicture}}[x={:.4}cm, y={:.4}cm]",
+                5.0 / hw,
+                5.0 / hw
+            )];
+            lines.extend(self.color_definitions.iter().cloned());
+            lines.push(format!(
+                "\\clip ({:.4}, {:.4}) rectangle ({:.4}, {:.4});",
+                cx - hw,
+                cy - hw,
+                cx + hw,
+                cy + hw
+            ));
+            lines.extend(self.lines.iter().cloned());
+            lines.push("\\end{tikzpicture}".to_string());
+            write(filename, lines.join("\n") + "\n").unwrap();
+        }
+    }
+
+    pub struct AsymptoteCanvas {
+        lines: Vec<String>,
+        viewport: Viewport,
+        clip_lines: bool,
+    }
+    impl AsymptoteCanvas {
+        fn new(viewport: Viewport, clip_lines: bool) -> Self {
+            Self {
+                lines: vec![],
+                viewport,
+                clip_lines,
+            }
+        }
+
+        fn pen(color: &Color, stroke_width: i32) -> String {
+            let (r, g, b) = color.rgb;
+            format!(
+                "rgb({:.3}, {:.3}, {:.3}) + linewidth({:.2})",
+                r as f64 / 255.0,
+                g as f64 / 255.0,
+                b as f64 / 255.0,
+                stroke_width as f64 * PT_PER_SVG_PIXEL
+            )
+        }
+
+        fn add_label(&mut self, (x, y): Coords, label: &str, color: &Color) {
+            self.lines.push(format!(
+                "label(\"{}\", ({:.4}, {:.4}), NE, {});",
+                label,
+                x,
+                y,
+                Self::pen(color, 1)
+            ));
+        }
+    }
+    impl Canvas for AsymptoteCanvas {
+        fn draw_shape(
+            &mut self,
+            shape: &Shape,
+            color: &Color,
+            stroke_width: i32,
+            label: Option<&str>,
+        ) {
+            if let Shape::Point(point) = shape {
+                return self.draw_point(point, color, stroke_width, label);
+            }
+            let pen = Self::pen(color, stroke_width);
+            match shape {
+                Shape::Circle(circle) => self.lines.push(format!(
+                    "draw(circle(({:.4}, {:.4}), {:.4}), {});",
+                    circle.c.0.midpoint(),
+                    circle.c.1.midpoint(),
+                    circle.r2.sqrt().midpoint(),
+                    pen
+                )),
+                _ => {
+                    let Some((a, b)) = get_endpoints(shape, &self.viewport, self.clip_lines) else {
+                        return;
+                    };
+                    self.lines.push(format!(
+                        "draw(({:.4}, {:.4}) -- ({:.4}, {:.4}), {});",
+                        a.0, a.1, b.0, b.1, pen
+                    ));
+                }
+            }
+            if let Some(label) = label {
+                self.add_label(get_label_position(shape, &self.viewport), label, color);
+            }
+        }
+
+        fn draw_point(
+            &mut self,
+            point: &Point,
+            color: &Color,
+            stroke_width: i32,
+            label: Option<&str>,
+        ) {
+            let (x, y) = (point.0.midpoint(), point.1.midpoint());
+            self.lines.push(format!(
+                "filldraw(circle(({:.4}, {:.4}), {:.4}), black, {});",
+                x,
+                y,
+                self.viewport.hw / 200.0,
+                Self::pen(color, stroke_width)
+            ));
+            if let Some(label) = label {
+                self.add_label((x, y), label, color);
+            }
+        }
+
+        fn save(&self, filename: String) {
+            let Viewport { cx, cy, hw } = self.viewport;
+            let mut lines = vec!["size(10cm);".to_string()];
+            lines.extend(self.lines.iter().cloned());
+            lines.push(format!(
+                "clip(box(({:.4}, {:.4}), ({:.4}, {:.4})));",
+                cx - hw,
+                cy - hw,
+                cx + hw,
+                cy + hw
+            ));
+            write(filename, lines.join("\n") + "\n").unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read_to_string;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_draw_shapes_tikz() {
+        assert_eq!(DrawFormat::from_filename("solution.tex"), DrawFormat::Tikz);
+        assert_eq!(
+            DrawFormat::from_filename("solution.asy"),
+            DrawFormat::Asymptote
+        );
+        assert_eq!(DrawFormat::from_filename("solution"), DrawFormat::Svg);
+
+        let shapes = vec![
+            Shape::from_str("Line(nx=0.000,ny=1.000,d=0.000)").unwrap(),
+            Shape::from_str("Circle(c.x=0.000,c.y=0.000,r2=1.000)").unwrap(),
+        ];
+        let filename = std::env::temp_dir().join("euc_test_draw_shapes.tex");
+        let filename = filename.to_str().unwrap().to_string();
+        let options = DrawOptions {
+            half_width: Some(5.0),
+            ..DrawOptions::default()
+        };
+        Computation::draw_shapes(&shapes, filename.clone(), &options);
+        let tikz = read_to_string(&filename).unwrap();
+        std::fs::remove_file(&filename).unwrap();
+        assert!(tikz.starts_with("\\begin{tikzpicture}"));
+        assert!(tikz
+            .contains("\\draw[blue, line width=0.71pt] (0.0000, 0.0000) circle[radius=1.0000];"));
+        assert!(tikz.contains("at (0.0000, 1.0000) {2};"));
+    }
+
+    #[test]
+    fn test_viewport() {
+        let shapes = vec![
+            Shape::from_str("Line(nx=0.000,ny=1.000,d=0.000)").unwrap(),
+            Shape::from_str("Circle(c.x=4.000,c.y=0.000,r2=4.000)").unwrap(),
+        ];
+        let viewport = Viewport::fit(&[], &shapes);
+        assert_eq!((viewport.cx, viewport.cy), (3.0, 0.0));
+        assert!((viewport.hw - 3.8).abs() < 1e-9);
+
+        // With clipping, the line ends at the border of the viewport
+        let (a, b) = get_endpoints(&shapes[0], &viewport, true).unwrap();
+        assert!((a.0 + 0.8).abs() < 1e-9 && (b.0 - 6.8).abs() < 1e-9);
+        let far_line = Shape::from_str("Line(nx=0.000,ny=1.000,d=10.000)").unwrap();
+        assert_eq!(get_endpoints(&far_line, &viewport, true), None);
     }
 }
@@ -0,0 +1,494 @@
+use std::fmt::{self, Display, Formatter};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::computation::action::Action;
+use crate::computation::{Lemma, PointRef, ShapeRef, Solution, SolutionStep};
+use crate::element::Element;
+use crate::fint::FInt;
+use crate::problems::{ActionType, ProblemDefinition};
+use crate::shape::{Line, Point, Shape, ShapeTrait};
+use private::*;
+pub(super) use private::{get_given_shapes, get_point, get_shape, Similarity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Generality {
+    // The elements to find are constructed for all perturbations
+    General,
+    // The steps can be replayed, but miss the elements to find for some perturbations
+    Coincidental,
+    // The elements to find are constructed whenever the steps can be replayed, but for some
+    // perturbations a step can't be made (e.g. two circles stop intersecting)
+    Degenerate,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneralityReport {
+    pub generality: Generality,
+    pub trials: u32,
+    pub hits: u32,
+    // Seeds of the perturbations where the elements to find were missed
+    pub misses: Vec<u64>,
+    // Seeds of the perturbations where a step couldn't be made, with the reason
+    pub degenerate: Vec<(u64, String)>,
+}
+impl Display for GeneralityReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:?}: {} of {} perturbations hit the elements to find",
+            self.generality, self.hits, self.trials
+        )?;
+        for seed in &self.misses {
+            writeln!(f, "  seed {}: missed", seed)?;
+        }
+        for (seed, reason) in &self.degenerate {
+            writeln!(f, "  seed {}: {}", seed, reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl Solution {
+    // Replays the steps on instances of the problem with perturbed given elements (see
+    // ProblemDefinition::perturb()), placing the points of point actions at random. The
+    // perturbation with the seed s can be reproduced with StdRng::seed_from_u64(s).
+    pub fn check_generality(
+        &self,
+        problem: &ProblemDefinition,
+        trials: u32,
+        amount: f64,
+        seed: u64,
+    ) -> Result<GeneralityReport, String> {
+        let has_placed_points = self
+            .steps
+            .iter()
+            .any(|step| matches!(step.action_type, ActionType::PointActionType(_)));
+        if !problem.can_be_perturbed() && !has_placed_points {
+            return Err("The problem isn't symbolic, so it can't be perturbed".to_string());
+        }
+        let mut report = GeneralityReport {
+            generality: Generality::General,
+            trials,
+            hits: 0,
+            misses: vec![],
+            degenerate: vec![],
+        };
+        for trial in 0..trials {
+            let trial_seed = seed.wrapping_add(trial as u64);
+            let mut rng = StdRng::seed_from_u64(trial_seed);
+            let perturbed;
+            let instance = if problem.can_be_perturbed() {
+                perturbed = problem.perturb(&mut rng, amount)?;
+                &perturbed
+            } else {
+                problem
+            };
+            let mut place =
+                |_: &SolutionStep| [rng.random_range(-0.9..0.9), rng.random_range(-0.9..0.9)];
+            match self.replay_placing(instance, &mut place) {
+                Ok(shapes) if finds_all_elements(instance, &self.steps, &shapes) => {
+                    report.hits += 1
+                }
+                Ok(_) => report.misses.push(trial_seed),
+                Err(reason) => report.degenerate.push((trial_seed, reason)),
+            }
+        }
+        if !report.misses.is_empty() {
+            report.generality = Generality::Coincidental;
+        } else if !report.degenerate.is_empty() {
+            report.generality = Generality::Degenerate;
+        }
+        Ok(report)
+    }
+
+    // Makes the steps on another instance of the problem (with the same structure of the given
+    // elements); the intersection closest to the original one is taken. Returns the shapes of
+    // the steps.
+    pub fn replay(&self, instance: &ProblemDefinition) -> Result<Vec<Shape>, String> {
+        self.replay_placing(instance, &mut |step| step.placement.unwrap())
+    }
+
+    // Same as replay(), but the points of point actions are placed where place() tells
+    pub fn replay_placing(
+        &self,
+        instance: &ProblemDefinition,
+        place: &mut dyn FnMut(&SolutionStep) -> [f64; 2],
+    ) -> Result<Vec<Shape>, String> {
+        self.replay_on(
+            &get_given_shapes(instance),
+            &instance.lemmas,
+            place,
+            &Similarity::identity(),
+        )
+    }
+
+    // Same as replay_placing(), with the shapes of the given elements (points as Shape::point())
+    // and the lemmas used by the steps; the intersections are picked near the recorded ones moved
+    // by similarity
+    pub fn replay_on(
+        &self,
+        given: &[Shape],
+        lemmas: &[Lemma],
+        place: &mut dyn FnMut(&SolutionStep) -> [f64; 2],
+        similarity: &Similarity,
+    ) -> Result<Vec<Shape>, String> {
+        let mut shapes: Vec<Shape> = Vec::new();
+        for (k, step) in self.steps.iter().enumerate() {
+            let points = step
+                .points
+                .iter()
+                .map(|point| get_point(given, &shapes, point, similarity))
+                .collect::<Result<Vec<Point>, String>>()
+                .map_err(|reason| format!("Step {}: {}", k + 1, reason))?;
+            let step_shapes: Vec<Shape> = step
+                .shapes
+                .iter()
+                .map(|shape| get_shape(given, &shapes, shape))
+                .collect();
+            let element = match step.action_type {
+                ActionType::TwoPointActionType(value) => Some(Action::create_two_point_element(
+                    &points[0], &points[1], value,
+                )),
+                ActionType::PointAndLineActionType(value) => Some(
+                    Action::create_point_and_line_element(&points[0], &step_shapes[0], value),
+                ),
+                ActionType::ThreePointActionType(value) => {
+                    Action::create_three_point_element(&points[0], &points[1], &points[2], value)
+                }
+                ActionType::TwoPointAndLineActionType(value) => {
+                    Action::create_two_point_and_line_element(
+                        &points[0],
+                        &points[1],
+                        &step_shapes[0],
+                        value,
+                    )
+                }
+                ActionType::PointActionType(_) => {
+                    let point = Action::create_placed_point(step_shapes.first(), place(step));
+                    Some(Element::Point(point))
+                }
+                ActionType::ToolActionType(_) | ActionType::LemmaActionType(_) => None,
+            };
+            let shape = match (step.action_type, element) {
+                (ActionType::ToolActionType(value), _) => {
+                    value.get_tool().construct(&points, &step_shapes)
+                }
+                (ActionType::LemmaActionType(value), _) => {
+                    lemmas[value.lemma as usize].apply(&points, &step_shapes, value.output as usize)
+                }
+                (_, Some(Element::Point(point))) => Some(Shape::point(point)),
+                (_, element) => element.and_then(|element| element.get_shape()),
+            };
+            match shape {
+                Some(shape) if shape.well_formed() => shapes.push(shape),
+                _ => return Err(format!("Step {}: the action can't be made", k + 1)),
+            }
+        }
+        Ok(shapes)
+    }
+}
+
+mod private {
+    use super::*;
+
+    // The given elements as shapes, with points as Shape::point()
+    pub fn get_given_shapes(instance: &ProblemDefinition) -> Vec<Shape> {
+        instance
+            .given_elements
+            .iter()
+            .map(|element| match element {
+                Element::Point(point) => Shape::point(*point),
+                _ => element.get_shape().unwrap(),
+            })
+            .collect()
+    }
+
+    pub fn get_shape(given: &[Shape], shapes: &[Shape], shape: &ShapeRef) -> Shape {
+        match shape {
+            ShapeRef::Given(i) => given[*i],
+            ShapeRef::Step(i) => shapes[*i],
+        }
+    }
+
+    // Of the intersections of two shapes, the one closest to where the recorded point is moved
+    // by similarity
+    pub fn get_point(
+        given: &[Shape],
+        shapes: &[Shape],
+        point: &PointRef,
+        similarity: &Similarity,
+    ) -> Result<Point, String> {
+        match point {
+            PointRef::Given(i) => given[*i]
+                .as_point()
+                .ok_or(format!("Given element {} isn't a point", i)),
+            PointRef::Intersection {
+                shapes: refs,
+                point,
+            } => {
+                let shape1 = get_shape(given, shapes, &refs[0]);
+                let shape2 = get_shape(given, shapes, &refs[1]);
+                let point = similarity.apply(point);
+                shape1
+                    .find_intersection_points(&shape2)
+                    .into_iter()
+                    .flatten()
+                    // Squared distances, the square root of an interval around 0 is NaN
+                    .min_by(|p1, p2| {
+                        let d1 = ((p1.0 - point.0).sqr() + (p1.1 - point.1).sqr()).midpoint();
+                        let d2 = ((p2.0 - point.0).sqr() + (p2.1 - point.1).sqr()).midpoint();
+                        d1.total_cmp(&d2)
+                    })
+                    .ok_or(format!("{} and {} don't intersect", refs[0], refs[1]))
+            }
+            PointRef::Placed { step, .. } => Ok(shapes[*step].as_point().unwrap()),
+        }
+    }
+
+    // Rotation, scaling and translation taking the recorded instance of a solution to another
+    // one: p -> to + rotation * (p - from), with rotation as a complex number
+    #[derive(Debug, Clone, Copy)]
+    pub struct Similarity {
+        from: Point,
+        to: Point,
+        rotation: Point,
+    }
+
+    impl Similarity {
+        pub fn identity() -> Similarity {
+            let zero = Point(FInt::new(0.0), FInt::new(0.0));
+            Similarity {
+                from: zero,
+                to: zero,
+                rotation: Point(FInt::new(1.0), FInt::new(0.0)),
+            }
+        }
+
+        // The similarity taking the characteristic points of the shapes from to the ones of the
+        // corresponding shapes to (see get_anchors()); a translation if there's only one point
+        pub fn between(from: &[Shape], to: &[Shape]) -> Similarity {
+            let (from, to) = (get_anchors(from), get_anchors(to));
+            if from.is_empty() || from.len() != to.len() {
+                return Similarity::identity();
+            }
+            let mut similarity = Similarity {
+                from: from[0],
+                to: to[0],
+                ..Similarity::identity()
+            };
+            let second = (1..from.len()).find(|i| from[*i].distance_to(&from[0]).midpoint() > 1e-6);
+            if let Some(i) = second {
+                let (a, b) = (sub(&from[i], &from[0]), sub(&to[i], &to[0]));
+                let norm = a.0.sqr() + a.1.sqr();
+                similarity.rotation = Point(
+                    (b.0 * a.0 + b.1 * a.1) / norm,
+                    (b.1 * a.0 - b.0 * a.1) / norm,
+                );
+            }
+            similarity
+        }
+
+        pub fn apply(&self, point: &Point) -> Point {
+            let (p, r) = (sub(point, &self.from), self.rotation);
+            Point(
+                self.to.0 + r.0 * p.0 - r.1 * p.1,
+                self.to.1 + r.1 * p.0 + r.0 * p.1,
+            )
+        }
+    }
+
+    fn sub(a: &Point, b: &Point) -> Point {
+        Point(a.0 - b.0, a.1 - b.1)
+    }
+
+    // Points fixed by the shapes: the points, the centers of the circles and the ends of the
+    // rays and segments. Lines add the intersection of the first two if there's no such point,
+    // and the feet of the perpendiculars from the first point if there's only one.
+    fn get_anchors(shapes: &[Shape]) -> Vec<Point> {
+        let mut anchors: Vec<Point> = Vec::new();
+        let mut lines: Vec<Line> = Vec::new();
+        for shape in shapes {
+            match shape {
+                Shape::Point(point) => anchors.push(*point),
+                Shape::Circle(circle) => anchors.push(circle.c),
+                Shape::Ray(ray) => anchors.push(ray.a),
+                Shape::Segment(segment) => anchors.extend([segment.a, segment.b]),
+                Shape::Line(line) => lines.push(*line),
+            }
+        }
+        if anchors.is_empty() && lines.len() >= 2 {
+            let [intersection, _] = lines[0].find_intersection_points(&Shape::Line(lines[1]));
+            anchors.extend(intersection);
+        }
+        if anchors.len() == 1 {
+            let p = anchors[0];
+            for line in &lines {
+                let distance = line.nx * p.0 + line.ny * p.1 - line.d;
+                anchors.push(Point(p.0 - distance * line.nx, p.1 - distance * line.ny));
+            }
+        }
+        anchors
+    }
+
+    // Whether the elements to find are among the given elements, the shapes of the steps, the
+    // points made by tools and lemmas, and the intersections (placed points are generic, so
+    // they don't count)
+    pub fn finds_all_elements(
+        instance: &ProblemDefinition,
+        steps: &[SolutionStep],
+        shapes: &[Shape],
+    ) -> bool {
+        let mut all_shapes: Vec<Shape> = instance
+            .given_elements
+            .iter()
+            .filter_map(|element| element.get_shape())
+            .collect();
+        all_shapes.extend(shapes.iter().filter(|shape| shape.as_point().is_none()));
+        let mut points: Vec<Point> = instance
+            .given_elements
+            .iter()
+            .filter_map(|element| match element {
+                Element::Point(point) => Some(*point),
+                _ => None,
+            })
+            .collect();
+        points.extend(
+            steps
+                .iter()
+                .zip(shapes)
+                .filter(|(step, _)| !matches!(step.action_type, ActionType::PointActionType(_)))
+                .filter_map(|(_, shape)| shape.as_point()),
+        );
+        instance
+            .elements_to_find
+            .iter()
+            .all(|element| match element {
+                Element::Point(target) => {
+                    points.iter().any(|point| point.almost_equals(target))
+                        || all_shapes.iter().enumerate().any(|(i, shape1)| {
+                            all_shapes[i + 1..].iter().any(|shape2| {
+                                shape1
+                                    .find_intersection_points(shape2)
+                                    .into_iter()
+                                    .flatten()
+                                    .any(|point| point.almost_equals(target))
+                            })
+                        })
+                }
+                _ => {
+                    let target = element.get_shape().unwrap();
+                    shapes.iter().any(|shape| shape.almost_equals(&target))
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computation::test_helpers::{solve_problem, solve_with};
+    use crate::computation::SolveOptions;
+    use crate::element::{CircleCR, ReflectionPAV};
+    use crate::fint::FInt;
+    use crate::problems::{
+        Parameter, PointActionType, ProblemElements, SymbolicProblem, TwoPointActionType,
+    };
+
+    #[test]
+    fn test_check_generality() {
+        let (problem, mut solutions) = solve_problem(
+            "2.8-tangent-to-circle-at-point-with-perp",
+            &SolveOptions::without_files(),
+        );
+        let solution = solutions.remove(0);
+        let report = solution.check_generality(&problem, 20, 0.2, 0).unwrap();
+        assert_eq!(report.generality, Generality::General);
+        assert_eq!(report.hits, 20);
+
+        // The perpendicular through the center instead of the point on the circle misses
+        let mut wrong = solution.clone();
+        wrong.steps[1].points[0] = PointRef::Given(2);
+        let report = wrong.check_generality(&problem, 20, 0.2, 0).unwrap();
+        assert_eq!(report.generality, Generality::Coincidental);
+    }
+
+    #[test]
+    fn test_placed_points() {
+        // The center of a circle: two points on it, the diameter through their midpoint,
+        // and the perpendicular bisector of the diameter's ends
+        let c = Point(FInt::new(0.13), FInt::new(-0.21));
+        let (problem, mut solutions) =
+            solve_with("2.8-tangent-to-circle-at-point-with-perp", |problem| {
+                problem.given_elements = vec![Element::CircleCR(CircleCR {
+                    c,
+                    r: FInt::new(1.0),
+                })];
+                problem.elements_to_find = vec![Element::Point(c)];
+                problem.action_count = 4;
+                problem.action_types = vec![
+                    ActionType::PointActionType(PointActionType::OnShape),
+                    ActionType::TwoPointActionType(TwoPointActionType::MidPerp),
+                ]
+                .into();
+                problem.multimatch = true;
+                problem.symbolic = None;
+            });
+        let solution = solutions.remove(0);
+        assert_eq!(solution.step_count, 4);
+        let placed = solution
+            .steps
+            .iter()
+            .filter(|step| {
+                step.action_type == ActionType::PointActionType(PointActionType::OnShape)
+            })
+            .count();
+        assert_eq!(placed, 2);
+        let script = solution.to_geogebra_script(&problem);
+        assert!(script.contains("s1 = ClosestPoint(g0, "));
+
+        // The construction works wherever the points are placed
+        let report = solution.check_generality(&problem, 20, 0.2, 0).unwrap();
+        assert_eq!(report.generality, Generality::General);
+    }
+
+    #[test]
+    fn test_tool_points() {
+        // The reflection of a point across a line through two other points: the point made by
+        // the tool is found for all perturbations
+        fn build(values: &[f64]) -> Option<ProblemElements> {
+            let pt = |i: usize| Point(FInt::new(values[i]), FInt::new(values[i + 1]));
+            let (p, l1, l2) = (pt(0), pt(2), pt(4));
+            let v = Point(l2.0 - l1.0, l2.1 - l1.1);
+            Some((
+                vec![Element::Point(p), Element::Point(l1), Element::Point(l2)],
+                vec![Element::Point(ReflectionPAV { p, a: l1, v }.get_point())],
+            ))
+        }
+        static SYMBOLIC: SymbolicProblem = SymbolicProblem {
+            parameters: &[
+                Parameter::new("px", -1.0, -1.2, -0.8),
+                Parameter::new("py", 0.1, -0.1, 0.3),
+                Parameter::new("l1x", -0.8, -1.0, -0.6),
+                Parameter::new("l1y", -0.6, -0.8, -0.4),
+                Parameter::new("l2x", 0.9, 0.7, 1.1),
+                Parameter::new("l2y", 0.3, 0.1, 0.5),
+            ],
+            build,
+        };
+        let (problem, mut solutions) = solve_with("1.3-midpoint", |problem| {
+            problem.action_count = 2;
+            problem.action_types = ["LINE", "REFLECTION"]
+                .map(|name| name.parse().unwrap())
+                .to_vec()
+                .into();
+            problem.multimatch = true;
+            *problem = ProblemDefinition::from_symbolic(&SYMBOLIC, problem.clone());
+        });
+        let solution = solutions.remove(0);
+        let report = solution.check_generality(&problem, 5, 0.2, 0).unwrap();
+        assert_eq!(report.generality, Generality::General);
+        assert_eq!(report.hits, 5);
+    }
+}
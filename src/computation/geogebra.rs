@@ -0,0 +1,416 @@
+use std::fs::write;
+
+use crate::computation::{PointRef, ShapeRef, Solution};
+use crate::element::Element;
+use crate::problems::{
+    ActionType, PointActionType, PointAndLineActionType, ProblemDefinition, ThreePointActionType,
+    TwoPointActionType, TwoPointAndLineActionType,
+};
+use crate::shape::{Line, Point, Shape};
+use private::*;
+
+impl Solution {
+    // Writes the construction as GeoGebra commands, one per line (they can be pasted into the
+    // input bar). Given elements become free objects, so dragging them shows whether the
+    // construction generalizes.
+    pub fn to_geogebra_script(&self, problem: &ProblemDefinition) -> String {
+        if self
+            .steps
+            .iter()
+            .any(|step| matches!(step.action_type, ActionType::LemmaActionType(_)))
+        {
+            let expanded = self
+                .expand_lemmas(problem)
+                .expect("The steps of a solution can be replayed");
+            return expanded.to_geogebra_script(problem);
+        }
+        let mut script = GeoGebraScript::new(problem, self);
+        for (i, element) in problem.given_elements.iter().enumerate() {
+            script.add_given_element(i, element);
+        }
+        for (i, step) in self.steps.iter().enumerate() {
+            let points: Vec<String> = step
+                .points
+                .iter()
+                .map(|point| script.get_point_name(point))
+                .collect();
+            let shapes: Vec<String> = step.shapes.iter().map(get_shape_name).collect();
+            let command = match step.action_type {
+                ActionType::TwoPointAndLineActionType(value) => {
+                    let vertex = match value {
+                        TwoPointAndLineActionType::BisectorPosCAL
+                        | TwoPointAndLineActionType::BisectorNegCAL => 0,
+                        _ => 1,
+                    };
+                    let point = self.get_point(problem, &step.points[vertex]);
+                    let line = self.get_shape(problem, &step.shapes[0]);
+                    get_two_point_and_line_command(value, &points, &shapes, &point, &line)
+                }
+                ActionType::ToolActionType(value) => {
+                    let input_shapes: Vec<Shape> = step
+                        .shapes
+                        .iter()
+                        .map(|shape| self.get_shape(problem, shape))
+                        .collect();
+                    let name = value.get_tool().name();
+                    get_tool_command(name, &points, &shapes, &input_shapes, &step.shape)
+                }
+                // The placed point is bound to the shape at the place where it was put
+                ActionType::PointActionType(value) => {
+                    let point = coords(&step.shape.as_point().unwrap());
+                    match value {
+                        PointActionType::OnShape => {
+                            format!("ClosestPoint({}, {})", shapes[0], point)
+                        }
+                        _ => point,
+                    }
+                }
+                _ => get_command(step.action_type, &points, &shapes),
+            };
+            script.lines.push(format!("s{} = {}", i + 1, command));
+        }
+        script.lines.join("\n") + "\n"
+    }
+
+    pub fn save_geogebra_script(&self, problem: &ProblemDefinition, filename: String) {
+        write(filename, self.to_geogebra_script(problem)).unwrap();
+    }
+}
+
+mod private {
+    use super::*;
+
+    pub struct GeoGebraScript<'b> {
+        pub lines: Vec<String>,
+        problem: &'b ProblemDefinition,
+        solution: &'b Solution,
+        intersections: Vec<([ShapeRef; 2], Point)>,
+        free_points: Vec<(Point, String)>,
+    }
+    impl<'b> GeoGebraScript<'b> {
+        pub fn new(problem: &'b ProblemDefinition, solution: &'b Solution) -> Self {
+            Self {
+                lines: vec![],
+                problem,
+                solution,
+                intersections: vec![],
+                free_points: vec![],
+            }
+        }
+
+        pub fn add_given_element(&mut self, i: usize, element: &Element) {
+            let command = match element {
+                Element::Point(point) => {
+                    self.add_free_point(format!("G{}", i), point);
+                    return;
+                }
+                Element::LineAB(line) => {
+                    self.add_free_points(i, &[line.a, line.b]);
+                    format!("Line(G{i}a, G{i}b)")
+                }
+                Element::LineAV(line) => {
+                    self.add_free_points(i, &[line.a, shifted(&line.a, &line.v)]);
+                    format!("Line(G{i}a, G{i}b)")
+                }
+                Element::RayAV(ray) => {
+                    self.add_free_points(i, &[ray.a, shifted(&ray.a, &ray.v)]);
+                    format!("Ray(G{i}a, G{i}b)")
+                }
+                Element::SegmentAB(segment) => {
+                    self.add_free_points(i, &[segment.a, segment.b]);
+                    format!("Segment(G{i}a, G{i}b)")
+                }
+                Element::CircleCP(circle) => {
+                    self.add_free_points(i, &[circle.c, circle.p]);
+                    format!("Circle(G{i}a, G{i}b)")
+                }
+                Element::CircleCR(circle) => {
+                    self.add_free_points(i, &[circle.c]);
+                    self.lines
+                        .push(format!("G{}r = {}", i, number(circle.r.midpoint())));
+                    format!("Circle(G{i}a, G{i}r)")
+                }
+                Element::MidPerpAB(line) => {
+                    self.add_free_points(i, &[line.a, line.b]);
+                    format!("PerpendicularBisector(G{i}a, G{i}b)")
+                }
+                Element::BisectorCVV(line) => {
+                    self.add_free_points(
+                        i,
+                        &[
+                            line.c,
+                            shifted(&line.c, &line.v1),
+                            shifted(&line.c, &line.v2),
+                        ],
+                    );
+                    format!("AngleBisector(G{i}b, G{i}a, G{i}c)")
+                }
+                Element::TangentPCR(line) => {
+                    self.add_free_points(i, &[line.p, line.c]);
+                    self.lines
+                        .push(format!("G{}r = {}", i, number(line.r.midpoint())));
+                    let touch = touch_point(&line.get_shape(), &line.c);
+                    get_tangent_command(
+                        &format!("G{}a", i),
+                        &format!("Circle(G{i}b, G{i}r)"),
+                        &touch,
+                    )
+                }
+                Element::CircleABC(circle) => {
+                    self.add_free_points(i, &[circle.a, circle.b, circle.c]);
+                    format!("Circle(G{i}a, G{i}b, G{i}c)")
+                }
+                Element::ReflectionPAV(point) => {
+                    self.add_free_points(i, &[point.p, point.a, shifted(&point.a, &point.v)]);
+                    format!("Reflect(G{i}a, Line(G{i}b, G{i}c))")
+                }
+                Element::EquilateralAB(point) => {
+                    self.add_free_points(i, &[point.a, point.b]);
+                    format!("Rotate(G{i}b, 60°, G{i}a)")
+                }
+            };
+            self.lines.push(format!("g{} = {}", i, command));
+        }
+
+        // Defining points of a given element: G<i>a, G<i>b, ...
+        fn add_free_points(&mut self, i: usize, points: &[Point]) {
+            for (j, point) in points.iter().enumerate() {
+                let suffix = (b'a' + j as u8) as char;
+                self.add_free_point(format!("G{}{}", i, suffix), point);
+            }
+        }
+
+        // A point coinciding with an earlier one is bound to it, so that both move together
+        fn add_free_point(&mut self, name: String, point: &Point) {
+            let existing = self.free_points.iter().find(|(p, _)| p == point);
+            let definition = match existing {
+                Some((_, existing_name)) => existing_name.clone(),
+                None => coords(point),
+            };
+            self.lines.push(format!("{} = {}", name, definition));
+            self.free_points.push((*point, name));
+        }
+
+        // Adds a command for an intersection point the first time it's used
+        pub fn get_point_name(&mut self, point: &PointRef) -> String {
+            let (shapes, point) = match point {
+                PointRef::Given(i) => return format!("G{}", i),
+                PointRef::Placed { step, .. } => return format!("s{}", step + 1),
+                PointRef::Intersection { shapes, point } => (*shapes, *point),
+            };
+            let existing = self
+                .intersections
+                .iter()
+                .position(|(s, p)| *s == shapes && *p == point);
+            if let Some(index) = existing {
+                return format!("P{}", index + 1);
+            }
+            let both_lines = shapes.iter().all(|shape| {
+                let shape = self.solution.get_shape(self.problem, shape);
+                shape.get_direction().is_some()
+            });
+            // A line and a circle or two circles have two intersections: the one closest to
+            // the found point is picked
+            let command = if both_lines {
+                format!(
+                    "Intersect({}, {})",
+                    get_shape_name(&shapes[0]),
+                    get_shape_name(&shapes[1])
+                )
+            } else {
+                format!(
+                    "Intersect({}, {}, {})",
+                    get_shape_name(&shapes[0]),
+                    get_shape_name(&shapes[1]),
+                    coords(&point)
+                )
+            };
+            self.intersections.push((shapes, point));
+            let name = format!("P{}", self.intersections.len());
+            self.lines.push(format!("{} = {}", name, command));
+            name
+        }
+    }
+
+    // The macros of TOOLS, by name
+    pub fn get_tool_command(
+        name: &str,
+        p: &[String],
+        s: &[String],
+        shapes: &[Shape],
+        result: &Shape,
+    ) -> String {
+        match name {
+            // construct() only makes lines from circles, GeoGebra's own command gives both tangents
+            "TANGENT_POS" | "TANGENT_NEG" => match (&shapes[0], result) {
+                (Shape::Circle(circle), Shape::Line(line)) => {
+                    get_tangent_command(&p[0], &s[0], &touch_point(line, &circle.c))
+                }
+                _ => format!("Tangent({}, {})", p[0], s[0]),
+            },
+            "CIRCUMCIRCLE" => format!("Circle({}, {}, {})", p[0], p[1], p[2]),
+            "REFLECTION" => format!("Reflect({}, {})", p[0], s[0]),
+            // Rotating b around a counterclockwise gives the apex to the left of ab
+            "EQUILATERAL_POS" => format!("Rotate({}, 60°, {})", p[1], p[0]),
+            "EQUILATERAL_NEG" => format!("Rotate({}, 60°, {})", p[0], p[1]),
+            _ => panic!("No GeoGebra command for {}", name),
+        }
+    }
+
+    // The point of tangency is the intersection of the circle and the circle on the segment from
+    // the point to the center that is closest to the given one
+    pub fn get_tangent_command(p: &str, circle: &str, touch: &Point) -> String {
+        format!(
+            "Line({p}, Intersect({circle}, Circle(Midpoint({p}, Center({circle})), {p}), {}))",
+            coords(touch)
+        )
+    }
+
+    // The foot of the perpendicular from the center to the tangent
+    pub fn touch_point(line: &Line, center: &Point) -> Point {
+        let k =
+            (line.d - line.nx * center.0 - line.ny * center.1) / (line.nx.sqr() + line.ny.sqr());
+        Point(center.0 + line.nx * k, center.1 + line.ny * k)
+    }
+
+    pub fn get_shape_name(shape: &ShapeRef) -> String {
+        match shape {
+            ShapeRef::Given(i) => format!("g{}", i),
+            ShapeRef::Step(i) => format!("s{}", i + 1),
+        }
+    }
+
+    pub fn coords(point: &Point) -> String {
+        format!(
+            "({}, {})",
+            number(point.0.midpoint()),
+            number(point.1.midpoint())
+        )
+    }
+
+    fn number(x: f64) -> String {
+        let s = format!("{:.10}", x);
+        let s = s.trim_end_matches('0').trim_end_matches('.');
+        if s == "-0" {
+            "0".to_string()
+        } else {
+            s.to_string()
+        }
+    }
+
+    fn shifted(point: &Point, v: &Point) -> Point {
+        Point(point.0 + v.0, point.1 + v.1)
+    }
+
+    pub fn get_command(action_type: ActionType, p: &[String], s: &[String]) -> String {
+        match action_type {
+            ActionType::TwoPointActionType(value) => match value {
+                TwoPointActionType::Line => format!("Line({}, {})", p[0], p[1]),
+                TwoPointActionType::Circle12 => format!("Circle({}, {})", p[0], p[1]),
+                TwoPointActionType::Circle21 => format!("Circle({}, {})", p[1], p[0]),
+                TwoPointActionType::MidPerp => {
+                    format!("PerpendicularBisector({}, {})", p[0], p[1])
+                }
+                TwoPointActionType::Last => panic!("Can't happen"),
+            },
+            ActionType::PointAndLineActionType(value) => match value {
+                PointAndLineActionType::Perp => format!("PerpendicularLine({}, {})", p[0], s[0]),
+                PointAndLineActionType::Par => format!("Line({}, {})", p[0], s[0]),
+                PointAndLineActionType::Last => panic!("Can't happen"),
+            },
+            ActionType::ThreePointActionType(value) => match value {
+                ThreePointActionType::CircleCAB => {
+                    format!("Circle({}, Distance({}, {}))", p[0], p[1], p[2])
+                }
+                ThreePointActionType::CircleACB => {
+                    format!("Circle({}, Distance({}, {}))", p[1], p[0], p[2])
+                }
+                ThreePointActionType::CircleABC => {
+                    format!("Circle({}, Distance({}, {}))", p[2], p[0], p[1])
+                }
+                ThreePointActionType::BisectorCAB => {
+                    format!("AngleBisector({}, {}, {})", p[1], p[0], p[2])
+                }
+                ThreePointActionType::BisectorACB => {
+                    format!("AngleBisector({}, {}, {})", p[0], p[1], p[2])
+                }
+                ThreePointActionType::BisectorABC => {
+                    format!("AngleBisector({}, {}, {})", p[0], p[2], p[1])
+                }
+                ThreePointActionType::Last => panic!("Can't happen"),
+            },
+            // Lemmas are expanded into their steps (see Solution::expand_lemmas())
+            ActionType::TwoPointAndLineActionType(_)
+            | ActionType::PointActionType(_)
+            | ActionType::ToolActionType(_)
+            | ActionType::LemmaActionType(_) => panic!("Can't happen"),
+        }
+    }
+
+    // The vertex lies on the line, and the second side of the angle goes along the line. The side
+    // is fixed by the point of the line closest to where the direction used by the action points.
+    pub fn get_two_point_and_line_command(
+        action_type: TwoPointAndLineActionType,
+        p: &[String],
+        s: &[String],
+        vertex: &Point,
+        line: &Shape,
+    ) -> String {
+        let direction = line.get_direction().unwrap();
+        let (vertex_index, other_index, side) = match action_type {
+            TwoPointAndLineActionType::BisectorPosCAL => (0, 1, direction),
+            TwoPointAndLineActionType::BisectorPosACL => (1, 0, direction),
+            TwoPointAndLineActionType::BisectorNegCAL => {
+                (0, 1, Point(direction.0.negate(), direction.1.negate()))
+            }
+            TwoPointAndLineActionType::BisectorNegACL => {
+                (1, 0, Point(direction.0.negate(), direction.1.negate()))
+            }
+            TwoPointAndLineActionType::Last => panic!("Can't happen"),
+        };
+        format!(
+            "AngleBisector({}, {}, ClosestPoint({}, {}))",
+            p[other_index],
+            p[vertex_index],
+            s[0],
+            coords(&shifted(vertex, &side))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::private::get_tool_command;
+    use crate::computation::test_helpers::solve_problem;
+    use crate::computation::{SolveOptions, TOOLS};
+    use crate::fint::FInt;
+    use crate::shape::{Point, Shape};
+
+    #[test]
+    fn test_geogebra_script() {
+        let (problem, solutions) = solve_problem(
+            "2.8-tangent-to-circle-at-point-with-perp",
+            &SolveOptions::without_files(),
+        );
+        assert_eq!(
+            solutions[0].to_geogebra_script(&problem),
+            "G0a = (0, 0)\n\
+             G0b = (1, 0)\n\
+             g0 = Circle(G0a, G0b)\n\
+             G1 = G0b\n\
+             G2 = G0a\n\
+             s1 = Line(G1, G2)\n\
+             s2 = PerpendicularLine(G1, s1)\n"
+        );
+    }
+
+    // get_tool_command() maps the macros by name, so each one needs an entry there
+    #[test]
+    fn test_tool_commands() {
+        let names = ["A", "B", "C"].map(String::from);
+        let point = Shape::point(Point(FInt::new(0.0), FInt::new(0.0)));
+        for tool in TOOLS {
+            get_tool_command(tool.name(), &names, &names, &[point], &point);
+        }
+    }
+}
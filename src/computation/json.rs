@@ -0,0 +1,144 @@
+use std::fs::write;
+
+use crate::computation::Solution;
+use crate::Computation;
+use private::*;
+
+pub trait JsonState {
+    fn state_to_json(&self) -> String;
+    fn save_state_json(&self, filename: String);
+    fn solutions_to_json(solutions: &[Solution]) -> String;
+    fn save_solutions_json(solutions: &[Solution], filename: String);
+}
+impl<'a> JsonState for Computation<'a> {
+    fn state_to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.get_state_json()).unwrap()
+    }
+
+    fn save_state_json(&self, filename: String) {
+        write(filename, self.state_to_json()).unwrap();
+    }
+
+    fn solutions_to_json(solutions: &[Solution]) -> String {
+        serde_json::to_string_pretty(solutions).unwrap()
+    }
+
+    fn save_solutions_json(solutions: &[Solution], filename: String) {
+        write(filename, Self::solutions_to_json(solutions)).unwrap();
+    }
+}
+
+mod private {
+    use serde::Serialize;
+
+    use crate::computation::action::ElementLink;
+    use crate::computation::print::PrintStateHelper;
+    use crate::element::Element;
+    use crate::problems::ActionType;
+    use crate::shape::{Point, Shape};
+    use crate::{Computation, VecLengths};
+
+    // Deps are written as the list of shape indices they contain
+    #[derive(Serialize)]
+    pub struct StateJson<'b> {
+        pub point_origins: Vec<PointOriginJson>,
+        pub shape_origins: Vec<ShapeOriginJson<'b>>,
+        pub points_to_find: Vec<Point>,
+        pub shapes_to_find: Vec<Shape>,
+        pub found_points: Vec<Point>,
+        pub found_shapes: Vec<Shape>,
+        pub solution_deps: Option<Vec<u32>>,
+    }
+
+    #[derive(Serialize)]
+    pub struct PointOriginJson {
+        pub index: i32,
+        pub name: String,
+        pub point: Point,
+        pub deps: Vec<u32>,
+        pub shape_origin_indices: [i32; 2],
+        pub found_shape_mask: u32,
+        pub next: i32,
+    }
+
+    #[derive(Serialize)]
+    pub struct ShapeOriginJson<'b> {
+        pub index: i32,
+        pub name: String,
+        pub shape: Shape,
+        pub deps: Vec<u32>,
+        pub link: ElementLinkJson<'b>,
+        pub found_shape_mask: u32,
+        pub next: i32,
+    }
+
+    #[derive(Serialize)]
+    #[serde(tag = "type")]
+    pub enum ElementLinkJson<'b> {
+        Given {
+            element: &'b Element,
+        },
+        Action {
+            action_type: ActionType,
+            point_indices: Vec<i32>,
+            shape_indices: Vec<i32>,
+            priority: i32,
+        },
+    }
+
+    pub trait JsonStateHelper {
+        fn get_state_json(&self) -> StateJson<'_>;
+    }
+    impl<'a> JsonStateHelper for Computation<'a> {
+        fn get_state_json(&self) -> StateJson<'_> {
+            let point_origins = (0..self.point_origins.len_i32())
+                .map(|i| {
+                    let origin = &self.point_origins[i as usize];
+                    PointOriginJson {
+                        index: i,
+                        name: self.get_point_name(i),
+                        point: origin.point,
+                        deps: self.get_deps_indices(origin.deps),
+                        shape_origin_indices: origin.shape_origin_indices,
+                        found_shape_mask: origin.found_shape_mask,
+                        next: origin.next,
+                    }
+                })
+                .collect();
+            let shape_origins = (0..self.shape_origins.len_i32())
+                .map(|i| {
+                    let origin = &self.shape_origins[i as usize];
+                    let link = match &origin.element_link {
+                        ElementLink::GivenElement { element, .. } => {
+                            ElementLinkJson::Given { element }
+                        }
+                        ElementLink::Action(action) => ElementLinkJson::Action {
+                            action_type: action.action_type,
+                            point_indices: action.get_point_indices(),
+                            shape_indices: action.get_shape_indices(),
+                            priority: action.priority,
+                        },
+                    };
+                    ShapeOriginJson {
+                        index: i,
+                        name: self.get_shape_name(i),
+                        shape: origin.get_shape(),
+                        deps: self.get_deps_indices(origin.deps),
+                        link,
+                        found_shape_mask: origin.found_shape_mask,
+                        next: origin.next,
+                    }
+                })
+                .collect();
+            StateJson {
+                point_origins,
+                shape_origins,
+                points_to_find: self.points_to_find.as_vector(),
+                shapes_to_find: self.shapes_to_find.as_vector(),
+                found_points: self.found_points.as_vector(),
+                found_shapes: self.found_shapes.as_vector(),
+                solution_deps: self.solution_deps.map(|deps| self.get_deps_indices(deps)),
+            }
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use crate::computation::generality::{get_given_shapes, get_point, get_shape, Similarity};
+use crate::computation::{InputKind, PointRef, ShapeRef, Solution, SolutionStep};
+use crate::problems::{ActionType, LemmaActionType, ProblemDefinition};
+use crate::shape::{Point, Shape};
+use crate::SolveOptions;
+use private::*;
+
+// A compound tool recorded from the solution of a problem: the given elements used by the
+// solution become its inputs (points first, then lines and circles) and the shapes to find its
+// outputs. Applying it replays the solution with the inputs in place of the given elements.
+#[derive(Debug, Clone)]
+pub struct Lemma {
+    pub name: String,
+    pub solution: Solution,
+    // Shapes of the given elements of the recorded problem (points as Shape::point())
+    given: Vec<Shape>,
+    // Indices of the given elements used as inputs
+    pub inputs: Vec<usize>,
+    pub point_count: usize,
+    pub input_kinds: Vec<InputKind>,
+    // Steps making the shapes to find, in the order of elements_to_find
+    pub outputs: Vec<usize>,
+    pub e_moves: u32,
+    // The lemmas of the recorded problem, for the steps using them
+    lemmas: Vec<Lemma>,
+}
+
+impl Lemma {
+    // Solves the problem and records the first solution as a lemma
+    pub fn record(problem: &ProblemDefinition, name: &str) -> Result<Lemma, String> {
+        crate::solve(problem, &SolveOptions::without_files())
+            .first()
+            .ok_or(format!("No solution found for lemma {}", name))?
+            .to_lemma(problem, name)
+    }
+
+    pub fn get_shape_count(&self) -> usize {
+        self.inputs.len() - self.point_count
+    }
+
+    // Action types of the outputs, for the lemma with the given index in a problem
+    pub fn get_action_types(&self, lemma_index: usize) -> Vec<ActionType> {
+        (0..self.outputs.len())
+            .map(|output| {
+                ActionType::LemmaActionType(LemmaActionType {
+                    lemma: lemma_index as u8,
+                    output: output as u8,
+                    point_count: self.point_count as u8,
+                    shape_count: self.get_shape_count() as u8,
+                })
+            })
+            .collect()
+    }
+
+    // The first output is named after the lemma, the next ones get "_2", "_3"...
+    pub fn get_output_name(&self, output: usize) -> String {
+        match output {
+            0 => self.name.clone(),
+            _ => format!("{}_{}", self.name, output + 1),
+        }
+    }
+
+    // The output made from the inputs, if the steps can be made; of two intersections, the one
+    // placed like in the recorded solution relative to the inputs is taken
+    pub fn apply(&self, points: &[Point], shapes: &[Shape], output: usize) -> Option<Shape> {
+        let given = self.get_given(points, shapes);
+        let replayed = self
+            .solution
+            .replay_on(
+                &given,
+                &self.lemmas,
+                &mut |step| step.placement.unwrap(),
+                &self.get_similarity(&given),
+            )
+            .ok()?;
+        Some(replayed[self.outputs[output]])
+    }
+
+    // The similarity taking the recorded inputs to the inputs of given
+    fn get_similarity(&self, given: &[Shape]) -> Similarity {
+        let inputs =
+            |shapes: &[Shape]| -> Vec<Shape> { self.inputs.iter().map(|i| shapes[*i]).collect() };
+        Similarity::between(&inputs(&self.given), &inputs(given))
+    }
+
+    fn get_given(&self, points: &[Point], shapes: &[Shape]) -> Vec<Shape> {
+        let mut given = self.given.clone();
+        for (k, input) in self.inputs.iter().enumerate() {
+            given[*input] = match k.checked_sub(self.point_count) {
+                None => Shape::point(points[k]),
+                Some(i) => shapes[i],
+            };
+        }
+        given
+    }
+}
+
+impl Solution {
+    pub fn to_lemma(&self, problem: &ProblemDefinition, name: &str) -> Result<Lemma, String> {
+        let given = get_given_shapes(problem);
+        let mut used = vec![false; given.len()];
+        for step in &self.steps {
+            for point in &step.points {
+                match point {
+                    PointRef::Given(i) => used[*i] = true,
+                    PointRef::Intersection { shapes, .. } => {
+                        for shape in shapes {
+                            if let ShapeRef::Given(i) = shape {
+                                used[*i] = true;
+                            }
+                        }
+                    }
+                    PointRef::Placed { .. } => (),
+                }
+            }
+            for shape in &step.shapes {
+                if let ShapeRef::Given(i) = shape {
+                    used[*i] = true;
+                }
+            }
+        }
+        let (mut inputs, shape_inputs): (Vec<usize>, Vec<usize>) = (0..given.len())
+            .filter(|i| used[*i])
+            .partition(|i| given[*i].as_point().is_some());
+        let point_count = inputs.len();
+        inputs.extend(shape_inputs);
+        if inputs.len() > 3 {
+            return Err(format!(
+                "Lemma {} has {} inputs, at most 3 are supported",
+                name,
+                inputs.len()
+            ));
+        }
+        let outputs: Vec<usize> = problem
+            .elements_to_find
+            .iter()
+            .filter_map(|element| element.get_shape())
+            .filter(|target| target.as_point().is_none())
+            .filter_map(|target| {
+                self.steps
+                    .iter()
+                    .position(|step| step.shape.almost_equals(&target))
+            })
+            .collect();
+        if outputs.is_empty() {
+            return Err(format!("Lemma {} doesn't make any shape to find", name));
+        }
+        let input_kinds = inputs.iter().map(|i| InputKind::of(&given[*i])).collect();
+        Ok(Lemma {
+            name: name.to_string(),
+            solution: self.clone(),
+            given,
+            inputs,
+            point_count,
+            input_kinds,
+            outputs,
+            e_moves: self.e_moves,
+            lemmas: problem.lemmas.to_vec(),
+        })
+    }
+
+    // The solution with the steps of the lemmas in place of the lemma steps (e.g. for GeoGebra
+    // scripts)
+    pub fn expand_lemmas(&self, problem: &ProblemDefinition) -> Result<Solution, String> {
+        let given = get_given_shapes(problem);
+        let given_refs: Vec<Option<InputRef>> = given
+            .iter()
+            .enumerate()
+            .map(|(i, shape)| match shape.as_point() {
+                Some(_) => Some(InputRef::Point(PointRef::Given(i))),
+                None => Some(InputRef::Shape(ShapeRef::Given(i))),
+            })
+            .collect();
+        let mut steps = Vec::new();
+        self.expand_steps(
+            &given,
+            &given_refs,
+            &problem.lemmas,
+            &Similarity::identity(),
+            &mut steps,
+        )?;
+        Ok(Solution {
+            steps,
+            ..self.clone()
+        })
+    }
+
+    // Appends the steps to expanded, with the given elements replaced by given_refs; returns
+    // the indices of the steps in expanded
+    fn expand_steps(
+        &self,
+        given: &[Shape],
+        given_refs: &[Option<InputRef>],
+        lemmas: &[Lemma],
+        similarity: &Similarity,
+        expanded: &mut Vec<SolutionStep>,
+    ) -> Result<Vec<usize>, String> {
+        let place = &mut |step: &SolutionStep| step.placement.unwrap();
+        let shapes = self.replay_on(given, lemmas, place, similarity)?;
+        let mut step_indices: Vec<usize> = Vec::new();
+        for (k, step) in self.steps.iter().enumerate() {
+            let map_shape = |shape: &ShapeRef| match shape {
+                ShapeRef::Given(i) => match given_refs[*i] {
+                    Some(InputRef::Shape(shape)) => Ok(shape),
+                    _ => Err(format!("Given element {} isn't a shape input", i)),
+                },
+                ShapeRef::Step(i) => Ok(ShapeRef::Step(step_indices[*i])),
+            };
+            let points = step
+                .points
+                .iter()
+                .map(|point| {
+                    Ok(match point {
+                        PointRef::Given(i) => match given_refs[*i] {
+                            Some(InputRef::Point(point)) => point,
+                            _ => return Err(format!("Given element {} isn't a point input", i)),
+                        },
+                        PointRef::Intersection { shapes: refs, .. } => PointRef::Intersection {
+                            shapes: [map_shape(&refs[0])?, map_shape(&refs[1])?],
+                            point: get_point(given, &shapes, point, similarity)?,
+                        },
+                        PointRef::Placed { step, .. } => PointRef::Placed {
+                            step: step_indices[*step],
+                            point: shapes[*step].as_point().unwrap(),
+                        },
+                    })
+                })
+                .collect::<Result<Vec<PointRef>, String>>()?;
+            let step_shapes = step
+                .shapes
+                .iter()
+                .map(map_shape)
+                .collect::<Result<Vec<ShapeRef>, String>>()?;
+            if let ActionType::LemmaActionType(value) = step.action_type {
+                let lemma = &lemmas[value.lemma as usize];
+                let input_points = step
+                    .points
+                    .iter()
+                    .map(|point| get_point(given, &shapes, point, similarity))
+                    .collect::<Result<Vec<Point>, String>>()?;
+                let input_shapes: Vec<Shape> = step
+                    .shapes
+                    .iter()
+                    .map(|shape| get_shape(given, &shapes, shape))
+                    .collect();
+                let mut lemma_refs = vec![None; lemma.given.len()];
+                for (j, input) in lemma.inputs.iter().enumerate() {
+                    lemma_refs[*input] = Some(match j.checked_sub(lemma.point_count) {
+                        None => InputRef::Point(points[j]),
+                        Some(i) => InputRef::Shape(step_shapes[i]),
+                    });
+                }
+                let lemma_given = lemma.get_given(&input_points, &input_shapes);
+                let lemma_indices = lemma.solution.expand_steps(
+                    &lemma_given,
+                    &lemma_refs,
+                    &lemma.lemmas,
+                    &lemma.get_similarity(&lemma_given),
+                    expanded,
+                )?;
+                step_indices.push(lemma_indices[lemma.outputs[value.output as usize]]);
+            } else {
+                step_indices.push(expanded.len());
+                expanded.push(SolutionStep {
+                    points,
+                    shapes: step_shapes,
+                    shape: shapes[k],
+                    ..step.clone()
+                });
+            }
+        }
+        Ok(step_indices)
+    }
+}
+
+mod private {
+    use super::*;
+
+    // What a given element of a lemma stands for where the lemma is used
+    #[derive(Debug, Clone, Copy)]
+    pub enum InputRef {
+        Point(PointRef),
+        Shape(ShapeRef),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computation::test_helpers::solve_with;
+    use crate::element::{CircleCP, Element, LineAB};
+    use crate::fint::FInt;
+    use crate::problems::targets::tangent_at;
+    use crate::shape::Circle;
+
+    #[test]
+    fn test_lemma() {
+        let pt = |x: f64, y: f64| Point(FInt::new(x), FInt::new(y));
+        let recorded =
+            ProblemDefinition::get_problem_by_id("2.8-tangent-to-circle-at-point-with-perp")
+                .unwrap();
+        let lemma = Lemma::record(&recorded, "TANGENT_AT").unwrap();
+        assert_eq!((lemma.inputs.len(), lemma.point_count), (2, 2));
+        assert_eq!(lemma.outputs, vec![1]);
+        assert_eq!(lemma.e_moves, 4);
+
+        // The tangents at two points of a circle, with the lemma as the only tool
+        let (c, p1) = (pt(0.1, -0.2), pt(1.1, -0.2));
+        let p2 = pt(0.1 + 0.6, -0.2 + 0.8);
+        let (problem, solutions) = solve_with("1.3-midpoint-with-midperp", |problem| {
+            problem.given_elements = vec![
+                Element::CircleCP(CircleCP { c, p: p1 }),
+                Element::Point(c),
+                Element::Point(p1),
+                Element::Point(p2),
+            ];
+            problem.elements_to_find = vec![tangent_at(&c, &p1), tangent_at(&c, &p2)];
+            problem.action_count = 2;
+            problem.action_types = [].as_slice().into();
+            problem.add_lemma(lemma).unwrap();
+        });
+        assert_eq!(
+            problem.parse_action_type("TANGENT_AT"),
+            Ok(problem.action_types[0])
+        );
+        assert_eq!(solutions.len(), 1);
+        let solution = &solutions[0];
+        assert_eq!(solution.step_count, 2);
+        assert_eq!(solution.e_moves, 8);
+        assert_eq!(solution.replay(&problem).unwrap().len(), 2);
+
+        // The GeoGebra script has the steps of the lemma
+        let expanded = solution.expand_lemmas(&problem).unwrap();
+        assert_eq!(expanded.steps.len(), 4);
+        let script = solution.to_geogebra_script(&problem);
+        assert!(script.contains("s4 = PerpendicularLine("));
+
+        // A point referring to the given circle is an error, not a panic
+        let mut broken = solution.clone();
+        broken.steps[0].points[0] = PointRef::Given(0);
+        assert!(broken.expand_lemmas(&problem).is_err());
+    }
+
+    #[test]
+    fn test_lemma_on_moved_inputs() {
+        // The lemma makes the tangent wherever its inputs are: the intersections of its steps are
+        // picked relative to the inputs, not to where the lemma was recorded
+        let recorded =
+            ProblemDefinition::get_problem_by_id("2.8-tangent-to-circle-at-point").unwrap();
+        let lemma = Lemma::record(&recorded, "TANGENT_AT").unwrap();
+        let Shape::Line(tangent) = lemma.solution.steps[lemma.outputs[0]].shape else {
+            panic!("The tangent isn't a line");
+        };
+        let on_tangent = [
+            Point(tangent.nx * tangent.d, tangent.ny * tangent.d),
+            Point(
+                tangent.nx * tangent.d - tangent.ny,
+                tangent.ny * tangent.d + tangent.nx,
+            ),
+        ];
+        for (angle, (dx, dy)) in [(0.0, (7.0, -4.0)), (2.5, (0.0, 0.0)), (-1.9, (-3.0, 5.5))] {
+            let (sin, cos) = f64::sin_cos(angle);
+            let (sin, cos) = (FInt::new(sin), FInt::new(cos));
+            let moved = |p: &Point| {
+                Point(
+                    p.0 * cos - p.1 * sin + FInt::new(dx),
+                    p.0 * sin + p.1 * cos + FInt::new(dy),
+                )
+            };
+            let inputs: Vec<Shape> = lemma
+                .inputs
+                .iter()
+                .map(|i| match lemma.given[*i] {
+                    Shape::Point(point) => Shape::Point(moved(&point)),
+                    Shape::Circle(circle) => Shape::Circle(Circle {
+                        c: moved(&circle.c),
+                        r2: circle.r2,
+                    }),
+                    _ => panic!("Unexpected input {}", lemma.given[*i]),
+                })
+                .collect();
+            let (points, shapes) = inputs.split_at(lemma.point_count);
+            let points: Vec<Point> = points.iter().map(|p| p.as_point().unwrap()).collect();
+            let expected = LineAB {
+                a: moved(&on_tangent[0]),
+                b: moved(&on_tangent[1]),
+            }
+            .get_shape();
+            let shape = lemma.apply(&points, shapes, 0).unwrap();
+            assert!(shape.almost_equals(&Shape::Line(expected)), "{}", shape);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+use crate::computation::print::PrintStateHelper;
+use crate::element::Element;
+use crate::Computation;
+pub(super) use private::get_time_limit_reason;
+use private::*;
+
+// Budgets of Computation::solve(): the search stops with a report (see print_limit_report())
+// as soon as one of them is exceeded
+#[derive(Clone, Debug)]
+pub struct SearchLimits {
+    pub max_time: Option<Duration>,
+    pub max_iterations: u32,
+    pub max_queue_size: usize,
+    // Resident memory in megabytes, only checked where /proc/self/status is available
+    pub max_memory_mb: Option<u64>,
+}
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self {
+            max_time: None,
+            max_iterations: 1000000,
+            max_queue_size: 100000000,
+            max_memory_mb: None,
+        }
+    }
+}
+
+impl SearchLimits {
+    // When the time limit of a search started at time runs out
+    pub fn get_deadline(&self, time: SystemTime) -> Option<SystemTime> {
+        self.max_time.map(|max_time| time + max_time)
+    }
+}
+
+// Reading the resident memory isn't free, so it's only checked every so many iterations
+const MEMORY_CHECK_INTERVAL: u32 = 1000;
+
+impl<'a> Computation<'a> {
+    // Description of the first limit exceeded at iteration i of a search started at time
+    pub(super) fn get_exceeded_limit(
+        &self,
+        limits: &SearchLimits,
+        i: u32,
+        time: SystemTime,
+    ) -> Option<String> {
+        if i >= limits.max_iterations {
+            return Some(format!("iteration limit ({})", limits.max_iterations));
+        }
+        if self.queue.len() > limits.max_queue_size {
+            return Some(format!("queue size limit ({})", limits.max_queue_size));
+        }
+        if let Some(max_time) = limits.max_time {
+            if time.elapsed().unwrap() >= max_time {
+                return Some(get_time_limit_reason(max_time));
+            }
+        }
+        if let Some(max_memory_mb) = limits.max_memory_mb {
+            if i.is_multiple_of(MEMORY_CHECK_INTERVAL) {
+                match get_resident_memory_mb() {
+                    Some(memory_mb) if memory_mb > max_memory_mb => {
+                        return Some(format!(
+                            "memory limit ({} MB, {} MB used)",
+                            max_memory_mb, memory_mb
+                        ));
+                    }
+                    _ => (),
+                }
+            }
+        }
+        None
+    }
+
+    pub(super) fn print_limit_report(&self, reason: &str, i: u32, time: SystemTime) {
+        println!("--- Search stopped by the {} ---", reason);
+        println!(
+            "Iterations: {}, time: {} seconds, points: {}, shapes: {}",
+            i,
+            time.elapsed().unwrap().as_secs(),
+            self.point_origins.len(),
+            self.shape_origins.len()
+        );
+
+        let mut found_count = 0;
+        for (k, element) in self.problem.elements_to_find.iter().enumerate() {
+            let (found, description) = match element {
+                Element::Point(point) => (self.found_points.contains(*point), point.to_string()),
+                _ => {
+                    let shape = element.get_shape().unwrap();
+                    (self.found_shapes.contains(shape), shape.to_string())
+                }
+            };
+            found_count += found as u32;
+            let status = if found { "found" } else { "not found" };
+            println!("Element to find {}: {} ({})", k + 1, description, status);
+        }
+        println!(
+            "Found {} of {} elements to find",
+            found_count,
+            self.problem.elements_to_find.len()
+        );
+
+        // The shape leading to the most shapes to find, with the fewest actions
+        let best = (0..self.shape_origins.len())
+            .filter(|i| self.shape_origins[*i].found_shape_mask != 0)
+            .max_by_key(|i| {
+                let origin = &self.shape_origins[*i];
+                (
+                    origin.found_shape_mask.count_ones(),
+                    std::cmp::Reverse(self.get_deps_count(origin.deps)),
+                )
+            });
+        if let Some(index) = best {
+            let origin = &self.shape_origins[index];
+            let names: Vec<String> = self
+                .get_deps_indices(origin.deps)
+                .iter()
+                .map(|i| self.get_shape_name(*i as i32))
+                .collect();
+            println!(
+                "Best partial construction: {} of {} shapes to find in {} actions ({})",
+                origin.found_shape_mask.count_ones(),
+                self.shape_to_find_mask_by_shape.len(),
+                names.len(),
+                names.join(", ")
+            );
+        }
+
+        let mut counts_by_deps_count: BTreeMap<u32, usize> = BTreeMap::new();
+        for action in self.queue.iter() {
+            *counts_by_deps_count.entry(action.deps_count).or_default() += 1;
+        }
+        let counts: Vec<String> = counts_by_deps_count
+            .iter()
+            .map(|(deps_count, count)| format!("{}: {}", deps_count, count))
+            .collect();
+        println!(
+            "Queue: {} actions, top priority {}, by actions so far: {{{}}}",
+            self.queue.len(),
+            self.queue.peek().map_or(0, |action| action.priority),
+            counts.join(", ")
+        );
+    }
+}
+
+mod private {
+    use std::fs::read_to_string;
+    use std::time::Duration;
+
+    pub fn get_time_limit_reason(max_time: Duration) -> String {
+        format!("time limit ({} seconds)", max_time.as_secs())
+    }
+
+    // VmRSS of /proc/self/status (None where it's not available)
+    pub fn get_resident_memory_mb() -> Option<u64> {
+        let status = read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+        let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kb / 1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computation::test_helpers::run_search;
+    use crate::problems::ProblemDefinition;
+    use crate::SolveOptions;
+
+    #[test]
+    fn test_search_limits() {
+        let problem = ProblemDefinition::get_problem_by_id("7.5-herons").unwrap();
+        let options = SolveOptions {
+            limits: SearchLimits {
+                max_iterations: 20,
+                ..SearchLimits::default()
+            },
+            ..SolveOptions::without_files()
+        };
+        let (comp, solutions) = run_search(&problem, &options);
+        assert!(solutions.is_empty());
+        assert!(!comp.exhausted);
+        assert_eq!(comp.stop_reason.as_deref(), Some("iteration limit (20)"));
+
+        assert!(get_resident_memory_mb().is_none_or(|memory_mb| memory_mb > 0));
+    }
+
+    #[test]
+    fn test_time_limit_with_random_walks() {
+        // The search ends in well under a second, then the random walks stop at the time limit
+        let problem =
+            ProblemDefinition::get_problem_by_id("5.7-line-equidistant-from-two-lines-rw").unwrap();
+        let options = SolveOptions {
+            limits: SearchLimits {
+                max_time: Some(Duration::from_secs(1)),
+                ..SearchLimits::default()
+            },
+            ..SolveOptions::without_files()
+        };
+        let time = SystemTime::now();
+        let (comp, solutions) = run_search(&problem, &options);
+        assert!(time.elapsed().unwrap() < Duration::from_secs(10));
+        assert!(solutions.is_empty());
+        assert_eq!(comp.stop_reason.as_deref(), Some("time limit (1 seconds)"));
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use private::*;
+pub(super) use private::{join_names, PrintStateHelper};
 
 use crate::{Computation, VecLengths};
 
@@ -73,6 +73,16 @@ mod private {
     use crate::computation::{action::ElementLink, GIVEN};
     use crate::{shape::Shape, Computation, VecLengths};
 
+    // A free point is made from nothing, a point on a shape from the shape alone
+    pub fn join_names(names: &[String]) -> String {
+        match names {
+            [] => "the plane".to_string(),
+            [name] => name.clone(),
+            [name1, name2] => format!("{} and {}", name1, name2),
+            _ => format!("{}, {}, and {}", names[0], names[1], names[2]),
+        }
+    }
+
     pub trait PrintStateHelper {
         fn get_shape_name(&self, shape_index: i32) -> String;
         fn get_point_name(&self, point_index: i32) -> String;
@@ -90,6 +100,7 @@ mod private {
                 Shape::Ray(_ray) => "Ray",
                 Shape::Segment(_segment) => "Segment",
                 Shape::Circle(_circle) => "Circle",
+                Shape::Point(_point) => "Point",
             };
             return format!("{prefix}{name}{shape_index}");
         }
@@ -98,6 +109,9 @@ mod private {
             let origin = &self.point_origins[point_index as usize];
             if origin.shape_origin_indices[0] == GIVEN {
                 format!("GivenPoint{}", point_index)
+            } else if self.shape_origins[origin.shape_origin_indices[0] as usize].is_placed_point()
+            {
+                self.get_shape_name(origin.shape_origin_indices[0])
             } else {
                 format!(
                     "x/{}/{}",
@@ -136,15 +150,11 @@ mod private {
                             .map(|i| self.get_shape_name(i))
                             .collect();
                         names.append(&mut shape_names);
-
-                        let point_names_str = if names.len() == 2 {
-                            format!("{} and {}", names[0], names[1])
-                        } else {
-                            format!("{}, {}, and {}", names[0], names[1], names[2])
-                        };
                         format!(
                             "[pri = {}] from {} ({})",
-                            action.priority, point_names_str, origin.element_link,
+                            action.priority,
+                            join_names(&names),
+                            origin.element_link,
                         )
                     }
                 };
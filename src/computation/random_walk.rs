@@ -6,6 +6,7 @@ use super::*;
 use rand::{rng, Rng};
 
 const NEW_SHAPE_MULTIPLIER: u32 = 4;
+const DEADLINE_CHECK_INTERVAL: u32 = 1000;
 
 pub struct RandomWalkSolution {
     pub shapes: Vec<Shape>,
@@ -155,19 +156,27 @@ pub struct RandomWalk<'a> {
     random_walk_index: u32,
     parent: &'a RandomWalkParent<'a>,
     initial_shapes: Vec<Shape>,
+    // Uses of the groups of ProblemDefinition::action_limits by the initial shapes
+    initial_usage: Vec<u32>,
 }
 impl<'a> RandomWalk<'a> {
+    // Adds the use of the action to usage if a shape is returned
     fn choose_random_shape_to_add(
         &self,
         shapes: &[Shape],
         n: u32,
         added_shape_count: u32,
+        usage: &mut [u32],
     ) -> Option<Shape> {
         let action_type_count = self.parent.problem.action_types.len() as u32;
         let rw_choice_count = n * n * action_type_count;
         let rw_choice = rng().random_range(0..rw_choice_count);
         // println!("Generating {}-th shape, random value: {}", i, rw_choice);
         let i_action = rw_choice % action_type_count;
+        let action_type = self.parent.actions[i_action as usize];
+        if !self.parent.problem.fits_action_limits(usage, action_type) {
+            return None;
+        }
         let point_index_1 = (rw_choice / action_type_count) % n;
         let point_index_2 = (rw_choice / (n * action_type_count)) % n;
         if point_index_1 == point_index_2 {
@@ -190,6 +199,7 @@ impl<'a> RandomWalk<'a> {
                             return None;
                         }
                         // println!("Adding shape {}", shape);
+                        self.parent.problem.add_action_usage(usage, action_type);
                         return Some(shape);
                     }
                     _ => return None,
@@ -242,7 +252,13 @@ impl<'a> RandomWalk<'a> {
         None
     }
 
-    pub fn run_iterations(&self, limit: u32) -> Option<RandomWalkSolution> {
+    // Stops after limit iterations, or at the deadline (checked every DEADLINE_CHECK_INTERVAL
+    // iterations)
+    pub fn run_iterations(
+        &self,
+        limit: u32,
+        deadline: Option<SystemTime>,
+    ) -> Option<RandomWalkSolution> {
         if self.random_walk_index % 500 == 0 {
             println!(
                 "Running random walk {} with limit = {}",
@@ -281,13 +297,19 @@ impl<'a> RandomWalk<'a> {
         for f_index in 0..self.parent.shapes_to_find.len() {
             f_initial_data_list[f_index].reset_to(&f_data_list[f_index]);
         }
-        for _iteration in 0..limit {
+        for iteration in 0..limit {
             {
                 if *self.parent.solution_found.read().unwrap() {
                     break;
                 }
             }
+            if iteration % DEADLINE_CHECK_INTERVAL == 0
+                && deadline.is_some_and(|deadline| SystemTime::now() >= deadline)
+            {
+                break;
+            }
             let mut f_mask = initial_f_mask;
+            let mut usage = self.initial_usage.clone();
             if self.parent.problem.track_supports_in_rw {
                 for f_index in 0..self.parent.shapes_to_find.len() {
                     f_data_list[f_index].reset_to(&f_initial_data_list[f_index]);
@@ -319,7 +341,7 @@ impl<'a> RandomWalk<'a> {
                         break;
                     }
                     let n = self.parent.pt_index_counts[(i - i0) as usize];
-                    maybe_shape = self.choose_random_shape_to_add(&shapes, n, i);
+                    maybe_shape = self.choose_random_shape_to_add(&shapes, n, i, &mut usage);
                 }
                 if let Some(shape) = maybe_shape {
                     match f_index {
@@ -542,6 +564,9 @@ impl<'a> RandomWalkProcessing<'a> for Computation<'a> {
                 random_walk_index: random_walks.len_u32(),
                 parent: random_walk_parent,
                 initial_shapes: given_shapes.clone(),
+                initial_usage: self
+                    .problem
+                    .get_action_usage([action.action_type].into_iter()),
             });
             deps.push(action.get_action_deps(&self));
             last_shapes.push(action.shape);
@@ -558,6 +583,12 @@ impl<'a> RandomWalkProcessing<'a> for Computation<'a> {
                     random_walks[j]
                         .initial_shapes
                         .push(shape_origin.get_shape());
+                    if let ElementLink::Action(action) = &shape_origin.element_link {
+                        self.problem.add_action_usage(
+                            &mut random_walks[j].initial_usage,
+                            action.action_type,
+                        );
+                    }
                 }
             }
         }
@@ -0,0 +1,240 @@
+use std::fs::write;
+use std::path::Path;
+
+use svg::node::element::{Animate, Group, Text};
+use svg::Document;
+
+use crate::computation::draw::{Canvas, SvgCanvas, COLORS, HIGHLIGHT};
+use crate::computation::Solution;
+use crate::computation::{DrawOptions, Viewport};
+use crate::element::Element;
+use crate::problems::ProblemDefinition;
+use crate::shape::Shape;
+use private::*;
+
+impl Solution {
+    // An SVG image revealing the steps one by one (SMIL animation): first the inputs of a step
+    // are highlighted, then its shape is added
+    pub fn to_animated_svg(&self, problem: &ProblemDefinition, options: &DrawOptions) -> String {
+        let mut canvas = SvgCanvas::new(self.get_viewport(problem, options), options.clip_lines);
+        draw_given_elements(&mut canvas, problem);
+        for (k, step) in self.steps.iter().enumerate() {
+            let begin = k as f64 * STEP_SECONDS;
+            canvas.start_group(
+                Group::new()
+                    .set("id", format!("inputs{}", k + 1))
+                    .set("opacity", 0)
+                    .add(
+                        Animate::new()
+                            .set("attributeName", "opacity")
+                            .set("values", 1)
+                            .set("begin", format!("{}s", begin))
+                            .set("dur", format!("{}s", STEP_SECONDS)),
+                    )
+                    .add(
+                        Text::new(get_step_text(k, step))
+                            .set("x", 10)
+                            .set("y", 24)
+                            .set("font-size", 18),
+                    ),
+            );
+            self.draw_inputs(&mut canvas, problem, k);
+            canvas.start_group(
+                Group::new()
+                    .set("id", format!("step{}", k + 1))
+                    .set("opacity", 0)
+                    .add(
+                        Animate::new()
+                            .set("attributeName", "opacity")
+                            .set("to", 1)
+                            .set("begin", format!("{}s", begin + STEP_SECONDS / 2.0))
+                            .set("dur", "0.3s")
+                            .set("fill", "freeze"),
+                    ),
+            );
+            draw_step(&mut canvas, k, step, options);
+        }
+        canvas.into_document().to_string()
+    }
+
+    // A self-contained HTML page with the drawing, the list of the steps and Previous/Next
+    // buttons
+    pub fn to_html(&self, problem: &ProblemDefinition, options: &DrawOptions) -> String {
+        let mut canvas = SvgCanvas::new(self.get_viewport(problem, options), options.clip_lines);
+        draw_given_elements(&mut canvas, problem);
+        let mut items = Vec::new();
+        for (k, step) in self.steps.iter().enumerate() {
+            canvas.start_group(
+                Group::new()
+                    .set("id", format!("inputs{}", k + 1))
+                    .set("opacity", 0),
+            );
+            self.draw_inputs(&mut canvas, problem, k);
+            canvas.start_group(
+                Group::new()
+                    .set("id", format!("step{}", k + 1))
+                    .set("opacity", 0),
+            );
+            draw_step(&mut canvas, k, step, options);
+            items.push(format!(
+                "<li id=\"text{}\">{}</li>",
+                k + 1,
+                escape_html(&get_step_text(k, step))
+            ));
+        }
+        let document: Document = canvas.into_document();
+        HTML_TEMPLATE
+            .replace("{svg}", &document.to_string())
+            .replace("{steps}", &items.join("\n"))
+            .replace("{count}", &self.steps.len().to_string())
+    }
+
+    // Writes an HTML page for .html/.htm files, an animated SVG image otherwise
+    pub fn save_replay(
+        &self,
+        problem: &ProblemDefinition,
+        filename: String,
+        options: &DrawOptions,
+    ) {
+        let extension = Path::new(&filename).extension().and_then(|s| s.to_str());
+        let content = match extension {
+            Some("html") | Some("htm") => self.to_html(problem, options),
+            _ => self.to_animated_svg(problem, options),
+        };
+        write(filename, content).unwrap();
+    }
+
+    fn get_viewport(&self, problem: &ProblemDefinition, options: &DrawOptions) -> Viewport {
+        let mut points = Vec::new();
+        let mut shapes: Vec<Shape> = self.steps.iter().map(|step| step.shape).collect();
+        for element in problem
+            .given_elements
+            .iter()
+            .chain(problem.elements_to_find.iter())
+        {
+            match element {
+                Element::Point(point) => points.push(*point),
+                _ => shapes.push(element.get_shape().unwrap()),
+            }
+        }
+        for step in &self.steps {
+            points.extend(
+                step.points
+                    .iter()
+                    .map(|point| self.get_point(problem, point)),
+            );
+        }
+        options.get_viewport(&points, &shapes)
+    }
+
+    fn draw_inputs(&self, canvas: &mut SvgCanvas, problem: &ProblemDefinition, k: usize) {
+        let step = &self.steps[k];
+        for shape in &step.shapes {
+            canvas.draw_shape(&self.get_shape(problem, shape), HIGHLIGHT, 6, None);
+        }
+        for point in &step.points {
+            canvas.draw_point(&self.get_point(problem, point), HIGHLIGHT, 6, None);
+        }
+    }
+}
+
+mod private {
+    use super::*;
+    use crate::computation::print::join_names;
+    use crate::computation::SolutionStep;
+
+    pub const STEP_SECONDS: f64 = 2.0;
+
+    pub const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Solution</title>
+<style>
+body { display: flex; gap: 20px; font-family: sans-serif; }
+svg { width: 600px; height: 600px; border: 1px solid lightgray; }
+li.current { font-weight: bold; color: darkorange; }
+</style>
+</head>
+<body>
+<div>
+{svg}
+<p><button id="previous">Previous</button> <button id="next">Next</button> <span id="counter"></span></p>
+</div>
+<ol>
+{steps}
+</ol>
+<script>
+const count = {count};
+let current = 0;
+function show() {
+    for (let k = 1; k <= count; k++) {
+        document.getElementById("step" + k).setAttribute("opacity", k <= current ? 1 : 0);
+        document.getElementById("inputs" + k).setAttribute("opacity", k == current ? 1 : 0);
+        document.getElementById("text" + k).className = k == current ? "current" : "";
+    }
+    document.getElementById("counter").textContent = current + " / " + count;
+}
+document.getElementById("previous").onclick = () => { current = Math.max(current - 1, 0); show(); };
+document.getElementById("next").onclick = () => { current = Math.min(current + 1, count); show(); };
+show();
+</script>
+</body>
+</html>
+"#;
+
+    pub fn draw_given_elements(canvas: &mut SvgCanvas, problem: &ProblemDefinition) {
+        for element in &problem.given_elements {
+            match element {
+                Element::Point(point) => canvas.draw_point(point, COLORS[0], 2, None),
+                _ => canvas.draw_shape(&element.get_shape().unwrap(), COLORS[0], 3, None),
+            }
+        }
+    }
+
+    pub fn draw_step(canvas: &mut SvgCanvas, k: usize, step: &SolutionStep, options: &DrawOptions) {
+        let label = if options.labels {
+            format!("{}. {}", k + 1, step.name)
+        } else {
+            (k + 1).to_string()
+        };
+        canvas.draw_shape(&step.shape, COLORS[(k + 1) % 10], 3, Some(&label));
+    }
+
+    // Same wording as print_solution(), e.g. "2: Line5 from GivenPoint0 and x/Line3/Circle4"
+    pub fn get_step_text(k: usize, step: &SolutionStep) -> String {
+        format!(
+            "{}: {} from {} ({:?})",
+            k + 1,
+            step.name,
+            join_names(&step.input_names),
+            step.action_type
+        )
+    }
+
+    pub fn escape_html(s: &str) -> String {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::computation::test_helpers::solve_problem;
+    use crate::computation::{DrawOptions, SolveOptions};
+
+    #[test]
+    fn test_replay() {
+        let (problem, solutions) = solve_problem(
+            "2.8-tangent-to-circle-at-point-with-perp",
+            &SolveOptions::without_files(),
+        );
+        let svg = solutions[0].to_animated_svg(&problem, &DrawOptions::default());
+        assert!(svg.contains("<g id=\"step2\" opacity=\"0\">"));
+        assert!(svg.contains("begin=\"3s\""));
+        let html = solutions[0].to_html(&problem, &DrawOptions::default());
+        assert!(html.contains("const count = 2;"));
+        assert!(html.contains("<li id=\"text1\">1: Line"));
+    }
+}
@@ -0,0 +1,266 @@
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::computation::action::ElementLink;
+use crate::computation::print::PrintStateHelper;
+use crate::computation::GIVEN;
+use crate::element::Element;
+use crate::problems::{ActionType, ProblemDefinition};
+use crate::shape::{Point, Shape};
+use crate::Computation;
+
+// A shape used by a step: one of the problem's given elements or the result of an earlier step
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ShapeRef {
+    Given(usize),
+    Step(usize),
+}
+
+// A point used by a step: a given point, an intersection of two shapes (the point itself
+// tells which of the two intersections is meant) or a point placed by an earlier step
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum PointRef {
+    Given(usize),
+    Intersection { shapes: [ShapeRef; 2], point: Point },
+    Placed { step: usize, point: Point },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SolutionStep {
+    pub action_type: ActionType,
+    pub points: Vec<PointRef>,
+    pub shapes: Vec<ShapeRef>,
+    // Names of the points and shapes used, as printed by PrintState (e.g. "x/Line3/Circle5")
+    pub input_names: Vec<String>,
+    pub name: String,
+    // For point actions, the point is stored as the shape (see Shape::point())
+    pub shape: Shape,
+    // Where a point action placed its point (see Action::create_placed_point())
+    pub placement: Option<[f64; 2]>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Solution {
+    pub steps: Vec<SolutionStep>,
+    pub deps: u64,
+    // L-moves (tool uses) and E-moves (elementary moves, see ActionType::e_moves())
+    pub step_count: u32,
+    pub e_moves: u32,
+    // Total of the action costs of the problem (see ProblemDefinition::cost_budget)
+    pub cost: u32,
+    // Time from the start of the search until the solution was found
+    pub elapsed: Duration,
+}
+
+impl Display for ShapeRef {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ShapeRef::Given(index) => write!(f, "given{}", index),
+            ShapeRef::Step(index) => write!(f, "step{}", index + 1),
+        }
+    }
+}
+impl Display for PointRef {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            PointRef::Given(index) => write!(f, "given{}", index),
+            PointRef::Intersection { shapes, point } => {
+                write!(f, "x/{}/{} {}", shapes[0], shapes[1], point)
+            }
+            PointRef::Placed { step, point } => write!(f, "step{} {}", step + 1, point),
+        }
+    }
+}
+impl Display for Solution {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            let inputs: Vec<String> = step
+                .points
+                .iter()
+                .map(|point| point.to_string())
+                .chain(step.shapes.iter().map(|shape| shape.to_string()))
+                .collect();
+            writeln!(
+                f,
+                "{}: {} {:?} from {} -> {}",
+                i + 1,
+                step.name,
+                step.action_type,
+                inputs.join(", "),
+                step.shape
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Solution {
+    pub fn get_point(&self, problem: &ProblemDefinition, point: &PointRef) -> Point {
+        match point {
+            PointRef::Given(i) => match &problem.given_elements[*i] {
+                Element::Point(point) => *point,
+                _ => panic!("Given element {} isn't a point", i),
+            },
+            PointRef::Intersection { point, .. } | PointRef::Placed { point, .. } => *point,
+        }
+    }
+
+    pub fn get_shape(&self, problem: &ProblemDefinition, shape: &ShapeRef) -> Shape {
+        match shape {
+            ShapeRef::Given(i) => problem.given_elements[*i].get_shape().unwrap(),
+            ShapeRef::Step(i) => self.steps[*i].shape,
+        }
+    }
+}
+
+impl<'a> Computation<'a> {
+    // Collects the shapes included in the given deps in the order they were constructed
+    pub(super) fn build_solution(&mut self, deps: u64, elapsed: Duration) -> Solution {
+        let mut steps: Vec<SolutionStep> = Vec::new();
+        for i in 0..self.shape_origins.len() {
+            let origin_deps = self.shape_origins[i].deps;
+            if self.combine_deps(deps, origin_deps, None) != deps {
+                continue;
+            }
+            let origin = &self.shape_origins[i];
+            let action = match &origin.element_link {
+                ElementLink::GivenElement { .. } => continue,
+                ElementLink::Action(action) => action,
+            };
+            if steps.iter().any(|step| step.shape == action.shape) {
+                continue;
+            }
+            let point_indices = action.get_point_indices();
+            let shape_indices = action.get_shape_indices();
+            let step = SolutionStep {
+                action_type: action.action_type,
+                points: point_indices
+                    .iter()
+                    .map(|index| self.get_point_ref(*index, &steps))
+                    .collect(),
+                shapes: shape_indices
+                    .iter()
+                    .map(|index| self.get_shape_ref(*index, &steps))
+                    .collect(),
+                input_names: point_indices
+                    .iter()
+                    .map(|index| self.get_point_name(*index))
+                    .chain(
+                        shape_indices
+                            .iter()
+                            .map(|index| self.get_shape_name(*index)),
+                    )
+                    .collect(),
+                name: self.get_shape_name(i as i32),
+                shape: action.shape,
+                placement: action.get_point_placement(),
+            };
+            steps.push(step);
+        }
+        Solution {
+            step_count: steps.len() as u32,
+            e_moves: steps
+                .iter()
+                .map(|step| self.problem.get_e_moves(step.action_type))
+                .sum(),
+            cost: steps
+                .iter()
+                .map(|step| self.problem.get_action_cost(step.action_type))
+                .sum(),
+            steps,
+            deps,
+            elapsed,
+        }
+    }
+
+    fn get_shape_ref(&self, shape_index: i32, steps: &[SolutionStep]) -> ShapeRef {
+        let origin = &self.shape_origins[shape_index as usize];
+        match &origin.element_link {
+            ElementLink::GivenElement { element, .. } => {
+                ShapeRef::Given(self.get_given_element_index(element))
+            }
+            ElementLink::Action(action) => ShapeRef::Step(
+                steps
+                    .iter()
+                    .position(|step| step.shape == action.shape)
+                    .unwrap(),
+            ),
+        }
+    }
+
+    fn get_point_ref(&self, point_index: i32, steps: &[SolutionStep]) -> PointRef {
+        let origin = &self.point_origins[point_index as usize];
+        if origin.shape_origin_indices[0] == GIVEN {
+            let index = self
+                .problem
+                .given_elements
+                .iter()
+                .position(
+                    |element| matches!(element, Element::Point(point) if *point == origin.point),
+                )
+                .unwrap();
+            PointRef::Given(index)
+        } else if self.shape_origins[origin.shape_origin_indices[0] as usize].is_placed_point() {
+            match self.get_shape_ref(origin.shape_origin_indices[0], steps) {
+                ShapeRef::Step(step) => PointRef::Placed {
+                    step,
+                    point: origin.point,
+                },
+                ShapeRef::Given(_) => unreachable!(),
+            }
+        } else {
+            PointRef::Intersection {
+                shapes: [
+                    self.get_shape_ref(origin.shape_origin_indices[0], steps),
+                    self.get_shape_ref(origin.shape_origin_indices[1], steps),
+                ],
+                point: origin.point,
+            }
+        }
+    }
+
+    fn get_given_element_index(&self, element: &Element) -> usize {
+        self.problem
+            .given_elements
+            .iter()
+            .position(|given| std::ptr::eq(given, element))
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computation::test_helpers::solve_problem;
+    use crate::computation::SolveOptions;
+    use crate::problems::{PointAndLineActionType, TwoPointActionType};
+
+    #[test]
+    fn test_solution_steps() {
+        let (problem, solutions) = solve_problem(
+            "2.8-tangent-to-circle-at-point-with-perp",
+            &SolveOptions::without_files(),
+        );
+        assert_eq!(solutions.len(), 1);
+        let solution = &solutions[0];
+        assert_eq!(solution.step_count, 2);
+        assert_eq!(solution.e_moves, 4);
+        assert_eq!(solution.steps.len(), 2);
+        assert_eq!(
+            solution.steps[0].action_type,
+            ActionType::TwoPointActionType(TwoPointActionType::Line)
+        );
+        assert_eq!(
+            solution.steps[1].action_type,
+            ActionType::PointAndLineActionType(PointAndLineActionType::Perp)
+        );
+        assert_eq!(solution.steps[1].shapes, vec![ShapeRef::Step(0)]);
+        assert!(matches!(solution.steps[1].points[0], PointRef::Given(_)));
+        assert_eq!(
+            solution.steps[1].shape,
+            problem.elements_to_find[0].get_shape().unwrap()
+        );
+    }
+}
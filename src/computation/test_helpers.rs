@@ -0,0 +1,32 @@
+use crate::computation::{Computation, Solution, SolveOptions};
+use crate::problems::ProblemDefinition;
+
+// The built-in problem and its solutions
+pub fn solve_problem(id: &str, options: &SolveOptions) -> (ProblemDefinition, Vec<Solution>) {
+    let problem = ProblemDefinition::get_problem_by_id(id).unwrap();
+    let solutions = crate::solve(&problem, options);
+    (problem, solutions)
+}
+
+// The built-in problem changed by the closure (e.g. with other given elements), and its
+// solutions
+pub fn solve_with(
+    id: &str,
+    change: impl FnOnce(&mut ProblemDefinition),
+) -> (ProblemDefinition, Vec<Solution>) {
+    let mut problem = ProblemDefinition::get_problem_by_id(id).unwrap();
+    change(&mut problem);
+    let solutions = crate::solve(&problem, &SolveOptions::without_files());
+    (problem, solutions)
+}
+
+// A new search on the problem, with the Computation kept for looking at its state
+pub fn run_search<'a>(
+    problem: &'a ProblemDefinition,
+    options: &SolveOptions,
+) -> (Computation<'a>, Vec<Solution>) {
+    let mut computation = Computation::new(problem);
+    computation.initialize_queue();
+    let solutions = computation.solve(options);
+    (computation, solutions)
+}
@@ -0,0 +1,306 @@
+use crate::element::{CircleABC, EquilateralAB, ReflectionPAV, TangentPCR};
+use crate::fint::FInt;
+use crate::shape::{Point, Shape};
+
+// Kinds of the inputs of tools and lemmas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    Point,
+    Line,
+    Circle,
+}
+impl InputKind {
+    // Points are shapes too (see Shape::point()); rays and segments count as lines
+    pub fn of(shape: &Shape) -> InputKind {
+        match (shape.as_point(), shape.get_direction()) {
+            (Some(_), _) => InputKind::Point,
+            (None, Some(_)) => InputKind::Line,
+            (None, None) => InputKind::Circle,
+        }
+    }
+}
+
+// A macro tool the search handles without code of its own: it's tried on the combinations of
+// the known points and shapes matching its inputs (see Computation::queue_generic_actions()), so
+// a new macro takes an impl, an entry in TOOLS and its GeoGebra command (see geogebra.rs). This
+// is only a registry of macros; the basic actions (LINE, CIRCLE12, PERP...) keep their own code.
+pub trait Tool: Sync {
+    // Name used in problem files and on the command line
+    fn name(&self) -> &'static str;
+    // Tools sharing a category share usage limits by it (see ActionGroup)
+    fn category(&self) -> &'static str;
+    // The points come first, then the shapes
+    fn inputs(&self) -> &'static [InputKind];
+    // Whether swapping inputs of the same kind gives the same result (only one order is tried)
+    fn is_symmetric(&self) -> bool;
+    // See ProblemDefinition::get_e_moves()
+    fn e_moves(&self) -> u32;
+    // The result (a point as Shape::point()); None for degenerate inputs
+    fn construct(&self, points: &[Point], shapes: &[Shape]) -> Option<Shape>;
+}
+
+// Macros: tools of later Euclidea packs (or custom ones) standing for several moves, so that
+// problems can be solved at the macro level and the macros expanded afterwards
+pub static TOOLS: [&dyn Tool; 6] = [
+    &Tangent { pos: true },
+    &Tangent { pos: false },
+    &Circumcircle,
+    &Reflection,
+    &Equilateral { pos: true },
+    &Equilateral { pos: false },
+];
+
+// The tangents from a point to a circle; "pos" touches the circle counterclockwise from the
+// point as seen from the center. None unless the point lies outside of the circle.
+struct Tangent {
+    pos: bool,
+}
+impl Tool for Tangent {
+    fn name(&self) -> &'static str {
+        if self.pos {
+            "TANGENT_POS"
+        } else {
+            "TANGENT_NEG"
+        }
+    }
+
+    fn category(&self) -> &'static str {
+        "TangentActionType"
+    }
+
+    fn inputs(&self) -> &'static [InputKind] {
+        &[InputKind::Point, InputKind::Circle]
+    }
+
+    fn is_symmetric(&self) -> bool {
+        false
+    }
+
+    // The line to the center, its midpoint, the circle on it and the tangent
+    fn e_moves(&self) -> u32 {
+        6
+    }
+
+    fn construct(&self, points: &[Point], shapes: &[Shape]) -> Option<Shape> {
+        let (point, Shape::Circle(circle)) = (&points[0], &shapes[0]) else {
+            return None;
+        };
+        let d2 = (point.0 - circle.c.0).sqr() + (point.1 - circle.c.1).sqr();
+        if !(d2 - circle.r2).always_positive() {
+            return None;
+        }
+        let tangent = TangentPCR {
+            p: *point,
+            c: circle.c,
+            r: circle.r2.sqrt(),
+            pos: self.pos,
+        };
+        Some(Shape::Line(tangent.get_shape()))
+    }
+}
+
+// The circle through three points
+struct Circumcircle;
+impl Tool for Circumcircle {
+    fn name(&self) -> &'static str {
+        "CIRCUMCIRCLE"
+    }
+
+    fn category(&self) -> &'static str {
+        "CircumcircleActionType"
+    }
+
+    fn inputs(&self) -> &'static [InputKind] {
+        &[InputKind::Point, InputKind::Point, InputKind::Point]
+    }
+
+    fn is_symmetric(&self) -> bool {
+        true
+    }
+
+    // Two perpendicular bisectors and the circle
+    fn e_moves(&self) -> u32 {
+        7
+    }
+
+    fn construct(&self, points: &[Point], _shapes: &[Shape]) -> Option<Shape> {
+        let [a, b, c] = points else {
+            return None;
+        };
+        let area = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+        if a == b || b == c || a == c || area == FInt::new(0.0) {
+            return None;
+        }
+        Some(Shape::Circle(
+            CircleABC {
+                a: *a,
+                b: *b,
+                c: *c,
+            }
+            .get_shape(),
+        ))
+    }
+}
+
+// The reflection of a point across a line (the result is a point); None if the point lies on
+// the line (or on its extension for rays and segments)
+struct Reflection;
+impl Tool for Reflection {
+    fn name(&self) -> &'static str {
+        "REFLECTION"
+    }
+
+    fn category(&self) -> &'static str {
+        "ReflectionActionType"
+    }
+
+    fn inputs(&self) -> &'static [InputKind] {
+        &[InputKind::Point, InputKind::Line]
+    }
+
+    fn is_symmetric(&self) -> bool {
+        false
+    }
+
+    // Two circles centered on the line
+    fn e_moves(&self) -> u32 {
+        2
+    }
+
+    fn construct(&self, points: &[Point], shapes: &[Shape]) -> Option<Shape> {
+        let p = points[0];
+        let a = match &shapes[0] {
+            Shape::Line(line) => {
+                let norm2 = line.nx.sqr() + line.ny.sqr();
+                Point(line.nx * line.d / norm2, line.ny * line.d / norm2)
+            }
+            Shape::Ray(ray) => ray.a,
+            Shape::Segment(segment) => segment.a,
+            Shape::Circle(_) | Shape::Point(_) => return None,
+        };
+        let v = shapes[0].get_direction()?;
+        if (p.0 - a.0) * v.1 - (p.1 - a.1) * v.0 == FInt::new(0.0) {
+            return None;
+        }
+        Some(Shape::point(ReflectionPAV { p, a, v }.get_point()))
+    }
+}
+
+// The apex of the equilateral triangle on a segment (the result is a point); "pos" lies to the
+// left of point1 -> point2
+struct Equilateral {
+    pos: bool,
+}
+impl Tool for Equilateral {
+    fn name(&self) -> &'static str {
+        if self.pos {
+            "EQUILATERAL_POS"
+        } else {
+            "EQUILATERAL_NEG"
+        }
+    }
+
+    fn category(&self) -> &'static str {
+        "EquilateralActionType"
+    }
+
+    fn inputs(&self) -> &'static [InputKind] {
+        &[InputKind::Point, InputKind::Point]
+    }
+
+    // The other order is the other tool
+    fn is_symmetric(&self) -> bool {
+        true
+    }
+
+    // Two circles
+    fn e_moves(&self) -> u32 {
+        2
+    }
+
+    fn construct(&self, points: &[Point], _shapes: &[Shape]) -> Option<Shape> {
+        let (a, b) = match self.pos {
+            true => (points[0], points[1]),
+            false => (points[1], points[0]),
+        };
+        if a == b {
+            return None;
+        }
+        Some(Shape::point(EquilateralAB { a, b }.get_point()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computation::test_helpers::solve_with;
+    use crate::element::{CircleCR, Element};
+
+    #[test]
+    fn test_macro_actions() {
+        let pt = |x: f64, y: f64| Point(FInt::new(x), FInt::new(y));
+
+        // Both tangents from a point
+        let (c, p) = (pt(0.1, -0.2), pt(2.3, 0.7));
+        let r = FInt::new(1.0);
+        let (problem, solutions) = solve_with("1.3-midpoint-with-midperp", |problem| {
+            problem.given_elements = vec![Element::Point(p), Element::CircleCR(CircleCR { c, r })];
+            problem.elements_to_find = [true, false]
+                .map(|pos| Element::TangentPCR(TangentPCR { p, c, r, pos }))
+                .into();
+            problem.action_count = 2;
+            problem.action_types = ["TANGENT_POS", "TANGENT_NEG", "LINE"]
+                .map(|name| name.parse().unwrap())
+                .to_vec()
+                .into();
+        });
+        assert_eq!(solutions.len(), 1);
+        assert!(solutions[0].steps.iter().all(|step| step
+            .action_type
+            .get_tool()
+            .map(|tool| tool.category())
+            == Some("TangentActionType")));
+        let script = solutions[0].to_geogebra_script(&problem);
+        assert!(
+            script.contains("s1 = Line(G0, Intersect(g1, Circle(Midpoint(G0, Center(g1)), G0), ")
+        );
+
+        // The circle through the apex of the equilateral triangle on ab and c
+        let (a, b, c) = (pt(-1.0, 0.1), pt(1.2, -0.3), pt(0.4, -1.7));
+        let apex = EquilateralAB { a, b }.get_point();
+        let (_, solutions) = solve_with("1.3-midpoint-with-midperp", |problem| {
+            problem.given_elements = vec![Element::Point(a), Element::Point(b), Element::Point(c)];
+            problem.elements_to_find = vec![Element::CircleABC(CircleABC { a: apex, b, c })];
+            problem.action_count = 2;
+            problem.action_types = ["EQUILATERAL_POS", "EQUILATERAL_NEG", "CIRCUMCIRCLE"]
+                .map(|name| name.parse().unwrap())
+                .to_vec()
+                .into();
+        });
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].step_count, 2);
+        assert_eq!(solutions[0].e_moves, 9);
+
+        // The reflection of a point across a line through two other points
+        let (l1, l2) = (pt(-0.8, -0.6), pt(0.9, 0.3));
+        let v = Point(l2.0 - l1.0, l2.1 - l1.1);
+        let target = ReflectionPAV { p: a, a: l1, v }.get_point();
+        let (_, solutions) = solve_with("1.3-midpoint-with-midperp", |problem| {
+            problem.given_elements =
+                vec![Element::Point(a), Element::Point(l1), Element::Point(l2)];
+            problem.elements_to_find = vec![Element::Point(target)];
+            problem.action_count = 2;
+            problem.action_types = ["LINE", "REFLECTION"]
+                .map(|name| name.parse().unwrap())
+                .to_vec()
+                .into();
+            problem.multimatch = true;
+        });
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].step_count, 2);
+        assert_eq!(
+            solutions[0].steps[1].action_type,
+            "REFLECTION".parse().unwrap()
+        );
+    }
+}
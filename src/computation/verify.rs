@@ -0,0 +1,377 @@
+use std::collections::HashSet;
+use std::fs::{read_to_string, write};
+use std::str::FromStr;
+
+use crate::computation::action::Action;
+use crate::computation::InputKind;
+use crate::element::Element;
+use crate::problems::ProblemDefinition;
+use crate::problems::{ActionType, PointActionType};
+use crate::shape::{Point, Shape, ShapeTrait};
+use crate::Computation;
+pub(super) use private::read_shapes;
+use private::*;
+
+pub trait Verify {
+    fn save_solution_shapes(&mut self, filename: String);
+    fn verify_shapes(
+        problem: &ProblemDefinition,
+        shapes: &[Shape],
+    ) -> Result<Vec<(ActionType, Shape)>, String>;
+    fn verify_shapes_from_file(
+        problem: &ProblemDefinition,
+        input_filename: String,
+    ) -> Result<Vec<(ActionType, Shape)>, String>;
+}
+impl<'a> Verify for Computation<'a> {
+    // Writes the shapes of the solution in the format read by verify_shapes_from_file()
+    fn save_solution_shapes(&mut self, filename: String) {
+        let deps_list = match self.solution_deps {
+            None => self.get_solution_deps_list(),
+            Some(deps) => HashSet::from([deps]),
+        };
+        let mut lines = Vec::new();
+        for i in 0..self.shape_origins.len() {
+            let origin_deps = self.shape_origins[i].deps;
+            let include = deps_list
+                .iter()
+                .any(|deps| self.combine_deps(*deps, origin_deps, None) == *deps);
+            if include {
+                lines.push(format!("{}\n", self.shape_origins[i].get_shape()));
+            }
+        }
+        write(filename, lines.concat()).unwrap();
+    }
+
+    // Replays the shapes one by one: each shape has to be the result of one of the problem's
+    // actions applied to the given elements and the intersections of the shapes before it.
+    // Returns the actions and the shapes they make if all elements to find are constructed.
+    fn verify_shapes(
+        problem: &ProblemDefinition,
+        shapes: &[Shape],
+    ) -> Result<Vec<(ActionType, Shape)>, String> {
+        let mut known_points = Vec::new();
+        let mut known_shapes = Vec::new();
+        for element in &problem.given_elements {
+            match element {
+                Element::Point(point) => add_point(&mut known_points, *point),
+                _ => add_shape(
+                    &mut known_points,
+                    &mut known_shapes,
+                    element.get_shape().unwrap(),
+                ),
+            }
+        }
+        let mut steps = Vec::new();
+        for (i, shape) in shapes.iter().enumerate() {
+            let known = match shape.as_point() {
+                Some(point) => known_points.iter().any(|known| known.almost_equals(&point)),
+                None => known_shapes.iter().any(|known| known.almost_equals(shape)),
+            };
+            if known {
+                continue;
+            }
+            match find_action(problem, &known_points, &known_shapes, shape) {
+                Some((action_type, constructed)) => {
+                    add_shape(&mut known_points, &mut known_shapes, constructed);
+                    steps.push((action_type, constructed));
+                }
+                None => {
+                    return Err(format!(
+                        "Shape {} ({}) can't be constructed from the elements before it",
+                        i + 1,
+                        shape
+                    ))
+                }
+            }
+        }
+        for element in &problem.elements_to_find {
+            let found = match element {
+                Element::Point(point) => known_points.contains(point),
+                _ => known_shapes.contains(&element.get_shape().unwrap()),
+            };
+            if !found {
+                return Err(format!("Element to find isn't constructed: {:?}", element));
+            }
+        }
+        if steps.len() > problem.action_count as usize {
+            return Err(format!(
+                "The solution takes {} actions, more than {}",
+                steps.len(),
+                problem.action_count
+            ));
+        }
+        Ok(steps)
+    }
+
+    fn verify_shapes_from_file(
+        problem: &ProblemDefinition,
+        input_filename: String,
+    ) -> Result<Vec<(ActionType, Shape)>, String> {
+        Self::verify_shapes(problem, &read_shapes(&input_filename)?)
+    }
+}
+
+mod private {
+    use super::*;
+
+    // The shapes of a file with one shape per line, as written by save_solution_shapes();
+    // empty lines are skipped
+    pub fn read_shapes(filename: &str) -> Result<Vec<Shape>, String> {
+        read_to_string(filename)
+            .map_err(|error| format!("{}: {}", filename, error))?
+            .lines()
+            .enumerate()
+            .filter(|(_, s)| !s.trim().is_empty())
+            .map(|(i, s)| {
+                Shape::from_str(s).map_err(|error| format!("{}:{}: {}", filename, i + 1, error))
+            })
+            .collect()
+    }
+
+    pub fn add_point(known_points: &mut Vec<Point>, point: Point) {
+        if !known_points.contains(&point) {
+            known_points.push(point);
+        }
+    }
+
+    // Points made by tools and Point actions are added to the known points
+    pub fn add_shape(known_points: &mut Vec<Point>, known_shapes: &mut Vec<Shape>, shape: Shape) {
+        if let Some(point) = shape.as_point() {
+            add_point(known_points, point);
+            return;
+        }
+        for known in known_shapes.iter() {
+            for point in known.find_intersection_points(&shape).into_iter().flatten() {
+                add_point(known_points, point);
+            }
+        }
+        known_shapes.push(shape);
+    }
+
+    // Finds an action producing (approximately) the given shape, and returns the precise result
+    pub fn find_action(
+        problem: &ProblemDefinition,
+        points: &[Point],
+        shapes: &[Shape],
+        shape: &Shape,
+    ) -> Option<(ActionType, Shape)> {
+        let lines: Vec<&Shape> = shapes
+            .iter()
+            .filter(|shape| shape.get_direction().is_some())
+            .collect();
+        for action_type in problem.action_types.iter() {
+            let mut candidates = Vec::new();
+            match action_type {
+                ActionType::TwoPointActionType(value) => {
+                    for i1 in 0..points.len() {
+                        for i2 in (i1 + 1)..points.len() {
+                            candidates.push(Action::create_two_point_element(
+                                &points[i1],
+                                &points[i2],
+                                *value,
+                            ));
+                        }
+                    }
+                }
+                ActionType::PointAndLineActionType(value) => {
+                    for point in points {
+                        for line in &lines {
+                            candidates
+                                .push(Action::create_point_and_line_element(point, line, *value));
+                        }
+                    }
+                }
+                ActionType::ThreePointActionType(value) => {
+                    for i1 in 0..points.len() {
+                        for i2 in (i1 + 1)..points.len() {
+                            for i3 in (i2 + 1)..points.len() {
+                                candidates.extend(Action::create_three_point_element(
+                                    &points[i1],
+                                    &points[i2],
+                                    &points[i3],
+                                    *value,
+                                ));
+                            }
+                        }
+                    }
+                }
+                ActionType::TwoPointAndLineActionType(value) => {
+                    for i1 in 0..points.len() {
+                        for i2 in (i1 + 1)..points.len() {
+                            for line in &lines {
+                                candidates.extend(Action::create_two_point_and_line_element(
+                                    &points[i1],
+                                    &points[i2],
+                                    line,
+                                    *value,
+                                ));
+                            }
+                        }
+                    }
+                }
+                ActionType::ToolActionType(_) | ActionType::LemmaActionType(_) => {
+                    let (kinds, _) = problem.get_input_kinds(*action_type).unwrap();
+                    let output = find_generic_output(
+                        problem,
+                        *action_type,
+                        kinds,
+                        (points, shapes),
+                        shape,
+                        &mut vec![],
+                    );
+                    if let Some(candidate) = output {
+                        return Some((*action_type, candidate));
+                    }
+                }
+                // Placed points can be anywhere on a known shape (or anywhere at all for
+                // free points)
+                ActionType::PointActionType(value) => {
+                    if let Some(point) = shape.as_point() {
+                        let placed = match value {
+                            PointActionType::Free => true,
+                            _ => shapes.iter().any(|known| known.contains_point(&point)),
+                        };
+                        if placed {
+                            return Some((*action_type, *shape));
+                        }
+                    }
+                }
+            }
+            for element in candidates {
+                if let Some(candidate) = element.get_shape() {
+                    if candidate.well_formed() && candidate.almost_equals(shape) {
+                        return Some((*action_type, candidate));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // Applies the tool or lemma to the combinations of distinct known points and shapes of the
+    // right kinds (the inputs chosen so far are in inputs) and returns the result matching the
+    // shape
+    pub fn find_generic_output(
+        problem: &ProblemDefinition,
+        action_type: ActionType,
+        kinds: &[InputKind],
+        (points, shapes): (&[Point], &[Shape]),
+        shape: &Shape,
+        inputs: &mut Vec<usize>,
+    ) -> Option<Shape> {
+        let point_count = kinds
+            .iter()
+            .filter(|kind| **kind == InputKind::Point)
+            .count();
+        let k = inputs.len();
+        if k == kinds.len() {
+            let input_points: Vec<Point> =
+                inputs[..point_count].iter().map(|i| points[*i]).collect();
+            let input_shapes: Vec<Shape> =
+                inputs[point_count..].iter().map(|i| shapes[*i]).collect();
+            return problem
+                .construct(action_type, &input_points, &input_shapes)
+                .filter(|candidate| candidate.well_formed() && candidate.almost_equals(shape));
+        }
+        let (count, same_kind) = if k < point_count {
+            (points.len(), 0..k)
+        } else {
+            (shapes.len(), point_count..k)
+        };
+        for i in 0..count {
+            if inputs[same_kind.clone()].contains(&i)
+                || (k >= point_count && InputKind::of(&shapes[i]) != kinds[k])
+            {
+                continue;
+            }
+            inputs.push(i);
+            let output =
+                find_generic_output(problem, action_type, kinds, (points, shapes), shape, inputs);
+            inputs.pop();
+            if output.is_some() {
+                return output;
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element::ReflectionPAV;
+    use crate::fint::FInt;
+    use crate::SolveOptions;
+
+    fn parse_shapes(lines: &[&str]) -> Vec<Shape> {
+        lines.iter().map(|s| Shape::from_str(s).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_verify_shapes() {
+        let problem =
+            ProblemDefinition::get_problem_by_id("2.8-tangent-to-circle-at-point-with-perp")
+                .unwrap();
+        let shapes = parse_shapes(&[
+            "Circle(c.x=0.000,c.y=0.000,r2=1.000)",
+            "Line(nx=0.000,ny=1.000,d=0.000)",
+            "Line(nx=1.000,ny=0.000,d=1.000)",
+        ]);
+        let steps = Computation::verify_shapes(&problem, &shapes).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[1].1, shapes[2]);
+        assert!(Computation::verify_shapes(&problem, &shapes[..2]).is_err());
+        let unreachable = parse_shapes(&["Line(nx=1.000,ny=0.000,d=0.500)"]);
+        assert!(Computation::verify_shapes(&problem, &unreachable).is_err());
+    }
+
+    #[test]
+    fn test_read_shapes() {
+        let filename = std::env::temp_dir()
+            .join(format!("euc-shapes-{}", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        write(
+            &filename,
+            "Line(nx=0.000,ny=1.000,d=0.000)\n\nLine(nx=1.000)\n",
+        )
+        .unwrap();
+        let result = read_shapes(&filename);
+        std::fs::remove_file(&filename).unwrap();
+        assert!(result
+            .unwrap_err()
+            .starts_with(&format!("{}:3: ", filename)));
+        assert!(read_shapes(&filename).is_err());
+    }
+
+    #[test]
+    fn test_verify_saved_solution() {
+        // The reflection of a point across a line through two other points: the solution
+        // saved by solve() is verified, with the reflected point as its last shape
+        let pt = |x: f64, y: f64| Point(FInt::new(x), FInt::new(y));
+        let (p, l1, l2) = (pt(-1.0, 0.1), pt(-0.8, -0.6), pt(0.9, 0.3));
+        let v = Point(l2.0 - l1.0, l2.1 - l1.1);
+        let mut problem = ProblemDefinition::get_problem_by_id("1.3-midpoint").unwrap();
+        problem.given_elements = vec![Element::Point(p), Element::Point(l1), Element::Point(l2)];
+        problem.elements_to_find = vec![Element::Point(ReflectionPAV { p, a: l1, v }.get_point())];
+        problem.action_count = 2;
+        problem.action_types = ["LINE", "REFLECTION"]
+            .map(|name| name.parse().unwrap())
+            .to_vec()
+            .into();
+        problem.multimatch = true;
+        let filename = std::env::temp_dir()
+            .join(format!("euc-solution-shapes-{}", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let options = SolveOptions {
+            solution_shapes: Some(filename.clone()),
+            ..SolveOptions::without_files()
+        };
+        assert_eq!(crate::solve(&problem, &options).len(), 1);
+        let result = Computation::verify_shapes_from_file(&problem, filename.clone());
+        std::fs::remove_file(&filename).unwrap();
+        assert_eq!(result.map(|steps| steps.len()), Ok(2));
+    }
+}
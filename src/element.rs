@@ -1,3 +1,4 @@
+use serde::Serialize;
 use strum_macros::IntoStaticStr;
 
 use crate::{
@@ -6,7 +7,7 @@ use crate::{
 };
 extern crate strum;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct LineAB {
     pub a: Point,
     pub b: Point,
@@ -38,7 +39,7 @@ impl LineAB {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct LineAV {
     pub a: Point,
     pub v: Point,
@@ -54,7 +55,7 @@ impl LineAV {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CircleCP {
     pub c: Point,
     pub p: Point,
@@ -66,7 +67,7 @@ impl CircleCP {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CircleCR {
     pub c: Point,
     pub r: FInt,
@@ -80,7 +81,7 @@ impl CircleCR {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RayAV {
     pub a: Point,
     pub v: Point,
@@ -95,7 +96,7 @@ impl RayAV {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SegmentAB {
     pub a: Point,
     pub b: Point,
@@ -109,7 +110,7 @@ impl SegmentAB {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct MidPerpAB {
     pub a: Point,
     pub b: Point,
@@ -125,7 +126,7 @@ impl MidPerpAB {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BisectorCVV {
     pub c: Point,
     pub v1: Point,
@@ -148,7 +149,90 @@ impl BisectorCVV {
     }
 }
 
-#[derive(Debug, IntoStaticStr)]
+// The tangent from p to the circle (p must lie outside of it); with pos, the point of
+// tangency is counterclockwise from p as seen from the center
+#[derive(Debug, Clone, Serialize)]
+pub struct TangentPCR {
+    pub p: Point,
+    pub c: Point,
+    pub r: FInt,
+    pub pos: bool,
+}
+impl TangentPCR {
+    pub fn get_shape(&self) -> Line {
+        // The point of tangency, seen from the center: r^2 / d along cp, r * t / d across it
+        let cp = Point(self.p.0 - self.c.0, self.p.1 - self.c.1);
+        let d2 = cp.0.sqr() + cp.1.sqr();
+        let along = self.r.sqr() / d2;
+        let across = self.r * (d2 - self.r.sqr()).sqrt() / d2;
+        let across = if self.pos { across } else { across.negate() };
+        let v = cp.rotated_90_pos();
+        LineAB {
+            a: self.p,
+            b: Point(
+                self.c.0 + cp.0 * along + v.0 * across,
+                self.c.1 + cp.1 * along + v.1 * across,
+            ),
+        }
+        .get_shape()
+    }
+}
+
+// The circle through three points that aren't collinear
+#[derive(Debug, Clone, Serialize)]
+pub struct CircleABC {
+    pub a: Point,
+    pub b: Point,
+    pub c: Point,
+}
+impl CircleABC {
+    pub fn get_shape(&self) -> Circle {
+        let (abx, aby) = (self.b.0 - self.a.0, self.b.1 - self.a.1);
+        let (acx, acy) = (self.c.0 - self.a.0, self.c.1 - self.a.1);
+        let (ab2, ac2) = (abx.sqr() + aby.sqr(), acx.sqr() + acy.sqr());
+        let d = FInt::new(2.0) * (abx * acy - aby * acx);
+        let c = Point(
+            self.a.0 + (acy * ab2 - aby * ac2) / d,
+            self.a.1 + (abx * ac2 - acx * ab2) / d,
+        );
+        CircleCP { c, p: self.a }.get_shape()
+    }
+}
+
+// The reflection of p across the line through a with the direction v
+#[derive(Debug, Clone, Serialize)]
+pub struct ReflectionPAV {
+    pub p: Point,
+    pub a: Point,
+    pub v: Point,
+}
+impl ReflectionPAV {
+    pub fn get_point(&self) -> Point {
+        let (apx, apy) = (self.p.0 - self.a.0, self.p.1 - self.a.1);
+        let k =
+            FInt::new(2.0) * (apx * self.v.0 + apy * self.v.1) / (self.v.0.sqr() + self.v.1.sqr());
+        Point(self.a.0 + self.v.0 * k - apx, self.a.1 + self.v.1 * k - apy)
+    }
+}
+
+// The apex of the equilateral triangle on ab lying to the left of it
+#[derive(Debug, Clone, Serialize)]
+pub struct EquilateralAB {
+    pub a: Point,
+    pub b: Point,
+}
+impl EquilateralAB {
+    pub fn get_point(&self) -> Point {
+        let height = FInt::new(0.75).sqrt();
+        let v = Point(self.b.0 - self.a.0, self.b.1 - self.a.1).rotated_90_pos();
+        Point(
+            (self.a.0 + self.b.0) * FInt::new(0.5) + v.0 * height,
+            (self.a.1 + self.b.1) * FInt::new(0.5) + v.1 * height,
+        )
+    }
+}
+
+#[derive(Debug, Clone, IntoStaticStr, Serialize)]
 pub enum Element {
     Point(Point),
     LineAB(LineAB),
@@ -159,6 +243,11 @@ pub enum Element {
     CircleCR(CircleCR),
     MidPerpAB(MidPerpAB),
     BisectorCVV(BisectorCVV),
+    TangentPCR(TangentPCR),
+    CircleABC(CircleABC),
+    // Points made by macros are shapes (see Shape::point())
+    ReflectionPAV(ReflectionPAV),
+    EquilateralAB(EquilateralAB),
     // SegmentAB(SegmentAB),
 }
 impl Element {
@@ -173,6 +262,14 @@ impl Element {
             Element::CircleCR(circle_cr) => Some(Shape::Circle(circle_cr.get_shape())),
             Element::MidPerpAB(mid_perp_ab) => Some(Shape::Line(mid_perp_ab.get_shape())),
             Element::BisectorCVV(bisector_cvv) => Some(Shape::Line(bisector_cvv.get_shape())),
+            Element::TangentPCR(tangent_pcr) => Some(Shape::Line(tangent_pcr.get_shape())),
+            Element::CircleABC(circle_abc) => Some(Shape::Circle(circle_abc.get_shape())),
+            Element::ReflectionPAV(reflection_pav) => {
+                Some(Shape::point(reflection_pav.get_point()))
+            }
+            Element::EquilateralAB(equilateral_ab) => {
+                Some(Shape::point(equilateral_ab.get_point()))
+            }
         }
     }
 }
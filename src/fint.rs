@@ -6,6 +6,7 @@ use std::{
 };
 
 use float_next_after::NextAfter;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
 
 use crate::hashset2::WithTwoHashes;
 
@@ -38,6 +39,15 @@ impl Display for FInt {
         }
     }
 }
+// Serialized with both bounds, so that the width of the interval is preserved
+impl Serialize for FInt {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("FInt", 2)?;
+        state.serialize_field("lower", &self.0)?;
+        state.serialize_field("upper", &self.1)?;
+        state.end()
+    }
+}
 impl FInt {
     pub const fn zero() -> Self {
         Self(0.0, 0.0)
@@ -61,10 +71,25 @@ impl FInt {
         Self(lower, upper)
     }
 
+    pub fn get_bounds(self) -> (f64, f64) {
+        (self.0, self.1)
+    }
+
     pub fn negate(self) -> FInt {
         Self::new_with_bounds(-self.1, -self.0)
     }
 
+    // Keeps the width of the interval; an interval around 0 starts at 0
+    pub fn abs(self) -> FInt {
+        if self.0 >= 0.0 {
+            self
+        } else if self.1 <= 0.0 {
+            self.negate()
+        } else {
+            Self::new_with_bounds(0.0, f64::max(-self.0, self.1))
+        }
+    }
+
     pub fn inverse(self) -> FInt {
         if self.0.is_nan() || self.1.is_nan() || (self.0 <= 0.0 && self.1 >= 0.0) {
             Self::new_with_bounds(f64::NAN, f64::NAN)
@@ -221,6 +246,22 @@ mod tests {
         assert!(result.1 - result.0 > 0.0);
     }
 
+    #[test]
+    fn test_abs() {
+        assert_eq!(
+            FInt::new_with_bounds(-2.0, -1.0).abs().get_bounds(),
+            (1.0, 2.0)
+        );
+        assert_eq!(
+            FInt::new_with_bounds(1.0, 2.0).abs().get_bounds(),
+            (1.0, 2.0)
+        );
+        assert_eq!(
+            FInt::new_with_bounds(-3.0, 1.0).abs().get_bounds(),
+            (0.0, 3.0)
+        );
+    }
+
     #[test]
     fn test_complex_operation() {
         let result = FInt::new(1.2) / (FInt::new(1.00001) - FInt::new(0.5) * FInt::new(2.0))
@@ -230,4 +271,13 @@ mod tests {
             "FInt(-7.447568350471557e-6, 9.872077498584987e-6)"
         );
     }
+
+    #[test]
+    fn test_serialize_with_bounds() {
+        let x = FInt::new_with_bounds(1.0, 2.5);
+        assert_eq!(
+            serde_json::to_string(&x).unwrap(),
+            "{\"lower\":1.0,\"upper\":2.5}"
+        );
+    }
 }
@@ -1,6 +1,10 @@
 use std::collections::{hash_map, hash_set};
 use std::collections::{HashMap, HashSet};
-use std::hash::{Hash, Hasher};
+use std::hash::{BuildHasherDefault, DefaultHasher, Hash, Hasher};
+
+// Fixed keys rather than std's random ones, so that the iteration order (and so the search)
+// is the same in every run
+type FixedState = BuildHasherDefault<DefaultHasher>;
 
 pub trait WithTwoHashes: Eq + Copy {
     fn hash1<H: Hasher>(&self, state: &mut H);
@@ -36,7 +40,11 @@ impl<'a, T: WithTwoHashes> Iterator for HashSet2Iter<'a, T> {
 }
 
 #[derive(Clone)]
-pub struct HashSet2<T: WithTwoHashes>(HashSet<Hash1Wrapper<T>>, HashSet<Hash2Wrapper<T>>, u32);
+pub struct HashSet2<T: WithTwoHashes>(
+    HashSet<Hash1Wrapper<T>, FixedState>,
+    HashSet<Hash2Wrapper<T>, FixedState>,
+    u32,
+);
 impl<'a, T: WithTwoHashes> IntoIterator for &'a HashSet2<T> {
     type Item = &'a T;
 
@@ -48,7 +56,7 @@ impl<'a, T: WithTwoHashes> IntoIterator for &'a HashSet2<T> {
 }
 impl<T: WithTwoHashes> HashSet2<T> {
     pub fn new() -> Self {
-        HashSet2(HashSet::new(), HashSet::new(), 0)
+        HashSet2(HashSet::default(), HashSet::default(), 0)
     }
 
     pub fn len(&self) -> u32 {
@@ -126,13 +134,13 @@ impl<'a, T: WithTwoHashes, V: Copy> Iterator for HashMap2Iter<'a, T, V> {
 
 #[derive(Debug, Clone)]
 pub struct HashMap2<T: WithTwoHashes, V: Copy>(
-    HashMap<Hash1Wrapper<T>, V>,
-    HashMap<Hash2Wrapper<T>, V>,
+    HashMap<Hash1Wrapper<T>, V, FixedState>,
+    HashMap<Hash2Wrapper<T>, V, FixedState>,
     u32,
 );
 impl<T: WithTwoHashes, V: Copy> HashMap2<T, V> {
     pub fn new() -> Self {
-        HashMap2(HashMap::new(), HashMap::new(), 0)
+        HashMap2(HashMap::default(), HashMap::default(), 0)
     }
 
     pub fn insert_if_new(&mut self, key: T, value: V) {
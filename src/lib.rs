@@ -0,0 +1,63 @@
+pub use computation::Computation;
+pub use computation::{PointRef, SearchLimits, ShapeRef, Solution, SolutionStep, SolveOptions};
+pub use fint::FInt;
+use hashset2::HashMap2;
+use hashset2::WithTwoHashes;
+pub use problems::{ActionGroup, ActionType, ProblemDefinition};
+pub use shape::{Point, Shape};
+// use rayon::prelude::*;
+
+pub mod computation;
+pub mod element;
+pub mod fint;
+mod hashset2;
+mod problem_file;
+pub mod problems;
+pub mod shape;
+
+#[allow(unused_macros)]
+macro_rules! box_array {
+    ($val:expr ; $len:expr) => {{
+        // Use a generic function so that the pointer cast remains type-safe
+        fn vec_to_boxed_array<T>(vec: Vec<T>) -> Box<[T; $len]> {
+            let boxed_slice = vec.into_boxed_slice();
+
+            let ptr = ::std::boxed::Box::into_raw(boxed_slice) as *mut [T; $len];
+
+            unsafe { Box::from_raw(ptr) }
+        }
+
+        vec_to_boxed_array(vec![$val; $len])
+    }};
+}
+
+trait VecLengths {
+    fn len_u32(&self) -> u32;
+    fn len_i32(&self) -> i32;
+}
+impl<K: WithTwoHashes, V: Copy> VecLengths for HashMap2<K, V> {
+    fn len_u32(&self) -> u32 {
+        self.len()
+    }
+    fn len_i32(&self) -> i32 {
+        self.len() as i32
+    }
+}
+impl<T> VecLengths for Vec<T> {
+    fn len_u32(&self) -> u32 {
+        self.len() as u32
+    }
+    fn len_i32(&self) -> i32 {
+        self.len() as i32
+    }
+}
+
+// Searches for a construction solving the problem; SolveOptions tells which files to write
+pub fn solve(problem: &ProblemDefinition, options: &SolveOptions) -> Vec<Solution> {
+    if problem.iterative_deepening {
+        return Computation::solve_iteratively(problem, options);
+    }
+    let mut computation = Computation::new(problem);
+    computation.initialize_queue();
+    computation.solve(options)
+}
@@ -1,67 +1,451 @@
-use computation::Computation;
-use computation::PrintState;
-
-use fint::FInt;
-use hashset2::HashMap2;
-use hashset2::WithTwoHashes;
-use problems::ProblemDefinition;
-// use rayon::prelude::*;
-
-mod computation;
-mod element;
-mod fint;
-mod hashset2;
-mod problems;
-mod shape;
-
-#[allow(unused_macros)]
-macro_rules! box_array {
-    ($val:expr ; $len:expr) => {{
-        // Use a generic function so that the pointer cast remains type-safe
-        fn vec_to_boxed_array<T>(vec: Vec<T>) -> Box<[T; $len]> {
-            let boxed_slice = vec.into_boxed_slice();
-
-            let ptr = ::std::boxed::Box::into_raw(boxed_slice) as *mut [T; $len];
-
-            unsafe { Box::from_raw(ptr) }
-        }
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand};
+use euc::computation::{Checkpoint, DrawFormat, DrawOptions, DrawState, Lemma, PrintState, Verify};
+use euc::problems::{ProblemStatus, ProblemVariant};
+use euc::{ActionGroup, ActionType, Computation, ProblemDefinition, SearchLimits, SolveOptions};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const DEFAULT_GENERALITY_TRIALS: u32 = 10;
 
-        vec_to_boxed_array(vec![$val; $len])
-    }};
+#[derive(Parser)]
+#[command(name = "euc", about = "Searches for Euclidea constructions")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-trait VecLengths {
-    fn len_u32(&self) -> u32;
-    fn len_i32(&self) -> i32;
+#[derive(Subcommand)]
+enum Command {
+    /// Solve a problem given by a problem file or a built-in problem id
+    Solve {
+        /// Problem file or built-in problem id (see `list`); defaults to get_problem()
+        problem: Option<String>,
+        #[command(flatten)]
+        overrides: ProblemOverrides,
+        #[command(flatten)]
+        output: Box<OutputArgs>,
+        #[command(flatten)]
+        limits: LimitArgs,
+        /// Threads checking the new actions (all cores by default); the results are the same
+        /// with any number of threads
+        #[arg(long)]
+        threads: Option<usize>,
+        #[command(flatten)]
+        generality: GeneralityArgs,
+    },
+    /// Draw the shapes listed in a file (one Line/Circle per line) to an SVG, TikZ (.tex) or
+    /// Asymptote (.asy) file
+    Draw {
+        shapes: String,
+        #[arg(short, long, default_value = "shapes.svg")]
+        output: String,
+        #[command(flatten)]
+        draw: DrawArgs,
+    },
+    /// List the built-in problems
+    List {
+        #[arg(long)]
+        pack: Option<u32>,
+        #[arg(long)]
+        level: Option<u32>,
+        /// basic, advanced, full, random-walk or modified
+        #[arg(long)]
+        variant: Option<ProblemVariant>,
+        /// solved, solved-with-random-walk, too-large, too-many-actions, not-solved or unknown
+        #[arg(long)]
+        status: Option<ProblemStatus>,
+    },
+    /// Check that a shapes file (as written by `solve --solution-shapes`) solves a problem
+    Verify {
+        problem: String,
+        solution: String,
+        #[command(flatten)]
+        overrides: ProblemOverrides,
+    },
 }
-impl<K: WithTwoHashes, V: Copy> VecLengths for HashMap2<K, V> {
-    fn len_u32(&self) -> u32 {
-        self.len() as u32
-    }
-    fn len_i32(&self) -> i32 {
-        self.len() as i32
+
+#[derive(Args)]
+struct ProblemOverrides {
+    /// Override the number of actions allowed
+    #[arg(long)]
+    action_count: Option<u32>,
+    /// Override the allowed actions: a preset (BASIC, ADVANCED, FULL, ...) or a comma-separated list
+    #[arg(long)]
+    actions: Option<String>,
+    /// Costs of the action types for --cost-budget: E_MOVES or a comma-separated list like
+    /// PERP=3,PAR=4 (unlisted action types cost 1)
+    #[arg(long)]
+    action_costs: Option<String>,
+    /// Limit the total cost of the actions (in addition to the action count)
+    #[arg(long)]
+    cost_budget: Option<u32>,
+    /// Limit the uses of action types or categories, e.g. CIRCLE12=0,CIRCLE21=0 or
+    /// ThreePointActionType=1
+    #[arg(long)]
+    action_limits: Option<String>,
+    /// Keep searching for solutions with fewer E-moves (elementary moves) after the first one
+    #[arg(long)]
+    minimize_e_moves: bool,
+    /// Solve with 1, 2, ... actions up to the action count, proving that the first solutions
+    /// found use the fewest actions
+    #[arg(long)]
+    iterative_deepening: bool,
+    /// Add compound tools recorded from solutions of built-in problems, e.g.
+    /// TANGENT_AT=2.8-tangent-to-circle-at-point-with-perp (the outputs after the first one are
+    /// named TANGENT_AT_2, ...). Lemmas are recorded again on each run and aren't saved with
+    /// solutions or checkpoints: pass the same --lemmas to verify a solution using them.
+    #[arg(long)]
+    lemmas: Option<String>,
+    /// Use a random instance of a symbolic problem, drawn with this seed
+    #[arg(long)]
+    random_instance: Option<u64>,
+}
+
+#[derive(Args)]
+struct GeneralityArgs {
+    /// Replay each solution on this many perturbed instances of the problem (by default 10 for
+    /// symbolic problems, 0 otherwise)
+    #[arg(long)]
+    check_generality: Option<u32>,
+    /// How far the given elements are moved by a perturbation
+    #[arg(long, default_value_t = 0.1)]
+    perturbation: f64,
+    /// Seed of the first perturbation (the others use the next seeds)
+    #[arg(long, default_value_t = 0)]
+    perturbation_seed: u64,
+}
+
+#[derive(Args)]
+struct OutputArgs {
+    /// SVG file with all explored shapes (empty to disable); .tex or .asy for TikZ or Asymptote
+    #[arg(long, default_value = "final.svg")]
+    final_svg: String,
+    /// SVG file with the solution (empty to disable); .tex or .asy for TikZ or Asymptote
+    #[arg(long, default_value = "solution.svg")]
+    solution_svg: String,
+    /// Prefix of the SVG files with random walk solutions (empty to disable)
+    #[arg(long, default_value = "rw_solution_")]
+    rw_solution_prefix: String,
+    /// Format of the random walk solutions: svg, tikz or asymptote
+    #[arg(long, default_value = "svg")]
+    rw_solution_format: DrawFormat,
+    /// Text file with the shapes of the solution, to be checked with `verify`
+    #[arg(long)]
+    solution_shapes: Option<String>,
+    /// JSON file with the structured solutions
+    #[arg(long)]
+    solutions_json: Option<String>,
+    /// JSON file with the search state (point and shape origins, deps, found elements)
+    #[arg(long)]
+    state_json: Option<String>,
+    /// GeoGebra script (commands for the input bar) reproducing the solution
+    #[arg(long)]
+    geogebra: Option<String>,
+    /// Step-by-step replay of the solution: an HTML page (.html) or an animated SVG
+    #[arg(long)]
+    replay: Option<String>,
+    /// File where the search state is saved periodically, to be continued with --resume
+    #[arg(long)]
+    checkpoint: Option<String>,
+    /// Seconds between the checkpoints
+    #[arg(long, default_value_t = 600)]
+    checkpoint_interval: u64,
+    /// Continue the search saved in this checkpoint file (with the same problem and options)
+    #[arg(long)]
+    resume: Option<String>,
+    #[command(flatten)]
+    draw: DrawArgs,
+}
+
+#[derive(Args)]
+struct LimitArgs {
+    /// Seconds after which the search stops
+    #[arg(long)]
+    max_time: Option<u64>,
+    /// Number of explored actions after which the search stops
+    #[arg(long, default_value_t = 1000000)]
+    max_iterations: u32,
+    /// Number of queued actions above which the search stops
+    #[arg(long, default_value_t = 100000000)]
+    max_queue_size: usize,
+    /// Resident memory (in MB) above which the search stops
+    #[arg(long)]
+    max_memory: Option<u64>,
+}
+impl LimitArgs {
+    fn to_search_limits(&self) -> SearchLimits {
+        SearchLimits {
+            max_time: self.max_time.map(Duration::from_secs),
+            max_iterations: self.max_iterations,
+            max_queue_size: self.max_queue_size,
+            max_memory_mb: self.max_memory,
+        }
     }
 }
-impl<T> VecLengths for Vec<T> {
-    fn len_u32(&self) -> u32 {
-        self.len() as u32
+
+#[derive(Args)]
+struct DrawArgs {
+    /// Half-width of the drawn square centered at the origin (by default it's fitted to the
+    /// drawn elements)
+    #[arg(long)]
+    half_width: Option<f64>,
+    /// Cut lines and rays at the border of the drawing
+    #[arg(long)]
+    clip_lines: bool,
+    /// Label points and shapes with their names
+    #[arg(long)]
+    labels: bool,
+}
+impl DrawArgs {
+    fn to_draw_options(&self) -> DrawOptions {
+        DrawOptions {
+            half_width: self.half_width,
+            clip_lines: self.clip_lines,
+            labels: self.labels,
+        }
     }
-    fn len_i32(&self) -> i32 {
-        self.len() as i32
+}
+impl OutputArgs {
+    fn to_solve_options(&self) -> SolveOptions {
+        let non_empty = |s: &String| if s.is_empty() { None } else { Some(s.clone()) };
+        SolveOptions {
+            final_svg: non_empty(&self.final_svg),
+            solution_svg: non_empty(&self.solution_svg),
+            rw_solution_prefix: non_empty(&self.rw_solution_prefix),
+            solution_shapes: self.solution_shapes.clone(),
+            solutions_json: self.solutions_json.clone(),
+            state_json: self.state_json.clone(),
+            geogebra_script: self.geogebra.clone(),
+            replay: self.replay.clone(),
+            rw_solution_format: self.rw_solution_format,
+            draw_options: self.draw.to_draw_options(),
+            checkpoint: self.checkpoint.clone(),
+            checkpoint_interval: Duration::from_secs(self.checkpoint_interval),
+            limits: SearchLimits::default(),
+            threads: None,
+        }
     }
 }
 
 struct Main();
 impl Main {
-    fn compute() {
-        let problem = ProblemDefinition::get_problem();
-        let mut computation = Computation::new(&problem);
-
-        computation.initialize_queue();
-        computation.print_state();
-        println!("Running...");
-        computation.solve();
+    fn load_problem(
+        name: Option<&String>,
+        overrides: &ProblemOverrides,
+    ) -> Result<ProblemDefinition, String> {
+        let mut problem = match name {
+            None => ProblemDefinition::get_problem(),
+            Some(name) if Path::new(name).is_file() => ProblemDefinition::from_file(name)?,
+            Some(name) => ProblemDefinition::get_problem_by_id(name).ok_or(format!(
+                "No such problem file or built-in problem: {}",
+                name
+            ))?,
+        };
+        if let Some(lemmas) = &overrides.lemmas {
+            for s in lemmas.split(',') {
+                let (name, id) = s
+                    .split_once('=')
+                    .ok_or(format!("Expected NAME=problem-id, found {}", s))?;
+                let lemma_problem = ProblemDefinition::get_problem_by_id(id.trim())
+                    .ok_or(format!("No such built-in problem: {}", id))?;
+                problem.add_lemma(Lemma::record(&lemma_problem, name.trim())?)?;
+            }
+        }
+        if let Some(action_count) = overrides.action_count {
+            problem.action_count = action_count;
+        }
+        if let Some(actions) = &overrides.actions {
+            problem.action_types = match ProblemDefinition::get_preset(actions) {
+                Some(preset) => preset.action_types,
+                None => actions
+                    .split(',')
+                    .map(|s| problem.parse_action_type(s.trim()))
+                    .collect::<Result<Vec<ActionType>, String>>()?
+                    .into(),
+            };
+        }
+        if let Some(costs) = &overrides.action_costs {
+            if costs == "E_MOVES" {
+                problem.set_e_move_costs();
+            } else {
+                problem.action_costs = costs
+                    .split(',')
+                    .map(|s| {
+                        let (name, cost) = Self::parse_assignment(s)?;
+                        Ok((problem.parse_action_type(name)?, cost))
+                    })
+                    .collect::<Result<Vec<(ActionType, u32)>, String>>()?
+                    .into();
+            }
+        }
+        if let Some(budget) = overrides.cost_budget {
+            problem.cost_budget = Some(budget);
+        }
+        if let Some(limits) = &overrides.action_limits {
+            problem.action_limits = limits
+                .split(',')
+                .map(|s| {
+                    let (name, limit) = Self::parse_assignment(s)?;
+                    Ok((ActionGroup::from_str(name)?, limit))
+                })
+                .collect::<Result<Vec<(ActionGroup, u32)>, String>>()?
+                .into();
+        }
+        if overrides.minimize_e_moves {
+            problem.minimize_e_moves = true;
+            problem.multimatch = true;
+        }
+        if overrides.iterative_deepening {
+            problem.iterative_deepening = true;
+        }
+        if let Some(seed) = overrides.random_instance {
+            problem = problem.random_instance(&mut StdRng::seed_from_u64(seed))?;
+        }
+        Ok(problem)
+    }
+
+    // NAME=value, e.g. PERP=3
+    fn parse_assignment(s: &str) -> Result<(&str, u32), String> {
+        let (name, value) = s
+            .split_once('=')
+            .ok_or(format!("Expected NAME=value, found {}", s))?;
+        let value = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("Wrong value for {}: {}", name, value))?;
+        Ok((name.trim(), value))
+    }
+
+    fn compute(
+        problem: &ProblemDefinition,
+        options: &SolveOptions,
+        resume: Option<&String>,
+        generality: Option<&GeneralityArgs>,
+    ) -> Result<(), String> {
+        let solutions = if problem.iterative_deepening {
+            if resume.is_some() {
+                return Err("Iterative deepening searches can't be resumed".to_string());
+            }
+            Computation::solve_iteratively(problem, options)
+        } else {
+            let mut computation = Computation::new(problem);
+
+            match resume {
+                Some(filename) => computation.load_checkpoint(filename)?,
+                None => computation.initialize_queue(),
+            }
+            computation.print_state();
+            println!("Running...");
+            computation.solve(options)
+        };
+        for (i, solution) in solutions.iter().enumerate() {
+            let cost = match problem.cost_budget {
+                Some(_) => format!(", cost {}", solution.cost),
+                None => String::new(),
+            };
+            print!(
+                "--- Solution {} ({}L, {}E{}, found in {} seconds) ---\n{}",
+                i + 1,
+                solution.step_count,
+                solution.e_moves,
+                cost,
+                solution.elapsed.as_secs(),
+                solution
+            );
+            let Some(args) = generality else {
+                continue;
+            };
+            let trials = args.check_generality.unwrap_or(match problem.symbolic {
+                Some(_) => DEFAULT_GENERALITY_TRIALS,
+                None => 0,
+            });
+            if trials > 0 {
+                match solution.check_generality(
+                    problem,
+                    trials,
+                    args.perturbation,
+                    args.perturbation_seed,
+                ) {
+                    Ok(report) => print!("Generality: {}", report),
+                    Err(message) => println!("Generality: {}", message),
+                }
+            }
+        }
         println!("Finished");
+        Ok(())
+    }
+
+    fn run(command: Command) -> Result<(), String> {
+        match command {
+            Command::Solve {
+                problem,
+                overrides,
+                output,
+                limits,
+                threads,
+                generality,
+            } => {
+                let problem = Self::load_problem(problem.as_ref(), &overrides)?;
+                let options = SolveOptions {
+                    limits: limits.to_search_limits(),
+                    threads,
+                    ..output.to_solve_options()
+                };
+                Self::compute(
+                    &problem,
+                    &options,
+                    output.resume.as_ref(),
+                    Some(&generality),
+                )?;
+            }
+            Command::Draw {
+                shapes,
+                output,
+                draw,
+            } => Computation::draw_shapes_from_file(shapes, output, &draw.to_draw_options())?,
+            Command::List {
+                pack,
+                level,
+                variant,
+                status,
+            } => {
+                for entry in ProblemDefinition::get_problem_entries() {
+                    if pack.is_some_and(|pack| pack != entry.pack)
+                        || level.is_some_and(|level| level != entry.level)
+                        || variant.is_some_and(|variant| variant != entry.variant)
+                        || status.is_some_and(|status| status != entry.status)
+                    {
+                        continue;
+                    }
+                    let variant: &str = entry.variant.into();
+                    let status: &str = entry.status.into();
+                    match entry.note {
+                        Some(note) => {
+                            println!("{:<52} {:<12} {} ({})", entry.id, variant, status, note)
+                        }
+                        None => println!("{:<52} {:<12} {}", entry.id, variant, status),
+                    }
+                }
+            }
+            Command::Verify {
+                problem,
+                solution,
+                overrides,
+            } => {
+                let problem = Self::load_problem(Some(&problem), &overrides)?;
+                let steps = Computation::verify_shapes_from_file(&problem, solution)?;
+                for (k, (action_type, shape)) in steps.iter().enumerate() {
+                    let name = problem.get_action_type_name(*action_type);
+                    println!("Step {}: {} ({})", k + 1, shape, name);
+                }
+                println!("Solution verified: {} actions", steps.len());
+            }
+        }
+        Ok(())
     }
 }
 
@@ -69,6 +453,17 @@ impl Main {
 // set RUSTFLAGS=-g&& cargo build --release
 
 fn main() {
-    // Computation::draw_shapes_from_file("shapes2.txt".to_string(), "shapes2.svg".to_string(), 5.0);
-    Main::compute();
+    let result = match Cli::parse().command {
+        Some(command) => Main::run(command),
+        None => Main::compute(
+            &ProblemDefinition::get_problem(),
+            &SolveOptions::default(),
+            None,
+            None,
+        ),
+    };
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        std::process::exit(1);
+    }
 }
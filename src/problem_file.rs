@@ -0,0 +1,539 @@
+use std::{collections::HashMap, fmt, fs::read_to_string, str::FromStr};
+
+use crate::{
+    computation::Lemma,
+    element::{
+        BisectorCVV, CircleCP, CircleCR, Element, LineAB, LineAV, MidPerpAB, RayAV, SegmentAB,
+    },
+    fint::FInt,
+    problems::{ActionGroup, ProblemDefinition},
+    shape::Point,
+};
+
+// Problem file format: one statement per line, '#' starts a comment.
+//
+//   preset BASIC                    # action set and flags to start from (first statement only)
+//   let h = sqrt(3) / 2             # named number
+//   point a = (0, h)                # named point
+//   given LineAB a (1, 0)           # given element
+//   find Point (0.5, 0)             # element to find
+//   action_count 4
+//   actions LINE CIRCLE12 CIRCLE21  # overrides the action set of the preset
+//   random_walk_at_n_actions 4
+//   cost PERP 3                     # cost of an action type in the cost budget (default 1)
+//   cost_budget 10
+//   limit CIRCLE12 1                # maximum uses of an action type or a category
+//   limit ThreePointActionType 0
+//   lemma TANGENT_AT 2.8-tangent-to-circle-at-point-with-perp
+//                                   # compound tool recorded from a solution of a built-in problem
+//   multimatch true                 # same for the other boolean flags
+//
+// Element arguments follow the field order of the element: Point p, LineAB a b, LineAV a v,
+// RayAV a v, SegmentAB a b, CircleCP c p, CircleCR c r, MidPerpAB a b, BisectorCVV c v1 v2.
+// Points are either names or (x, y); numbers are expressions with + - * / and sqrt().
+
+// Numbers keep their source text for error messages
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64, String),
+    Name(String),
+    Symbol(char),
+}
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(_, text) => write!(f, "'{}'", text),
+            Token::Name(name) => write!(f, "'{}'", name),
+            Token::Symbol(c) => write!(f, "'{}'", c),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = s.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                i += 1;
+                if i < chars.len() && (chars[i] == '-' || chars[i] == '+') {
+                    i += 1;
+                }
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse()
+                .map_err(|_| format!("wrong number '{}'", text))?;
+            tokens.push(Token::Number(value, text));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else if "()+-*/,=".contains(c) {
+            tokens.push(Token::Symbol(c));
+            i += 1;
+        } else {
+            return Err(format!("unexpected character '{}'", c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Statement<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    numbers: &'a HashMap<String, f64>,
+    points: &'a HashMap<String, Point>,
+}
+impl<'a> Statement<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Symbol(c)) if c == symbol => Ok(()),
+            Some(token) => Err(format!("expected '{}', found {}", symbol, token)),
+            None => Err(format!("expected '{}' at the end of the line", symbol)),
+        }
+    }
+
+    fn expect_name(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Name(name)) => Ok(name),
+            Some(token) => Err(format!("expected a name, found {}", token)),
+            None => Err("expected a name at the end of the line".to_string()),
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), String> {
+        match self.peek() {
+            None => Ok(()),
+            Some(token) => Err(format!("unexpected {} at the end of the line", token)),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<f64, String> {
+        let mut value = self.parse_term()?;
+        while let Some(Token::Symbol(c)) = self.peek() {
+            let c = *c;
+            if c != '+' && c != '-' {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.parse_term()?;
+            value = if c == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    fn parse_term(&mut self) -> Result<f64, String> {
+        let mut value = self.parse_factor()?;
+        while let Some(Token::Symbol(c)) = self.peek() {
+            let c = *c;
+            if c != '*' && c != '/' {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.parse_factor()?;
+            value = if c == '*' { value * rhs } else { value / rhs };
+        }
+        Ok(value)
+    }
+
+    fn parse_factor(&mut self) -> Result<f64, String> {
+        match self.next() {
+            Some(Token::Number(value, _)) => Ok(value),
+            Some(Token::Symbol('-')) => Ok(-self.parse_factor()?),
+            Some(Token::Symbol('(')) => {
+                let value = self.parse_number()?;
+                self.expect_symbol(')')?;
+                Ok(value)
+            }
+            Some(Token::Name(name)) if name == "sqrt" => {
+                self.expect_symbol('(')?;
+                let value = self.parse_number()?;
+                self.expect_symbol(')')?;
+                if value < 0.0 {
+                    return Err(format!("sqrt of a negative number ({})", value));
+                }
+                Ok(value.sqrt())
+            }
+            Some(Token::Name(name)) => self
+                .numbers
+                .get(&name)
+                .copied()
+                .ok_or(format!("unknown number '{}'", name)),
+            Some(token) => Err(format!("expected a number, found {}", token)),
+            None => Err("expected a number at the end of the line".to_string()),
+        }
+    }
+
+    fn parse_point(&mut self) -> Result<Point, String> {
+        match self.next() {
+            Some(Token::Symbol('(')) => {
+                let x = self.parse_number()?;
+                self.expect_symbol(',')?;
+                let y = self.parse_number()?;
+                self.expect_symbol(')')?;
+                Ok(Point(FInt::new(x), FInt::new(y)))
+            }
+            Some(Token::Name(name)) => self
+                .points
+                .get(&name)
+                .copied()
+                .ok_or(format!("unknown point '{}'", name)),
+            Some(token) => Err(format!("expected a point, found {}", token)),
+            None => Err("expected a point at the end of the line".to_string()),
+        }
+    }
+
+    fn parse_element(&mut self) -> Result<Element, String> {
+        let kind = self.expect_name()?;
+        let element = match kind.as_str() {
+            "Point" => Element::Point(self.parse_point()?),
+            "LineAB" => Element::LineAB(LineAB {
+                a: self.parse_point()?,
+                b: self.parse_point()?,
+            }),
+            "LineAV" => Element::LineAV(LineAV {
+                a: self.parse_point()?,
+                v: self.parse_point()?,
+            }),
+            "RayAV" => Element::RayAV(RayAV {
+                a: self.parse_point()?,
+                v: self.parse_point()?,
+            }),
+            "SegmentAB" => Element::SegmentAB(SegmentAB {
+                a: self.parse_point()?,
+                b: self.parse_point()?,
+            }),
+            "CircleCP" => Element::CircleCP(CircleCP {
+                c: self.parse_point()?,
+                p: self.parse_point()?,
+            }),
+            "CircleCR" => Element::CircleCR(CircleCR {
+                c: self.parse_point()?,
+                r: FInt::new(self.parse_number()?),
+            }),
+            "MidPerpAB" => Element::MidPerpAB(MidPerpAB {
+                a: self.parse_point()?,
+                b: self.parse_point()?,
+            }),
+            "BisectorCVV" => Element::BisectorCVV(BisectorCVV {
+                c: self.parse_point()?,
+                v1: self.parse_point()?,
+                v2: self.parse_point()?,
+            }),
+            _ => return Err(format!("unknown element '{}'", kind)),
+        };
+        self.expect_end()?;
+        Ok(element)
+    }
+
+    fn parse_bool(&mut self) -> Result<bool, String> {
+        let value = match self.expect_name()?.as_str() {
+            "true" => true,
+            "false" => false,
+            other => return Err(format!("expected true or false, found '{}'", other)),
+        };
+        self.expect_end()?;
+        Ok(value)
+    }
+
+    fn parse_count(&mut self) -> Result<u32, String> {
+        let value = match self.next() {
+            Some(Token::Number(value, _)) if value >= 0.0 && value.fract() == 0.0 => value as u32,
+            Some(token) => return Err(format!("expected a whole number, found {}", token)),
+            None => return Err("expected a whole number at the end of the line".to_string()),
+        };
+        self.expect_end()?;
+        Ok(value)
+    }
+}
+
+struct ProblemFileParser {
+    problem: ProblemDefinition,
+    numbers: HashMap<String, f64>,
+    points: HashMap<String, Point>,
+    statement_count: u32,
+}
+impl ProblemFileParser {
+    fn new() -> Self {
+        Self {
+            problem: ProblemDefinition::get_preset("BASIC").unwrap(),
+            numbers: HashMap::new(),
+            points: HashMap::new(),
+            statement_count: 0,
+        }
+    }
+
+    fn parse(mut self, s: &str) -> Result<ProblemDefinition, String> {
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap();
+            if line.trim().is_empty() {
+                continue;
+            }
+            self.parse_statement(line)
+                .map_err(|message| format!("line {}: {}", i + 1, message))?;
+            self.statement_count += 1;
+        }
+        if self.problem.action_count == 0 {
+            return Err("action_count is missing".to_string());
+        }
+        if self.problem.elements_to_find.is_empty() {
+            return Err("no elements to find".to_string());
+        }
+        Ok(self.problem)
+    }
+
+    fn parse_statement(&mut self, line: &str) -> Result<(), String> {
+        // Problem ids aren't names, so the statement isn't tokenized
+        if let Some(rest) = line.trim().strip_prefix("lemma ") {
+            let (name, id) = rest
+                .trim()
+                .split_once(char::is_whitespace)
+                .ok_or("expected a lemma name and a problem id")?;
+            let problem = ProblemDefinition::get_problem_by_id(id.trim())
+                .ok_or(format!("unknown problem '{}'", id.trim()))?;
+            return self.problem.add_lemma(Lemma::record(&problem, name)?);
+        }
+        let mut statement = Statement {
+            tokens: tokenize(line)?,
+            pos: 0,
+            numbers: &self.numbers,
+            points: &self.points,
+        };
+        let keyword = statement.expect_name()?;
+        match keyword.as_str() {
+            "preset" => {
+                if self.statement_count > 0 {
+                    return Err("preset must be the first statement".to_string());
+                }
+                let name = statement.expect_name()?;
+                statement.expect_end()?;
+                self.problem = ProblemDefinition::get_preset(&name)
+                    .ok_or(format!("unknown preset '{}'", name))?;
+            }
+            "let" => {
+                let name = statement.expect_name()?;
+                statement.expect_symbol('=')?;
+                let value = statement.parse_number()?;
+                statement.expect_end()?;
+                self.numbers.insert(name, value);
+            }
+            "point" => {
+                let name = statement.expect_name()?;
+                statement.expect_symbol('=')?;
+                let point = statement.parse_point()?;
+                statement.expect_end()?;
+                self.points.insert(name, point);
+            }
+            "given" => {
+                let element = statement.parse_element()?;
+                self.problem.given_elements.push(element);
+            }
+            "find" => {
+                let element = statement.parse_element()?;
+                self.problem.elements_to_find.push(element);
+            }
+            "actions" => {
+                let mut action_types = Vec::new();
+                while !statement.at_end() {
+                    action_types.push(self.problem.parse_action_type(&statement.expect_name()?)?);
+                }
+                if action_types.is_empty() {
+                    return Err("no action types listed".to_string());
+                }
+                self.problem.action_types = action_types.into();
+            }
+            "action_count" => self.problem.action_count = statement.parse_count()?,
+            "random_walk_at_n_actions" => {
+                self.problem.random_walk_at_n_actions = Some(statement.parse_count()?)
+            }
+            "prioritize_low_action_count_shapes" => {
+                self.problem.prioritize_low_action_count_shapes = statement.parse_bool()?
+            }
+            "multimatch" => self.problem.multimatch = statement.parse_bool()?,
+            "find_all_solutions" => self.problem.find_all_solutions = statement.parse_bool()?,
+            "track_supports_in_rw" => self.problem.track_supports_in_rw = statement.parse_bool()?,
+            "minimize_e_moves" => self.problem.minimize_e_moves = statement.parse_bool()?,
+            "iterative_deepening" => self.problem.iterative_deepening = statement.parse_bool()?,
+            "cost" => {
+                let action_type = self.problem.parse_action_type(&statement.expect_name()?)?;
+                let cost = statement.parse_count()?;
+                let mut costs = self.problem.action_costs.to_vec();
+                costs.retain(|(listed, _)| *listed != action_type);
+                costs.push((action_type, cost));
+                self.problem.action_costs = costs.into();
+            }
+            "cost_budget" => self.problem.cost_budget = Some(statement.parse_count()?),
+            "limit" => {
+                let name = statement.expect_name()?;
+                let group = ActionGroup::from_str(&name).or_else(|message| {
+                    self.problem
+                        .parse_action_type(&name)
+                        .map(ActionGroup::Action)
+                        .map_err(|_| message)
+                })?;
+                let limit = statement.parse_count()?;
+                let mut limits = self.problem.action_limits.to_vec();
+                limits.retain(|(listed, _)| *listed != group);
+                limits.push((group, limit));
+                self.problem.action_limits = limits.into();
+            }
+            _ => return Err(format!("unknown statement '{}'", keyword)),
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ProblemDefinition {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ProblemFileParser::new().parse(s)
+    }
+}
+
+impl ProblemDefinition {
+    pub fn from_file(filename: &str) -> Result<Self, String> {
+        let contents =
+            read_to_string(filename).map_err(|error| format!("{}: {}", filename, error))?;
+        Self::from_str(&contents).map_err(|message| format!("{}: {}", filename, message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problems::ActionType;
+
+    #[test]
+    fn test_parse_problem() {
+        let problem = ProblemDefinition::from_str(
+            "
+            # Heron's problem (7.5)
+            let k = sqrt(0.36) + 0.2 * 0
+            point x = (0, 0)
+            point p1 = (k, 0.8)
+            given LineAV (0.12345, 0) (1, 0)
+            given Point p1
+            given Point (-0.72, 0.96)
+            find LineAB x p1
+            find CircleCR x 2 * k
+            action_count 4
+            multimatch true
+            ",
+        )
+        .unwrap();
+        assert_eq!(problem.given_elements.len(), 3);
+        assert_eq!(problem.elements_to_find.len(), 2);
+        assert_eq!(problem.action_count, 4);
+        assert!(problem.multimatch);
+        assert_eq!(
+            problem.action_types,
+            ProblemDefinition::get_preset("BASIC").unwrap().action_types
+        );
+        match &problem.given_elements[1] {
+            Element::Point(point) => assert_eq!(*point, Point(FInt::new(0.6), FInt::new(0.8))),
+            _ => panic!("Expected a point"),
+        }
+        match &problem.elements_to_find[1] {
+            Element::CircleCR(circle) => assert_eq!(circle.r, FInt::new(1.2)),
+            _ => panic!("Expected a circle"),
+        }
+    }
+
+    #[test]
+    fn test_preset_and_actions() {
+        let problem = ProblemDefinition::from_str(
+            "preset ADVANCED\nactions LINE PERP\nfind Point (0, 0)\naction_count 2\n\
+             cost PERP 3\ncost PERP 2\ncost_budget 5",
+        )
+        .unwrap();
+        let perp = ActionType::from_str("PERP").unwrap();
+        assert_eq!(problem.get_action_cost(perp), 2);
+        assert_eq!(problem.cost_budget, Some(5));
+        assert_eq!(
+            problem.action_types.as_ref(),
+            [
+                ActionType::from_str("LINE").unwrap(),
+                ActionType::from_str("PERP").unwrap()
+            ]
+        );
+
+        let problem = ProblemDefinition::from_str(
+            "find Point (0, 0)\naction_count 2\nlimit CIRCLE12 1\nlimit TwoPointActionType 3",
+        )
+        .unwrap();
+        assert_eq!(
+            problem.action_limits.as_ref(),
+            [
+                (
+                    ActionGroup::Action(ActionType::from_str("CIRCLE12").unwrap()),
+                    1
+                ),
+                (ActionGroup::TwoPointActions, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bundled_problem_files() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/problems");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if let Err(message) = ProblemDefinition::from_file(path.to_str().unwrap()) {
+                panic!("{}", message);
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors_have_line_numbers() {
+        let error = ProblemDefinition::from_str("action_count 2\n\ngiven LineAB (0, 0)\n")
+            .err()
+            .unwrap();
+        assert_eq!(error, "line 3: expected a point at the end of the line");
+        let error = ProblemDefinition::from_str("action_count 2\ngiven Point q\n")
+            .err()
+            .unwrap();
+        assert_eq!(error, "line 2: unknown point 'q'");
+        let error = ProblemDefinition::from_str("action_count 2\npreset FULL\n")
+            .err()
+            .unwrap();
+        assert_eq!(error, "line 2: preset must be the first statement");
+        // Tokens are quoted as written
+        let error = ProblemDefinition::from_str("action_count 2\nlet 3e0 = 1\n")
+            .err()
+            .unwrap();
+        assert_eq!(error, "line 2: expected a name, found '3e0'");
+        let error = ProblemDefinition::from_str("action_count 2 x\n")
+            .err()
+            .unwrap();
+        assert_eq!(error, "line 1: unexpected 'x' at the end of the line");
+    }
+}
@@ -1,14 +1,39 @@
+use std::{borrow::Cow, f64::consts::PI, fmt, str::FromStr};
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Serialize, Serializer};
+
 use crate::{
+    computation::{InputKind, Lemma, Tool, TOOLS},
     element::{CircleCP, CircleCR, Element, LineAB, LineAV, RayAV, SegmentAB},
     fint::FInt,
     shape::{Point, Shape, ShapeTrait},
 };
 
+mod registry;
+mod symbolic;
+pub mod targets;
+
+use registry::PROBLEMS;
+pub use registry::{ProblemEntry, ProblemStatus, ProblemVariant};
+pub use symbolic::{Parameter, ProblemElements, SymbolicProblem};
+use targets::{
+    fermat_point, harmonic_mean, incircle, point_along, reflection, regular_polygon, tangent_at,
+};
+
+const DEFAULT_PROBLEM_ID: &str = "15.8-line-circle-intersection-mod";
+
 fn pt(x: f64, y: f64) -> Point {
     Point(FInt::new(x), FInt::new(y))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+// A random shift in [-amount, amount]
+fn jitter(rng: &mut StdRng, amount: f64) -> f64 {
+    rng.random_range(-amount..=amount)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub enum TwoPointActionType {
     Line,
     Circle12,
@@ -17,14 +42,14 @@ pub enum TwoPointActionType {
     Last,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub enum PointAndLineActionType {
     Perp,
     Par,
     Last,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub enum ThreePointActionType {
     CircleCAB,
     CircleACB,
@@ -35,7 +60,7 @@ pub enum ThreePointActionType {
     Last,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub enum TwoPointAndLineActionType {
     BisectorPosCAL, // "Positive" means that line.get_direction() is used (not -line.get_direction())
     BisectorPosACL,
@@ -44,12 +69,60 @@ pub enum TwoPointAndLineActionType {
     Last,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+// Euclidea's Point tool: a point at a generic position on a shape or in the plane (see
+// Action::create_placed_point())
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum PointActionType {
+    OnShape,
+    Free,
+    Last,
+}
+
+// An entry of TOOLS, the tools handled generically by the search (see Tool)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ToolActionType(u8);
+impl ToolActionType {
+    pub fn get_tool(self) -> &'static dyn Tool {
+        TOOLS[self.0 as usize]
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        TOOLS
+            .iter()
+            .position(|tool| tool.name() == name)
+            .map(|index| ToolActionType(index as u8))
+    }
+}
+impl fmt::Debug for ToolActionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_tool().name())
+    }
+}
+impl Serialize for ToolActionType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.get_tool().name())
+    }
+}
+
+// The output with the given index of a lemma of the problem (see ProblemDefinition::lemmas).
+// The inputs are point_count points followed by shape_count shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct LemmaActionType {
+    pub lemma: u8,
+    pub output: u8,
+    pub point_count: u8,
+    pub shape_count: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub enum ActionType {
     TwoPointActionType(TwoPointActionType),
     PointAndLineActionType(PointAndLineActionType),
     ThreePointActionType(ThreePointActionType),
     TwoPointAndLineActionType(TwoPointAndLineActionType),
+    PointActionType(PointActionType),
+    ToolActionType(ToolActionType),
+    LemmaActionType(LemmaActionType),
 }
 impl ActionType {
     const LINE: Self = ActionType::TwoPointActionType(TwoPointActionType::Line);
@@ -72,18 +145,205 @@ impl ActionType {
         ActionType::TwoPointAndLineActionType(TwoPointAndLineActionType::BisectorNegCAL);
     const BISECTOR_NEG_ACL: Self =
         ActionType::TwoPointAndLineActionType(TwoPointAndLineActionType::BisectorNegACL);
+    const POINT_ON_SHAPE: Self = ActionType::PointActionType(PointActionType::OnShape);
+    const FREE_POINT: Self = ActionType::PointActionType(PointActionType::Free);
+
+    // Names used in problem files and on the command line (tools are named by Tool::name())
+    const NAMED: [(&str, Self); 18] = [
+        ("LINE", Self::LINE),
+        ("CIRCLE12", Self::CIRCLE12),
+        ("CIRCLE21", Self::CIRCLE21),
+        ("MID_PERP", Self::MID_PERP),
+        ("PERP", Self::PERP),
+        ("PAR", Self::PAR),
+        ("CIRCLE_CAB", Self::CIRCLE_CAB),
+        ("CIRCLE_ACB", Self::CIRCLE_ACB),
+        ("CIRCLE_ABC", Self::CIRCLE_ABC),
+        ("BISECTOR_CAB", Self::BISECTOR_CAB),
+        ("BISECTOR_ACB", Self::BISECTOR_ACB),
+        ("BISECTOR_ABC", Self::BISECTOR_ABC),
+        ("BISECTOR_POS_CAL", Self::BISECTOR_POS_CAL),
+        ("BISECTOR_POS_ACL", Self::BISECTOR_POS_ACL),
+        ("BISECTOR_NEG_CAL", Self::BISECTOR_NEG_CAL),
+        ("BISECTOR_NEG_ACL", Self::BISECTOR_NEG_ACL),
+        ("POINT_ON_SHAPE", Self::POINT_ON_SHAPE),
+        ("FREE_POINT", Self::FREE_POINT),
+    ];
+
+    // Euclidea's E-moves (elementary compass and straightedge moves) for one use of the tool;
+    // a tool use is one L-move. Perp and bisectors take the minimal count, e.g. Perp through a
+    // point on the line is 4E in Euclidea. Lemmas belong to a problem, so this is private and
+    // ProblemDefinition::get_e_moves() is the way to count all action types.
+    fn e_moves(self) -> u32 {
+        match self {
+            ActionType::TwoPointActionType(value) => match value {
+                TwoPointActionType::Line
+                | TwoPointActionType::Circle12
+                | TwoPointActionType::Circle21 => 1,
+                TwoPointActionType::MidPerp => 3,
+                TwoPointActionType::Last => panic!("Can't happen"),
+            },
+            ActionType::PointAndLineActionType(value) => match value {
+                PointAndLineActionType::Perp => 3,
+                PointAndLineActionType::Par => 4,
+                PointAndLineActionType::Last => panic!("Can't happen"),
+            },
+            ActionType::ThreePointActionType(value) => match value {
+                ThreePointActionType::CircleCAB
+                | ThreePointActionType::CircleACB
+                | ThreePointActionType::CircleABC => 5,
+                ThreePointActionType::BisectorCAB
+                | ThreePointActionType::BisectorACB
+                | ThreePointActionType::BisectorABC => 4,
+                ThreePointActionType::Last => panic!("Can't happen"),
+            },
+            ActionType::TwoPointAndLineActionType(_) => 4,
+            // Euclidea doesn't count points in the moves
+            ActionType::PointActionType(_) => 0,
+            ActionType::ToolActionType(value) => value.get_tool().e_moves(),
+            ActionType::LemmaActionType(_) => {
+                panic!("Lemmas are counted by ProblemDefinition::get_e_moves()")
+            }
+        }
+    }
+
+    // The name used in problem files; None for lemmas (see
+    // ProblemDefinition::get_action_type_name())
+    pub fn name(self) -> Option<&'static str> {
+        match self {
+            ActionType::ToolActionType(value) => Some(value.get_tool().name()),
+            _ => Self::NAMED
+                .iter()
+                .find(|(_, action_type)| *action_type == self)
+                .map(|(name, _)| *name),
+        }
+    }
+
+    pub fn get_tool(self) -> Option<&'static dyn Tool> {
+        match self {
+            ActionType::ToolActionType(value) => Some(value.get_tool()),
+            _ => None,
+        }
+    }
+
+    // Numbers of input points and shapes of the actions handled generically (tools and lemmas)
+    pub fn get_input_counts(self) -> Option<(usize, usize)> {
+        match self {
+            ActionType::ToolActionType(value) => {
+                let inputs = value.get_tool().inputs();
+                let point_count = inputs
+                    .iter()
+                    .filter(|kind| **kind == InputKind::Point)
+                    .count();
+                Some((point_count, inputs.len() - point_count))
+            }
+            ActionType::LemmaActionType(value) => {
+                Some((value.point_count as usize, value.shape_count as usize))
+            }
+            _ => None,
+        }
+    }
+}
+impl FromStr for ActionType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::NAMED
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, action_type)| *action_type)
+            .or(ToolActionType::from_name(s).map(ActionType::ToolActionType))
+            .ok_or("Unknown action type: ".to_string() + s)
+    }
+}
+
+// Action types sharing a usage limit (see ProblemDefinition::action_limits): a single action
+// type, a whole category or the tools of a category (see Tool::category())
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionGroup {
+    Action(ActionType),
+    TwoPointActions,
+    PointAndLineActions,
+    ThreePointActions,
+    TwoPointAndLineActions,
+    PointActions,
+    ToolActions(&'static str),
+    LemmaActions,
+}
+impl ActionGroup {
+    pub fn contains(self, action_type: ActionType) -> bool {
+        match self {
+            ActionGroup::Action(value) => value == action_type,
+            ActionGroup::TwoPointActions => {
+                matches!(action_type, ActionType::TwoPointActionType(_))
+            }
+            ActionGroup::PointAndLineActions => {
+                matches!(action_type, ActionType::PointAndLineActionType(_))
+            }
+            ActionGroup::ThreePointActions => {
+                matches!(action_type, ActionType::ThreePointActionType(_))
+            }
+            ActionGroup::TwoPointAndLineActions => {
+                matches!(action_type, ActionType::TwoPointAndLineActionType(_))
+            }
+            ActionGroup::PointActions => matches!(action_type, ActionType::PointActionType(_)),
+            ActionGroup::ToolActions(category) => action_type
+                .get_tool()
+                .is_some_and(|tool| tool.category() == category),
+            ActionGroup::LemmaActions => matches!(action_type, ActionType::LemmaActionType(_)),
+        }
+    }
+}
+impl FromStr for ActionGroup {
+    type Err = String;
+    // Categories are named after the enums of their action types, e.g. ThreePointActionType
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "TwoPointActionType" => Ok(ActionGroup::TwoPointActions),
+            "PointAndLineActionType" => Ok(ActionGroup::PointAndLineActions),
+            "ThreePointActionType" => Ok(ActionGroup::ThreePointActions),
+            "TwoPointAndLineActionType" => Ok(ActionGroup::TwoPointAndLineActions),
+            "PointActionType" => Ok(ActionGroup::PointActions),
+            "LemmaActionType" => Ok(ActionGroup::LemmaActions),
+            _ => match TOOLS.iter().find(|tool| tool.category() == s) {
+                Some(tool) => Ok(ActionGroup::ToolActions(tool.category())),
+                None => Ok(ActionGroup::Action(ActionType::from_str(s)?)),
+            },
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct ProblemDefinition {
     pub given_elements: Vec<Element>,
     pub elements_to_find: Vec<Element>,
     pub action_count: u32,
-    pub action_types: &'static [ActionType],
+    pub action_types: Cow<'static, [ActionType]>,
     pub random_walk_at_n_actions: Option<u32>,
     pub prioritize_low_action_count_shapes: bool,
     pub multimatch: bool,
     pub find_all_solutions: bool,
     pub track_supports_in_rw: bool,
+    // Keeps searching after the first solution and only reports solutions with fewer E-moves
+    // than the ones before, so the last one is the best in E-moves within action_count (set
+    // multimatch too, so that alternative constructions of the same shape are kept)
+    pub minimize_e_moves: bool,
+    // Solves with 1, 2,... actions up to action_count, so that the solutions found are known
+    // to use the fewest actions (with multimatch, see Computation::solve_iteratively())
+    pub iterative_deepening: bool,
+    // Costs of the action types that don't cost 1 (see cost_budget)
+    pub action_costs: Cow<'static, [(ActionType, u32)]>,
+    // Limits the total cost of the actions in addition to action_count; the search then
+    // prefers constructions with a low cost rather than few actions
+    pub cost_budget: Option<u32>,
+    // Maximum number of uses of action types or categories in a construction (0 forbids them,
+    // e.g. to look for solutions without the compass)
+    pub action_limits: Cow<'static, [(ActionGroup, u32)]>,
+    // Compound tools recorded from solutions of other problems (see Lemma); they're used
+    // through LemmaActionType
+    pub lemmas: Cow<'static, [Lemma]>,
+    // Set for problems declared by their free parameters (see SymbolicProblem): the given
+    // elements and the elements to find are those of the original parameter values
+    pub symbolic: Option<&'static SymbolicProblem>,
 }
 #[allow(dead_code)]
 impl ProblemDefinition {
@@ -91,56 +351,81 @@ impl ProblemDefinition {
         given_elements: vec![],
         elements_to_find: vec![],
         action_count: 0,
-        action_types: &[ActionType::LINE, ActionType::CIRCLE12, ActionType::CIRCLE21],
+        action_types: Cow::Borrowed(&[
+            ActionType::LINE,
+            ActionType::CIRCLE12,
+            ActionType::CIRCLE21,
+        ]),
         random_walk_at_n_actions: None,
         prioritize_low_action_count_shapes: true,
         multimatch: false,
         find_all_solutions: false,
         track_supports_in_rw: false,
+        minimize_e_moves: false,
+        iterative_deepening: false,
+        action_costs: Cow::Borrowed(&[]),
+        cost_budget: None,
+        action_limits: Cow::Borrowed(&[]),
+        lemmas: Cow::Borrowed(&[]),
+        symbolic: None,
     };
 
     const LIMITED_ADVANCED: ProblemDefinition = ProblemDefinition {
         given_elements: vec![],
         elements_to_find: vec![],
         action_count: 0,
-        action_types: &[
+        action_types: Cow::Borrowed(&[
             ActionType::LINE,
             ActionType::CIRCLE12,
             ActionType::CIRCLE21,
             ActionType::MID_PERP,
             ActionType::PERP,
-        ],
+        ]),
         random_walk_at_n_actions: None,
         prioritize_low_action_count_shapes: true,
         multimatch: false,
         find_all_solutions: false,
         track_supports_in_rw: false,
+        minimize_e_moves: false,
+        iterative_deepening: false,
+        action_costs: Cow::Borrowed(&[]),
+        cost_budget: None,
+        action_limits: Cow::Borrowed(&[]),
+        lemmas: Cow::Borrowed(&[]),
+        symbolic: None,
     };
 
     const ADVANCED: ProblemDefinition = ProblemDefinition {
         given_elements: vec![],
         elements_to_find: vec![],
         action_count: 0,
-        action_types: &[
+        action_types: Cow::Borrowed(&[
             ActionType::LINE,
             ActionType::CIRCLE12,
             ActionType::CIRCLE21,
             ActionType::MID_PERP,
             ActionType::PERP,
             ActionType::PAR,
-        ],
+        ]),
         random_walk_at_n_actions: None,
         prioritize_low_action_count_shapes: true,
         multimatch: false,
         find_all_solutions: false,
         track_supports_in_rw: false,
+        minimize_e_moves: false,
+        iterative_deepening: false,
+        action_costs: Cow::Borrowed(&[]),
+        cost_budget: None,
+        action_limits: Cow::Borrowed(&[]),
+        lemmas: Cow::Borrowed(&[]),
+        symbolic: None,
     };
 
     const FULL_WITHOUT_BISECTOR: ProblemDefinition = ProblemDefinition {
         given_elements: vec![],
         elements_to_find: vec![],
         action_count: 0,
-        action_types: &[
+        action_types: Cow::Borrowed(&[
             ActionType::LINE,
             ActionType::CIRCLE12,
             ActionType::CIRCLE21,
@@ -150,19 +435,26 @@ impl ProblemDefinition {
             ActionType::CIRCLE_CAB,
             ActionType::CIRCLE_ACB,
             ActionType::CIRCLE_ABC,
-        ],
+        ]),
         random_walk_at_n_actions: None,
         prioritize_low_action_count_shapes: true,
         multimatch: false,
         find_all_solutions: false,
         track_supports_in_rw: false,
+        minimize_e_moves: false,
+        iterative_deepening: false,
+        action_costs: Cow::Borrowed(&[]),
+        cost_budget: None,
+        action_limits: Cow::Borrowed(&[]),
+        lemmas: Cow::Borrowed(&[]),
+        symbolic: None,
     };
 
     const FULL: ProblemDefinition = ProblemDefinition {
         given_elements: vec![],
         elements_to_find: vec![],
         action_count: 0,
-        action_types: &[
+        action_types: Cow::Borrowed(&[
             ActionType::LINE,
             ActionType::CIRCLE12,
             ActionType::CIRCLE21,
@@ -179,14 +471,170 @@ impl ProblemDefinition {
             ActionType::BISECTOR_POS_ACL,
             ActionType::BISECTOR_NEG_CAL,
             ActionType::BISECTOR_NEG_ACL,
-        ],
+        ]),
         random_walk_at_n_actions: None,
         prioritize_low_action_count_shapes: true,
         multimatch: false,
         find_all_solutions: false,
         track_supports_in_rw: false,
+        minimize_e_moves: false,
+        iterative_deepening: false,
+        action_costs: Cow::Borrowed(&[]),
+        cost_budget: None,
+        action_limits: Cow::Borrowed(&[]),
+        lemmas: Cow::Borrowed(&[]),
+        symbolic: None,
     };
 
+    pub fn get_preset(name: &str) -> Option<ProblemDefinition> {
+        match name {
+            "BASIC" => Some(Self::BASIC),
+            "LIMITED_ADVANCED" => Some(Self::LIMITED_ADVANCED),
+            "ADVANCED" => Some(Self::ADVANCED),
+            "FULL_WITHOUT_BISECTOR" => Some(Self::FULL_WITHOUT_BISECTOR),
+            "FULL" => Some(Self::FULL),
+            _ => None,
+        }
+    }
+
+    pub fn get_action_cost(&self, action_type: ActionType) -> u32 {
+        self.action_costs
+            .iter()
+            .find(|(listed, _)| *listed == action_type)
+            .map_or(1, |(_, cost)| *cost)
+    }
+
+    // Each shape still to find takes at least this much
+    pub fn get_min_action_cost(&self) -> u32 {
+        self.action_types
+            .iter()
+            .map(|action_type| self.get_action_cost(*action_type))
+            .min()
+            .unwrap_or(1)
+    }
+
+    // Makes the costs of the allowed action types their E-moves, so that a cost budget bounds
+    // the E-moves of the construction
+    pub fn set_e_move_costs(&mut self) {
+        let costs: Vec<(ActionType, u32)> = self
+            .action_types
+            .iter()
+            .map(|action_type| (*action_type, self.get_e_moves(*action_type)))
+            .collect();
+        self.action_costs = costs.into();
+    }
+
+    // Input kinds of the actions handled generically (tools and lemmas), and whether the inputs
+    // of the same kind can be swapped (see Tool::is_symmetric())
+    pub fn get_input_kinds(&self, action_type: ActionType) -> Option<(&[InputKind], bool)> {
+        match action_type {
+            ActionType::ToolActionType(value) => {
+                let tool = value.get_tool();
+                Some((tool.inputs(), tool.is_symmetric()))
+            }
+            ActionType::LemmaActionType(value) => {
+                Some((&self.lemmas[value.lemma as usize].input_kinds, false))
+            }
+            _ => None,
+        }
+    }
+
+    // The result of a tool or lemma (a point as Shape::point()); None for degenerate inputs
+    pub fn construct(
+        &self,
+        action_type: ActionType,
+        points: &[Point],
+        shapes: &[Shape],
+    ) -> Option<Shape> {
+        match action_type {
+            ActionType::ToolActionType(value) => value.get_tool().construct(points, shapes),
+            ActionType::LemmaActionType(value) => {
+                self.lemmas[value.lemma as usize].apply(points, shapes, value.output as usize)
+            }
+            _ => None,
+        }
+    }
+
+    // The E-moves of the action type, including the lemmas of the problem
+    pub fn get_e_moves(&self, action_type: ActionType) -> u32 {
+        match action_type {
+            ActionType::LemmaActionType(value) => self.lemmas[value.lemma as usize].e_moves,
+            _ => action_type.e_moves(),
+        }
+    }
+
+    // Makes the lemma available to the search (each output is an action type costing 1 by
+    // default, like the basic tools)
+    pub fn add_lemma(&mut self, lemma: Lemma) -> Result<(), String> {
+        if self.lemmas.iter().any(|known| known.name == lemma.name) {
+            return Err(format!("Lemma {} is added twice", lemma.name));
+        }
+        if self.lemmas.len() > u8::MAX as usize {
+            return Err("Too many lemmas".to_string());
+        }
+        let action_types = lemma.get_action_types(self.lemmas.len());
+        let mut lemmas = self.lemmas.to_vec();
+        lemmas.push(lemma);
+        self.lemmas = lemmas.into();
+        self.action_types = [&self.action_types[..], &action_types[..]].concat().into();
+        Ok(())
+    }
+
+    // Action types by name (see ActionType::from_str()), including the outputs of the lemmas
+    // (see Lemma::get_output_name())
+    pub fn parse_action_type(&self, name: &str) -> Result<ActionType, String> {
+        for (i, lemma) in self.lemmas.iter().enumerate() {
+            for (k, action_type) in lemma.get_action_types(i).into_iter().enumerate() {
+                if lemma.get_output_name(k) == name {
+                    return Ok(action_type);
+                }
+            }
+        }
+        ActionType::from_str(name)
+    }
+
+    // Inverse of parse_action_type()
+    pub fn get_action_type_name(&self, action_type: ActionType) -> String {
+        match action_type {
+            ActionType::LemmaActionType(value) => {
+                self.lemmas[value.lemma as usize].get_output_name(value.output as usize)
+            }
+            _ => action_type.name().unwrap().to_string(),
+        }
+    }
+
+    // Uses of the groups of action_limits (in the same order) by the action types
+    pub fn get_action_usage(&self, action_types: impl Iterator<Item = ActionType>) -> Vec<u32> {
+        let mut usage = vec![0; self.action_limits.len()];
+        for action_type in action_types {
+            self.add_action_usage(&mut usage, action_type);
+        }
+        usage
+    }
+
+    pub fn add_action_usage(&self, usage: &mut [u32], action_type: ActionType) {
+        for (count, (group, _)) in usage.iter_mut().zip(self.action_limits.iter()) {
+            if group.contains(action_type) {
+                *count += 1;
+            }
+        }
+    }
+
+    // Whether one more use of the action type stays within the limits
+    pub fn fits_action_limits(&self, usage: &[u32], action_type: ActionType) -> bool {
+        usage
+            .iter()
+            .zip(self.action_limits.iter())
+            .all(|(count, (group, limit))| !group.contains(action_type) || count < limit)
+    }
+
+    pub fn is_within_action_limits(&self, usage: &[u32]) -> bool {
+        usage
+            .iter()
+            .zip(self.action_limits.iter())
+            .all(|(count, (_, limit))| count <= limit)
+    }
+
     pub fn has_point_and_line_actions(&self) -> bool {
         self.action_types
             .iter()
@@ -205,6 +653,10 @@ impl ProblemDefinition {
             .any(|action_type| matches!(action_type, ActionType::TwoPointAndLineActionType(_)))
     }
 
+    pub fn has_action(&self, action_type: ActionType) -> bool {
+        self.action_types.contains(&action_type)
+    }
+
     // Easy problem, solved
     fn midpoint_problem_1_3() -> ProblemDefinition {
         let p1 = pt(-1.0, 0.0);
@@ -226,12 +678,12 @@ impl ProblemDefinition {
             given_elements: vec![Element::Point(p1), Element::Point(p2)],
             elements_to_find: vec![Element::Point(px)],
             action_count: 2,
-            action_types: &[
+            action_types: Cow::Borrowed(&[
                 ActionType::LINE,
                 ActionType::CIRCLE12,
                 ActionType::CIRCLE21,
                 ActionType::MID_PERP,
-            ],
+            ]),
             ..Self::BASIC
         }
     }
@@ -293,7 +745,6 @@ impl ProblemDefinition {
     fn tangent_to_circle_at_point_2_8() -> ProblemDefinition {
         let c = pt(0.0, 0.0);
         let p0 = pt(1.0, 0.0);
-        let v = pt(0.0, 1.0);
         let p_additional = pt((1.0 - 0.23f64 * 0.23).sqrt(), 0.23);
 
         ProblemDefinition {
@@ -303,7 +754,7 @@ impl ProblemDefinition {
                 Element::Point(c),
                 Element::Point(p_additional),
             ],
-            elements_to_find: vec![Element::LineAV(LineAV { a: p0, v })],
+            elements_to_find: vec![tangent_at(&c, &p0)],
             action_count: 3, // Looks like it can't be solved in 3 actions
             multimatch: true,
             ..Self::BASIC
@@ -311,26 +762,47 @@ impl ProblemDefinition {
     }
 
     fn tangent_to_circle_at_point_2_8_with_perp() -> ProblemDefinition {
-        let c = pt(0.0, 0.0);
-        let p0 = pt(1.0, 0.0);
-        let v = pt(0.0, 1.0);
+        static SYMBOLIC: SymbolicProblem = SymbolicProblem {
+            parameters: &[
+                Parameter::new("cx", 0.0, -0.5, 0.5),
+                Parameter::new("cy", 0.0, -0.5, 0.5),
+                Parameter::new("r", 1.0, 0.5, 1.5),
+                Parameter::new("angle", 0.0, -PI, PI),
+            ],
+            build: ProblemDefinition::tangent_to_circle_at_point_2_8_with_perp_elements,
+        };
+        Self::from_symbolic(
+            &SYMBOLIC,
+            ProblemDefinition {
+                action_count: 2,
+                action_types: Cow::Borrowed(&[
+                    ActionType::LINE,
+                    ActionType::CIRCLE12,
+                    ActionType::CIRCLE21,
+                    ActionType::PERP,
+                ]),
+                ..Self::BASIC
+            },
+        )
+    }
 
-        ProblemDefinition {
-            given_elements: vec![
+    // The point is at the given angle on the circle
+    fn tangent_to_circle_at_point_2_8_with_perp_elements(
+        values: &[f64],
+    ) -> Option<ProblemElements> {
+        let [cx, cy, r, angle] = values else {
+            return None;
+        };
+        let c = pt(*cx, *cy);
+        let p0 = pt(cx + r * angle.cos(), cy + r * angle.sin());
+        Some((
+            vec![
                 Element::CircleCP(CircleCP { c, p: p0 }),
                 Element::Point(p0),
                 Element::Point(c),
             ],
-            elements_to_find: vec![Element::LineAV(LineAV { a: p0, v })],
-            action_count: 2,
-            action_types: &[
-                ActionType::LINE,
-                ActionType::CIRCLE12,
-                ActionType::CIRCLE21,
-                ActionType::PERP,
-            ],
-            ..Self::BASIC
-        }
+            vec![tangent_at(&c, &p0)],
+        ))
     }
 
     fn equilateral_triangle_in_circle_problem_4_4() -> ProblemDefinition {
@@ -777,8 +1249,8 @@ impl ProblemDefinition {
         let pc = pt(0.0, 0.0);
         let p1 = pt(1.0, 1.0);
         let p2 = pt(1.12345, 0.0);
-        let px1 = pt(-1.0, -1.0);
-        let px2 = pt(-1.12345, 0.0);
+        let px1 = reflection(&p1, &pc);
+        let px2 = reflection(&p2, &pc);
         ProblemDefinition {
             given_elements: vec![
                 Element::LineAB(LineAB { a: p1, b: p2 }),
@@ -999,7 +1471,7 @@ impl ProblemDefinition {
             action_count: 5,
             multimatch: true,
             prioritize_low_action_count_shapes: false,
-            // action_types: &[
+            // action_types: Cow::Borrowed(&[
             //     ActionType::LINE,
             //     ActionType::CIRCLE12,
             //     ActionType::CIRCLE21,
@@ -1007,7 +1479,7 @@ impl ProblemDefinition {
             //     ActionType::CIRCLE_CAB,
             //     ActionType::CIRCLE_ACB,
             //     ActionType::CIRCLE_ABC,
-            // ],
+            // ]),
             ..Self::FULL_WITHOUT_BISECTOR
         }
     }
@@ -1108,40 +1580,91 @@ impl ProblemDefinition {
     }
 
     fn herons_problem_7_5() -> ProblemDefinition {
-        let c = pt(0.12345, 0.0);
+        static SYMBOLIC: SymbolicProblem = SymbolicProblem {
+            parameters: &[
+                Parameter::new("cx", 0.12345, -0.5, 0.5),
+                Parameter::new("x1", 0.6, 0.2, 1.0),
+                Parameter::new("y1", 0.8, 0.4, 1.2),
+                Parameter::new("x2", -0.72, -1.1, -0.3),
+                Parameter::new("y2", 0.96, 0.5, 1.4),
+            ],
+            build: ProblemDefinition::herons_problem_7_5_elements,
+        };
+        Self::from_symbolic(
+            &SYMBOLIC,
+            ProblemDefinition {
+                action_count: 4,
+                ..Self::BASIC
+            },
+        )
+    }
+
+    // The line is the x axis through the point c, the points p1 and p2 lie above it
+    fn herons_problem_7_5_elements(values: &[f64]) -> Option<ProblemElements> {
+        let [cx, x1, y1, x2, y2] = values else {
+            return None;
+        };
+        if *y1 <= 0.0 || *y2 <= 0.0 {
+            return None;
+        }
+        let c = pt(*cx, 0.0);
         let v = pt(1.0, 0.0);
-        let x = pt(0.0, 0.0);
-        let p1 = pt(0.6, 0.8);
-        let p2 = pt(-0.72, 0.96);
-        ProblemDefinition {
-            given_elements: vec![
+        // The line from p1 to the reflection of p2 crosses the x axis at x
+        let x = pt(x1 + (x2 - x1) * y1 / (y1 + y2), 0.0);
+        let p1 = pt(*x1, *y1);
+        let p2 = pt(*x2, *y2);
+        Some((
+            vec![
                 Element::LineAV(LineAV { a: c, v }),
                 Element::Point(p1),
                 Element::Point(p2),
                 Element::Point(c),
             ],
-            elements_to_find: vec![
+            vec![
                 Element::LineAB(LineAB { a: x, b: p1 }),
                 Element::LineAB(LineAB { a: x, b: p2 }),
             ],
-            action_count: 4,
-            ..Self::BASIC
-        }
+        ))
     }
 
     fn inscribed_circle_7_7_with_prep() -> ProblemDefinition {
-        // Heronian triangle (21, 20, 13), area = 126, h = 12.6
-        // r = 2S / (a1 + a2 + a3) = 14/3
-        // x-distance from b: (AB + BC - AC) / 2 = (20 + 21 - 13) / 2 = 14
-        let a = pt(0.0, 1.26);
-        let b = pt(-1.68, 0.0);
-        let c = pt(0.32, 0.0);
-
-        let cx = pt(1.4 - 1.68, 1.4 / 3.0);
-        let c_side = pt(0.32 - 1.3, 0.0);
+        // The original triangle is Heronian: (21, 20, 13) scaled by 0.1
+        static SYMBOLIC: SymbolicProblem = SymbolicProblem {
+            parameters: &[
+                Parameter::new("ax", 0.0, -0.5, 0.5),
+                Parameter::new("ay", 1.26, 0.8, 1.8),
+                Parameter::new("bx", -1.68, -2.2, -1.2),
+                Parameter::new("by", 0.0, -0.3, 0.3),
+                Parameter::new("cx", 0.32, 0.0, 0.8),
+                Parameter::new("cy", 0.0, -0.3, 0.3),
+            ],
+            build: ProblemDefinition::inscribed_circle_7_7_with_prep_elements,
+        };
+        Self::from_symbolic(
+            &SYMBOLIC,
+            ProblemDefinition {
+                action_count: 2,
+                ..Self::BASIC
+            },
+        )
+    }
 
-        ProblemDefinition {
-            given_elements: vec![
+    // Triangle abc with the incircle to find; the preparation circles go through the point
+    // c_side on cb with |c c_side| = |ca|
+    fn inscribed_circle_7_7_with_prep_elements(values: &[f64]) -> Option<ProblemElements> {
+        let [ax, ay, bx, by, cx, cy] = values else {
+            return None;
+        };
+        let ac = (cx - ax).hypot(cy - ay);
+        let bc = (cx - bx).hypot(cy - by);
+        if ac >= bc {
+            return None;
+        }
+        let (a, b, c) = (pt(*ax, *ay), pt(*bx, *by), pt(*cx, *cy));
+        let incircle = incircle(&a, &b, &c)?;
+        let c_side = pt(cx + (bx - cx) * ac / bc, cy + (by - cy) * ac / bc);
+        Some((
+            vec![
                 Element::SegmentAB(SegmentAB { a, b }),
                 Element::SegmentAB(SegmentAB { a, b: c }),
                 Element::SegmentAB(SegmentAB { a: c, b }),
@@ -1149,13 +1672,8 @@ impl ProblemDefinition {
                 Element::CircleCP(CircleCP { c: c_side, p: a }),
                 Element::CircleCP(CircleCP { c: a, p: c_side }),
             ],
-            elements_to_find: vec![Element::CircleCR(CircleCR {
-                c: cx,
-                r: FInt::new(1.4 / 3.0),
-            })],
-            action_count: 2,
-            ..Self::BASIC
-        }
+            vec![incircle],
+        ))
     }
 
     fn circle_tangent_to_three_lines_7_8() -> ProblemDefinition {
@@ -1188,23 +1706,47 @@ impl ProblemDefinition {
     // - Circle(c.x=-0.167,c.y=0.500,r2=0.278)
     // - Circle(c.x=0.000,c.y=0.000,r2=1.000)
     fn circle_tangent_to_three_lines_7_8_rw() -> ProblemDefinition {
-        let cx = pt(0.0, 0.0);
-        let p = pt(0.0, -1.0);
+        static SYMBOLIC: SymbolicProblem = SymbolicProblem {
+            parameters: &[
+                Parameter::new("x", 0.0, -0.5, 0.5),
+                Parameter::new("y", 0.0, -0.5, 0.5),
+                Parameter::new("r", 1.0, 0.5, 1.5),
+                // atan2(0.6, 0.8)
+                Parameter::new("angle", 0.6435011087932844, 0.3, 1.2),
+            ],
+            build: ProblemDefinition::circle_tangent_to_three_lines_7_8_rw_elements,
+        };
+        Self::from_symbolic(
+            &SYMBOLIC,
+            ProblemDefinition {
+                action_count: 6,
+                random_walk_at_n_actions: Some(4),
+                ..Self::BASIC
+            },
+        )
+    }
+
+    // The circle with center (x, y) and radius r touches the x axis and the two parallel lines
+    // in the direction of the angle
+    fn circle_tangent_to_three_lines_7_8_rw_elements(values: &[f64]) -> Option<ProblemElements> {
+        let [x, y, r, angle] = values else {
+            return None;
+        };
+        let (cos, sin) = (angle.cos(), angle.sin());
+        let cx = pt(*x, *y);
+        let p = pt(*x, y - r);
         let v = pt(1.0, 0.0);
-        let v2 = pt(0.8, 0.6);
-        let px1 = pt(-0.6, 0.8);
-        let px2 = pt(0.6, -0.8);
-        ProblemDefinition {
-            given_elements: vec![
+        let v2 = pt(cos, sin);
+        let px1 = pt(x - r * sin, y + r * cos);
+        let px2 = pt(x + r * sin, y - r * cos);
+        Some((
+            vec![
                 Element::LineAV(LineAV { a: p, v }),
                 Element::LineAV(LineAV { a: px1, v: v2 }),
                 Element::LineAV(LineAV { a: px2, v: v2 }),
             ],
-            elements_to_find: vec![Element::CircleCP(CircleCP { c: cx, p })],
-            action_count: 6,
-            random_walk_at_n_actions: Some(4),
-            ..Self::BASIC
-        }
+            vec![Element::CircleCP(CircleCP { c: cx, p })],
+        ))
     }
 
     fn circle_tangent_to_three_lines_7_8_rw_alt() -> ProblemDefinition {
@@ -1304,7 +1846,7 @@ impl ProblemDefinition {
             ],
             elements_to_find: vec![Element::LineAB(LineAB { a, b: px })],
             action_count: 4,
-            action_types: &[
+            action_types: Cow::Borrowed(&[
                 ActionType::LINE,
                 ActionType::CIRCLE12,
                 ActionType::CIRCLE21,
@@ -1312,7 +1854,7 @@ impl ProblemDefinition {
                 ActionType::CIRCLE_CAB,
                 ActionType::CIRCLE_ACB,
                 ActionType::CIRCLE_ABC,
-            ],
+            ]),
             ..Self::BASIC
         }
     }
@@ -1365,15 +1907,11 @@ impl ProblemDefinition {
     }
 
     fn regular_octagon_8_4() -> ProblemDefinition {
-        let kh = 0.5f64.sqrt();
         let p1 = pt(0.0, 0.0);
         let p2 = pt(1.0, 0.0);
-        let px3 = pt(1.0 + kh, kh);
-        let px4 = pt(1.0 + kh, 1.0 + kh);
-        let px5 = pt(1.0, 1.0 + 2.0 * kh);
-        let px6 = pt(0.0, 1.0 + 2.0 * kh);
-        let px7 = pt(-kh, 1.0 + kh);
-        let px8 = pt(-kh, kh);
+        let [_, _, px3, px4, px5, px6, px7, px8] = regular_polygon(&p1, &p2, 8)[..] else {
+            panic!("An octagon has 8 vertices");
+        };
 
         ProblemDefinition {
             given_elements: vec![
@@ -1398,15 +1936,11 @@ impl ProblemDefinition {
     }
 
     fn regular_octagon_8_4_adv() -> ProblemDefinition {
-        let kh = 0.5f64.sqrt();
         let p1 = pt(0.0, 0.0);
         let p2 = pt(1.0, 0.0);
-        let px3 = pt(1.0 + kh, kh);
-        let px4 = pt(1.0 + kh, 1.0 + kh);
-        let px5 = pt(1.0, 1.0 + 2.0 * kh);
-        let px6 = pt(0.0, 1.0 + 2.0 * kh);
-        let px7 = pt(-kh, 1.0 + kh);
-        let px8 = pt(-kh, kh);
+        let [_, _, px3, px4, px5, px6, px7, px8] = regular_polygon(&p1, &p2, 8)[..] else {
+            panic!("An octagon has 8 vertices");
+        };
 
         ProblemDefinition {
             given_elements: vec![
@@ -1488,12 +2022,12 @@ impl ProblemDefinition {
     }
 
     fn torricelli_point_8_6_rw() -> ProblemDefinition {
-        let o = pt(0.0, 0.0);
         let a = pt(0.0, 1.22348);
         let k1 = 1.328783;
         let k2 = 1.787345;
         let b = pt(-k1 * 0.75f64.sqrt(), -0.5 * k1);
         let c = pt(k2 * 0.75f64.sqrt(), -0.5 * k2);
+        let o = fermat_point(&a, &b, &c).unwrap();
 
         ProblemDefinition {
             given_elements: vec![
@@ -1512,12 +2046,12 @@ impl ProblemDefinition {
     }
 
     fn torricelli_point_8_6_adv() -> ProblemDefinition {
-        let o = pt(0.0, 0.0);
         let a = pt(0.0, 1.22348);
         let k1 = 1.328783;
         let k2 = 1.787345;
         let b = pt(-k1 * 0.75f64.sqrt(), -0.5 * k1);
         let c = pt(k2 * 0.75f64.sqrt(), -0.5 * k2);
+        let o = fermat_point(&a, &b, &c).unwrap();
 
         ProblemDefinition {
             given_elements: vec![
@@ -1582,9 +2116,11 @@ impl ProblemDefinition {
         let p2 = pt(r, 0.0);
         let p = pt(0.0, 0.0);
         let v = pt(0.72387, 0.932483);
-        let l = FInt::new(2.0 * 1.0 * r / (1.0 + r));
-        let d = v.distance_to(&p);
-        let px = Point(l * v.0 / d, l * v.1 / d);
+        let px = point_along(
+            &p,
+            &v,
+            harmonic_mean(p.distance_to(&p1), p.distance_to(&p2)),
+        );
 
         ProblemDefinition {
             given_elements: vec![
@@ -1687,7 +2223,7 @@ impl ProblemDefinition {
             ],
             elements_to_find: vec![Element::Point(px1)],
             action_count: 7,
-            action_types: &[ActionType::CIRCLE12, ActionType::CIRCLE21],
+            action_types: Cow::Borrowed(&[ActionType::CIRCLE12, ActionType::CIRCLE21]),
             multimatch: true,
             random_walk_at_n_actions: Some(5),
             ..Self::BASIC
@@ -1717,7 +2253,7 @@ impl ProblemDefinition {
             ],
             elements_to_find: vec![Element::Point(px1)],
             action_count: 5,
-            action_types: &[ActionType::CIRCLE12, ActionType::CIRCLE21],
+            action_types: Cow::Borrowed(&[ActionType::CIRCLE12, ActionType::CIRCLE21]),
             multimatch: true,
             random_walk_at_n_actions: Some(4),
             // prioritize_low_action_count_shapes: false,
@@ -1741,61 +2277,19 @@ impl ProblemDefinition {
 
     */
 
+    pub fn get_problem_entries() -> &'static [ProblemEntry] {
+        &PROBLEMS
+    }
+
+    pub fn get_problem_by_id(id: &str) -> Option<ProblemDefinition> {
+        PROBLEMS
+            .iter()
+            .find(|entry| entry.id == id)
+            .map(|entry| (entry.create)())
+    }
+
+    // Solved when no problem is given on the command line
     pub fn get_problem() -> ProblemDefinition {
-        Self::line_circle_intersection_15_8_mod()
-        // Self::circle_tangent_to_three_lines_7_8_rw_alt()
-        // Self::parallelogram_by_three_midpoints_6_11_full_partial()
-        // Self::midpoint_problem_1_3()
-
-        // Self::chord_trisection_10_8()
-        // Self::circumscribed_square_5_8_rw()
-        // Self::line_equidistant_from_two_lines_5_7_rw()
-
-        // Too large:
-        // Self::circumscribed_square_5_8()
-        // Self::circle_tangent_to_square_side_5_10()
-        // Self::circle_tangent_to_three_lines_7_8()
-        // Self::regular_octagon_8_4()
-        // Self::triangle_midsegment_9_10()
-
-        // Finds a solution with too many actions
-        // Self::parallelogram_by_three_midpoints_6_11()
-        // Self::angle_of_75_7_3_full()
-
-        // Solved problems
-        // Self::midpoint_problem_1_3()
-        // Self::inscribed_square_1_7()
-        // Self::tangent_to_circle_at_point_2_8()
-        // Self::angle_of_60_4_2()
-        // Self::circumscribed_equilateral_triangle_4_3()
-        // Self::line_equidistant_from_two_points_5_3()
-        // Self::shift_angle_5_6()
-        // Self::line_equidistant_from_two_lines_5_7()
-        // Self::point_reflection_6_1() - didn't mark the point
-        // Self::copy_segment_6_3()
-        // Self::chord_trisection_10_8()
-        // Self::tangent_to_circle_at_point_2_8_with_perp()
-        // Self::square_by_opposite_midpoints_4_9_adv()
-        // Self::equilateral_triangle_in_circle_problem_4_4_adv()
-        // Self::symmetry_of_four_lines_6_10()
-        // Self::symmetry_of_four_lines_6_10_adv()
-        // Self::annulus_7_2()
-        // Self::annulus_7_2_adv()
-        // Self::herons_problem_7_5() - added an unneeded action
-        // Self::segment_by_midpoint_7_9_adv()
-        // Self::angle_isosceles_7_10()
-        // Self::angle_54_trisection_8_2_mm()
-        // Self::interior_angles_8_3()
-        // Self::regular_octagon_8_4_adv()
-        // Self::triangle_cleaver_8_5_adv()
-        // Self::torricelli_point_8_6_adv() (required setting priority for found_point to 500)
-        // Self::harmonic_mean_of_segments_9_8()
-
-        // Solved with random walk
-        // Self::circle_tangent_to_square_side_5_10_rw_mod()
-        // Self::torricelli_point_8_6_rw()
-        // Self::minimum_perimeter_2_9_7()
-        // Self::equilateral_triangle_in_circle_problem_4_4()
-        // Self::circumscribed_square_5_8_mod()
+        Self::get_problem_by_id(DEFAULT_PROBLEM_ID).unwrap()
     }
 }
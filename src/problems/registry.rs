@@ -0,0 +1,595 @@
+use strum_macros::{EnumString, IntoStaticStr};
+
+use super::ProblemDefinition;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum ProblemVariant {
+    Basic,
+    // Uses the advanced set of actions
+    Advanced,
+    // Uses the full set of actions
+    Full,
+    // Relies on the random walk for the last actions
+    RandomWalk,
+    // The problem was changed (e.g. a partial target or extra given elements) to make it solvable
+    Modified,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum ProblemStatus {
+    Solved,
+    SolvedWithRandomWalk,
+    // The search runs out of memory or time before finding a solution
+    TooLarge,
+    // A solution is found, but with more actions than needed
+    TooManyActions,
+    NotSolved,
+    Unknown,
+}
+
+pub struct ProblemEntry {
+    // Stable id: "<pack>.<level>-<name>[-<variant>]"
+    pub id: &'static str,
+    pub pack: u32,
+    pub level: u32,
+    pub variant: ProblemVariant,
+    pub status: ProblemStatus,
+    pub note: Option<&'static str>,
+    pub create: fn() -> ProblemDefinition,
+}
+
+pub static PROBLEMS: [ProblemEntry; 58] = [
+    ProblemEntry {
+        id: "1.3-midpoint",
+        pack: 1,
+        level: 3,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::midpoint_problem_1_3,
+    },
+    ProblemEntry {
+        id: "1.3-midpoint-with-midperp",
+        pack: 1,
+        level: 3,
+        variant: ProblemVariant::Modified,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::midpoint_problem_1_3_with_midperp,
+    },
+    ProblemEntry {
+        id: "1.7-inscribed-square",
+        pack: 1,
+        level: 7,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: Some("apparently can't be solved in 7 actions"),
+        create: ProblemDefinition::inscribed_square_1_7,
+    },
+    ProblemEntry {
+        id: "1.7-inscribed-square-extended",
+        pack: 1,
+        level: 7,
+        variant: ProblemVariant::Modified,
+        status: ProblemStatus::NotSolved,
+        note: Some("apparently can't be solved in 7 actions"),
+        create: ProblemDefinition::inscribed_square_problem_1_7_extended,
+    },
+    ProblemEntry {
+        id: "2.8-tangent-to-circle-at-point",
+        pack: 2,
+        level: 8,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::tangent_to_circle_at_point_2_8,
+    },
+    ProblemEntry {
+        id: "2.8-tangent-to-circle-at-point-with-perp",
+        pack: 2,
+        level: 8,
+        variant: ProblemVariant::Modified,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::tangent_to_circle_at_point_2_8_with_perp,
+    },
+    ProblemEntry {
+        id: "4.4-equilateral-triangle-in-circle",
+        pack: 4,
+        level: 4,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::SolvedWithRandomWalk,
+        note: None,
+        create: ProblemDefinition::equilateral_triangle_in_circle_problem_4_4,
+    },
+    ProblemEntry {
+        id: "4.4-equilateral-triangle-in-circle-mm-mod",
+        pack: 4,
+        level: 4,
+        variant: ProblemVariant::Modified,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::equilateral_triangle_in_circle_problem_4_4_mm_mod,
+    },
+    ProblemEntry {
+        id: "4.4-equilateral-triangle-in-circle-adv",
+        pack: 4,
+        level: 4,
+        variant: ProblemVariant::Advanced,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::equilateral_triangle_in_circle_problem_4_4_adv,
+    },
+    ProblemEntry {
+        id: "4.2-angle-of-60",
+        pack: 4,
+        level: 2,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::angle_of_60_4_2,
+    },
+    ProblemEntry {
+        id: "4.2-angle-of-60-adv",
+        pack: 4,
+        level: 2,
+        variant: ProblemVariant::Advanced,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::angle_of_60_4_2_adv,
+    },
+    ProblemEntry {
+        id: "4.3-circumscribed-equilateral-triangle",
+        pack: 4,
+        level: 3,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::circumscribed_equilateral_triangle_4_3,
+    },
+    ProblemEntry {
+        id: "4.9-square-by-opposite-midpoints-adv",
+        pack: 4,
+        level: 9,
+        variant: ProblemVariant::Advanced,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::square_by_opposite_midpoints_4_9_adv,
+    },
+    ProblemEntry {
+        id: "5.3-line-equidistant-from-two-points",
+        pack: 5,
+        level: 3,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::line_equidistant_from_two_points_5_3,
+    },
+    ProblemEntry {
+        id: "5.6-shift-angle",
+        pack: 5,
+        level: 6,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: Some("required reducing the number of actions by 1 (not all shapes are constructed)"),
+        create: ProblemDefinition::shift_angle_5_6,
+    },
+    ProblemEntry {
+        id: "5.7-line-equidistant-from-two-lines",
+        pack: 5,
+        level: 7,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: Some(
+            "13 seconds with prioritize_low_action_count_shapes = false, 159 seconds with true",
+        ),
+        create: ProblemDefinition::line_equidistant_from_two_lines_5_7,
+    },
+    ProblemEntry {
+        id: "5.7-line-equidistant-from-two-lines-rw",
+        pack: 5,
+        level: 7,
+        variant: ProblemVariant::RandomWalk,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::line_equidistant_from_two_lines_5_7_rw,
+    },
+    ProblemEntry {
+        id: "5.8-circumscribed-square",
+        pack: 5,
+        level: 8,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::TooLarge,
+        note: None,
+        create: ProblemDefinition::circumscribed_square_5_8,
+    },
+    ProblemEntry {
+        id: "5.8-circumscribed-square-rw",
+        pack: 5,
+        level: 8,
+        variant: ProblemVariant::RandomWalk,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::circumscribed_square_5_8_rw,
+    },
+    ProblemEntry {
+        id: "5.8-circumscribed-square-mod",
+        pack: 5,
+        level: 8,
+        variant: ProblemVariant::Modified,
+        status: ProblemStatus::SolvedWithRandomWalk,
+        note: None,
+        create: ProblemDefinition::circumscribed_square_5_8_mod,
+    },
+    ProblemEntry {
+        id: "5.10-circle-tangent-to-square-side",
+        pack: 5,
+        level: 10,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::TooLarge,
+        note: None,
+        create: ProblemDefinition::circle_tangent_to_square_side_5_10,
+    },
+    ProblemEntry {
+        id: "5.10-circle-tangent-to-square-side-rw-mod",
+        pack: 5,
+        level: 10,
+        variant: ProblemVariant::RandomWalk,
+        status: ProblemStatus::SolvedWithRandomWalk,
+        note: None,
+        create: ProblemDefinition::circle_tangent_to_square_side_5_10_rw_mod,
+    },
+    ProblemEntry {
+        id: "5.9-square-in-square",
+        pack: 5,
+        level: 9,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::square_in_square_5_9,
+    },
+    ProblemEntry {
+        id: "6.1-point-reflection",
+        pack: 6,
+        level: 1,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: Some("didn't mark the point"),
+        create: ProblemDefinition::point_reflection_6_1,
+    },
+    ProblemEntry {
+        id: "6.3-copy-segment",
+        pack: 6,
+        level: 3,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::copy_segment_6_3,
+    },
+    ProblemEntry {
+        id: "6.10-symmetry-of-four-lines",
+        pack: 6,
+        level: 10,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::symmetry_of_four_lines_6_10,
+    },
+    ProblemEntry {
+        id: "6.10-symmetry-of-four-lines-adv",
+        pack: 6,
+        level: 10,
+        variant: ProblemVariant::Advanced,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::symmetry_of_four_lines_6_10_adv,
+    },
+    ProblemEntry {
+        id: "6.11-parallelogram-by-three-midpoints",
+        pack: 6,
+        level: 11,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::TooManyActions,
+        note: None,
+        create: ProblemDefinition::parallelogram_by_three_midpoints_6_11,
+    },
+    ProblemEntry {
+        id: "6.11-parallelogram-by-three-midpoints-adv",
+        pack: 6,
+        level: 11,
+        variant: ProblemVariant::Advanced,
+        status: ProblemStatus::Solved,
+        note: Some("solution found in 55301 seconds"),
+        create: ProblemDefinition::parallelogram_by_three_midpoints_6_11_adv,
+    },
+    ProblemEntry {
+        id: "6.11-parallelogram-by-three-midpoints-adv-mod",
+        pack: 6,
+        level: 11,
+        variant: ProblemVariant::Modified,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::parallelogram_by_three_midpoints_6_11_adv_mod,
+    },
+    ProblemEntry {
+        id: "6.11-parallelogram-by-three-midpoints-adv-partial",
+        pack: 6,
+        level: 11,
+        variant: ProblemVariant::Modified,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::parallelogram_by_three_midpoints_6_11_adv_partial,
+    },
+    ProblemEntry {
+        id: "6.11-parallelogram-by-three-midpoints-full-partial",
+        pack: 6,
+        level: 11,
+        variant: ProblemVariant::Modified,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::parallelogram_by_three_midpoints_6_11_full_partial,
+    },
+    ProblemEntry {
+        id: "7.2-annulus",
+        pack: 7,
+        level: 2,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::annulus_7_2,
+    },
+    ProblemEntry {
+        id: "7.2-annulus-adv",
+        pack: 7,
+        level: 2,
+        variant: ProblemVariant::Advanced,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::annulus_7_2_adv,
+    },
+    ProblemEntry {
+        id: "7.3-angle-of-75-full",
+        pack: 7,
+        level: 3,
+        variant: ProblemVariant::Full,
+        status: ProblemStatus::TooManyActions,
+        note: None,
+        create: ProblemDefinition::angle_of_75_7_3_full,
+    },
+    ProblemEntry {
+        id: "7.10-angle-isosceles",
+        pack: 7,
+        level: 10,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::angle_isosceles_7_10,
+    },
+    ProblemEntry {
+        id: "7.5-herons",
+        pack: 7,
+        level: 5,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: Some("added an unneeded action"),
+        create: ProblemDefinition::herons_problem_7_5,
+    },
+    ProblemEntry {
+        id: "7.7-inscribed-circle-with-prep",
+        pack: 7,
+        level: 7,
+        variant: ProblemVariant::Modified,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::inscribed_circle_7_7_with_prep,
+    },
+    ProblemEntry {
+        id: "7.8-circle-tangent-to-three-lines",
+        pack: 7,
+        level: 8,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::TooLarge,
+        note: None,
+        create: ProblemDefinition::circle_tangent_to_three_lines_7_8,
+    },
+    ProblemEntry {
+        id: "7.8-circle-tangent-to-three-lines-rw",
+        pack: 7,
+        level: 8,
+        variant: ProblemVariant::RandomWalk,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::circle_tangent_to_three_lines_7_8_rw,
+    },
+    ProblemEntry {
+        id: "7.8-circle-tangent-to-three-lines-rw-alt",
+        pack: 7,
+        level: 8,
+        variant: ProblemVariant::RandomWalk,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::circle_tangent_to_three_lines_7_8_rw_alt,
+    },
+    ProblemEntry {
+        id: "7.8-circle-tangent-to-three-lines-rw-mod",
+        pack: 7,
+        level: 8,
+        variant: ProblemVariant::RandomWalk,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::circle_tangent_to_three_lines_7_8_rw_mod,
+    },
+    ProblemEntry {
+        id: "7.9-segment-by-midpoint-adv",
+        pack: 7,
+        level: 9,
+        variant: ProblemVariant::Advanced,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::segment_by_midpoint_7_9_adv,
+    },
+    ProblemEntry {
+        id: "8.1-perimeter-bisector-adv",
+        pack: 8,
+        level: 1,
+        variant: ProblemVariant::Advanced,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::perimeter_bisector_8_1_adv,
+    },
+    ProblemEntry {
+        id: "8.2-angle-54-trisection-mm",
+        pack: 8,
+        level: 2,
+        variant: ProblemVariant::Modified,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::angle_54_trisection_8_2_mm,
+    },
+    ProblemEntry {
+        id: "8.3-interior-angles",
+        pack: 8,
+        level: 3,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::interior_angles_8_3,
+    },
+    ProblemEntry {
+        id: "8.4-regular-octagon",
+        pack: 8,
+        level: 4,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::TooLarge,
+        note: None,
+        create: ProblemDefinition::regular_octagon_8_4,
+    },
+    ProblemEntry {
+        id: "8.4-regular-octagon-adv",
+        pack: 8,
+        level: 4,
+        variant: ProblemVariant::Advanced,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::regular_octagon_8_4_adv,
+    },
+    ProblemEntry {
+        id: "8.5-triangle-cleaver-rw",
+        pack: 8,
+        level: 5,
+        variant: ProblemVariant::RandomWalk,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::triangle_cleaver_8_5_rw,
+    },
+    ProblemEntry {
+        id: "8.5-triangle-cleaver-adv",
+        pack: 8,
+        level: 5,
+        variant: ProblemVariant::Advanced,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::triangle_cleaver_8_5_adv,
+    },
+    ProblemEntry {
+        id: "8.6-torricelli-point-rw",
+        pack: 8,
+        level: 6,
+        variant: ProblemVariant::RandomWalk,
+        status: ProblemStatus::SolvedWithRandomWalk,
+        note: None,
+        create: ProblemDefinition::torricelli_point_8_6_rw,
+    },
+    ProblemEntry {
+        id: "8.6-torricelli-point-adv",
+        pack: 8,
+        level: 6,
+        variant: ProblemVariant::Advanced,
+        status: ProblemStatus::Solved,
+        note: Some("required setting priority for found_point to 500"),
+        create: ProblemDefinition::torricelli_point_8_6_adv,
+    },
+    ProblemEntry {
+        id: "9.7-minimum-perimeter-2",
+        pack: 9,
+        level: 7,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::SolvedWithRandomWalk,
+        note: None,
+        create: ProblemDefinition::minimum_perimeter_2_9_7,
+    },
+    ProblemEntry {
+        id: "9.8-harmonic-mean-of-segments",
+        pack: 9,
+        level: 8,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::harmonic_mean_of_segments_9_8,
+    },
+    ProblemEntry {
+        id: "9.10-triangle-midsegment",
+        pack: 9,
+        level: 10,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::TooLarge,
+        note: None,
+        create: ProblemDefinition::triangle_midsegment_9_10,
+    },
+    ProblemEntry {
+        id: "10.8-chord-trisection",
+        pack: 10,
+        level: 8,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Solved,
+        note: None,
+        create: ProblemDefinition::chord_trisection_10_8,
+    },
+    ProblemEntry {
+        id: "15.8-line-circle-intersection",
+        pack: 15,
+        level: 8,
+        variant: ProblemVariant::Basic,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::line_circle_intersection_15_8,
+    },
+    ProblemEntry {
+        id: "15.8-line-circle-intersection-mod",
+        pack: 15,
+        level: 8,
+        variant: ProblemVariant::Modified,
+        status: ProblemStatus::Unknown,
+        note: None,
+        create: ProblemDefinition::line_circle_intersection_15_8_mod,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_problem_ids() {
+        let mut ids = HashSet::new();
+        for entry in &PROBLEMS {
+            assert!(ids.insert(entry.id), "duplicate id {}", entry.id);
+            let prefix = format!("{}.{}-", entry.pack, entry.level);
+            assert!(entry.id.starts_with(&prefix), "{}", entry.id);
+        }
+    }
+
+    #[test]
+    fn test_problems_construct() {
+        for entry in &PROBLEMS {
+            let problem = (entry.create)();
+            assert!(problem.action_count > 0, "{}", entry.id);
+            assert!(!problem.given_elements.is_empty(), "{}", entry.id);
+            assert!(!problem.elements_to_find.is_empty(), "{}", entry.id);
+        }
+    }
+}
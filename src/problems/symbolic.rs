@@ -0,0 +1,166 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use super::{jitter, ProblemDefinition};
+use crate::element::Element;
+
+// Instances with degenerate parameter values are redrawn up to this many times
+const MAX_ATTEMPTS: u32 = 100;
+
+// A free parameter of a symbolic problem: its value in the original instance and the range
+// random instances are drawn from
+pub struct Parameter {
+    pub name: &'static str,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+}
+impl Parameter {
+    pub const fn new(name: &'static str, value: f64, min: f64, max: f64) -> Self {
+        Self {
+            name,
+            value,
+            min,
+            max,
+        }
+    }
+}
+
+// The given elements and the elements to find
+pub type ProblemElements = (Vec<Element>, Vec<Element>);
+
+// A problem given by its free parameters, with the given elements and the elements to find
+// derived from them (e.g. "triangle ABC, find the incircle"), so that new instances can be drawn
+pub struct SymbolicProblem {
+    pub parameters: &'static [Parameter],
+    // Returns the elements for the parameter values (in the order of the parameters), None if
+    // the values are degenerate (e.g. collinear vertices)
+    pub build: fn(&[f64]) -> Option<ProblemElements>,
+}
+impl SymbolicProblem {
+    pub fn original_values(&self) -> Vec<f64> {
+        self.parameters.iter().map(|p| p.value).collect()
+    }
+
+    // Drawn uniformly from the ranges
+    pub fn random_values(&self, rng: &mut StdRng) -> Vec<f64> {
+        self.parameters
+            .iter()
+            .map(|p| rng.random_range(p.min..=p.max))
+            .collect()
+    }
+
+    // Moved from the original values by up to the amount, staying within the ranges
+    pub fn perturbed_values(&self, rng: &mut StdRng, amount: f64) -> Vec<f64> {
+        self.parameters
+            .iter()
+            .map(|p| (p.value + jitter(rng, amount)).clamp(p.min, p.max))
+            .collect()
+    }
+}
+
+impl ProblemDefinition {
+    // The original instance of a symbolic problem; the other fields are taken from settings
+    pub fn from_symbolic(
+        symbolic: &'static SymbolicProblem,
+        settings: ProblemDefinition,
+    ) -> ProblemDefinition {
+        let (given_elements, elements_to_find) = (symbolic.build)(&symbolic.original_values())
+            .expect("The original values of a symbolic problem can't be degenerate");
+        ProblemDefinition {
+            given_elements,
+            elements_to_find,
+            symbolic: Some(symbolic),
+            ..settings
+        }
+    }
+
+    // An instance of a symbolic problem with the given parameter values (None for degenerate
+    // values or a problem that isn't symbolic)
+    pub fn instance(&self, values: &[f64]) -> Option<ProblemDefinition> {
+        let (given_elements, elements_to_find) = (self.symbolic?.build)(values)?;
+        Some(ProblemDefinition {
+            given_elements,
+            elements_to_find,
+            ..self.clone()
+        })
+    }
+
+    // An instance with all parameters drawn from their ranges
+    pub fn random_instance(&self, rng: &mut StdRng) -> Result<ProblemDefinition, String> {
+        let symbolic = self
+            .symbolic
+            .ok_or("The problem isn't symbolic, so it has no random instances".to_string())?;
+        self.draw_instance(|| symbolic.random_values(rng))
+    }
+
+    pub fn can_be_perturbed(&self) -> bool {
+        self.symbolic.is_some()
+    }
+
+    // An instance with the parameters of a symbolic problem shifted by up to the amount
+    pub fn perturb(&self, rng: &mut StdRng, amount: f64) -> Result<ProblemDefinition, String> {
+        let symbolic = self
+            .symbolic
+            .ok_or("The problem isn't symbolic, so it can't be perturbed".to_string())?;
+        self.draw_instance(|| symbolic.perturbed_values(rng, amount))
+    }
+
+    fn draw_instance(
+        &self,
+        mut draw_values: impl FnMut() -> Vec<f64>,
+    ) -> Result<ProblemDefinition, String> {
+        (0..MAX_ATTEMPTS)
+            .find_map(|_| self.instance(&draw_values()))
+            .ok_or(format!(
+                "No non-degenerate instance found in {} attempts",
+                MAX_ATTEMPTS
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::shape::Point;
+
+    fn coords(point: &Point) -> (f64, f64) {
+        (point.0.midpoint(), point.1.midpoint())
+    }
+
+    #[test]
+    fn test_random_instance() {
+        let problem =
+            ProblemDefinition::get_problem_by_id("7.7-inscribed-circle-with-prep").unwrap();
+        let original = problem
+            .instance(&problem.symbolic.unwrap().original_values())
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", original.given_elements),
+            format!("{:?}", problem.given_elements)
+        );
+
+        for seed in 0..10 {
+            let instance = problem
+                .random_instance(&mut StdRng::seed_from_u64(seed))
+                .unwrap();
+            assert_eq!(instance.action_count, problem.action_count);
+            let Element::CircleCR(incircle) = &instance.elements_to_find[0] else {
+                panic!("The incircle should be a CircleCR");
+            };
+            let (cx, cy) = coords(&incircle.c);
+            // The incircle touches the three sides
+            for element in &instance.given_elements[0..3] {
+                let Element::SegmentAB(side) = element else {
+                    panic!("The first given elements should be the sides");
+                };
+                let ((ax, ay), (bx, by)) = (coords(&side.a), coords(&side.b));
+                let distance = ((bx - ax) * (cy - ay) - (by - ay) * (cx - ax)).abs()
+                    / (bx - ax).hypot(by - ay);
+                assert!((distance - incircle.r.midpoint()).abs() < 1e-9);
+            }
+        }
+    }
+}
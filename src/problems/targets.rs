@@ -0,0 +1,353 @@
+use std::f64::consts::PI;
+
+use crate::{
+    element::{CircleCP, CircleCR, Element, LineAB, LineAV},
+    fint::FInt,
+    shape::Point,
+};
+
+// Targets of common constructions, computed from the points defining them. Polygons and
+// triangles built on a side ab lie to the left of it (counterclockwise).
+
+// Triangles with a smaller doubled area are considered degenerate
+const MIN_AREA2: f64 = 1e-9;
+
+fn add(p: &Point, q: &Point) -> Point {
+    Point(p.0 + q.0, p.1 + q.1)
+}
+
+fn sub(p: &Point, q: &Point) -> Point {
+    Point(p.0 - q.0, p.1 - q.1)
+}
+
+fn scaled(p: &Point, k: FInt) -> Point {
+    Point(p.0 * k, p.1 * k)
+}
+
+fn cross(p: &Point, q: &Point) -> FInt {
+    p.0 * q.1 - p.1 * q.0
+}
+
+// Twice the signed area of abc, positive if abc is counterclockwise
+fn area2(a: &Point, b: &Point, c: &Point) -> FInt {
+    cross(&sub(b, a), &sub(c, a))
+}
+
+fn is_degenerate(a: &Point, b: &Point, c: &Point) -> bool {
+    area2(a, b, c).midpoint().abs() < MIN_AREA2
+}
+
+// The average of the points with the given weights
+fn weighted(points: [&Point; 3], weights: [FInt; 3]) -> Point {
+    let total = weights[0] + weights[1] + weights[2];
+    let x = points[0].0 * weights[0] + points[1].0 * weights[1] + points[2].0 * weights[2];
+    let y = points[0].1 * weights[0] + points[1].1 * weights[1] + points[2].1 * weights[2];
+    Point(x / total, y / total)
+}
+
+pub fn midpoint(a: &Point, b: &Point) -> Point {
+    scaled(&add(a, b), FInt::new(0.5))
+}
+
+// The point symmetric to p with respect to the center
+pub fn reflection(p: &Point, center: &Point) -> Point {
+    sub(&scaled(center, FInt::new(2.0)), p)
+}
+
+// The point at the given distance from a in the direction v
+pub fn point_along(a: &Point, v: &Point, length: FInt) -> Point {
+    let norm = (v.0.sqr() + v.1.sqr()).sqrt();
+    add(a, &scaled(v, length / norm))
+}
+
+pub fn harmonic_mean(x: FInt, y: FInt) -> FInt {
+    FInt::new(2.0) * x * y / (x + y)
+}
+
+pub fn geometric_mean(x: FInt, y: FInt) -> FInt {
+    (x * y).sqrt()
+}
+
+// The third vertex of the equilateral triangle on ab
+pub fn equilateral_triangle(a: &Point, b: &Point) -> Point {
+    let half = FInt::new(0.5);
+    let height = FInt::new(0.75f64.sqrt());
+    let ab = sub(b, a);
+    add(
+        &add(a, &scaled(&ab, half)),
+        &scaled(&ab.rotated_90_pos(), height),
+    )
+}
+
+// The vertices of the regular n-gon on ab, starting with a and b
+pub fn regular_polygon(a: &Point, b: &Point, n: usize) -> Vec<Point> {
+    let angle = 2.0 * PI / n as f64;
+    let (cos, sin) = (FInt::new(angle.cos()), FInt::new(angle.sin()));
+    let mut vertices = vec![*a, *b];
+    let mut side = sub(b, a);
+    for _ in 2..n {
+        side = Point(side.0 * cos - side.1 * sin, side.0 * sin + side.1 * cos);
+        vertices.push(add(vertices.last().unwrap(), &side));
+    }
+    vertices
+}
+
+// The other two vertices of the square abcd
+pub fn square(a: &Point, b: &Point) -> [Point; 2] {
+    let side = sub(b, a).rotated_90_pos();
+    [add(b, &side), add(a, &side)]
+}
+
+// None for collinear vertices
+pub fn circumcircle(a: &Point, b: &Point, c: &Point) -> Option<Element> {
+    if is_degenerate(a, b, c) {
+        return None;
+    }
+    let (ab, ac) = (sub(b, a), sub(c, a));
+    let (ab2, ac2) = (ab.0.sqr() + ab.1.sqr(), ac.0.sqr() + ac.1.sqr());
+    let d = FInt::new(2.0) * cross(&ab, &ac);
+    let center = Point(
+        a.0 + (ac.1 * ab2 - ab.1 * ac2) / d,
+        a.1 + (ab.0 * ac2 - ac.0 * ab2) / d,
+    );
+    Some(Element::CircleCP(CircleCP { c: center, p: *a }))
+}
+
+// None for collinear vertices
+pub fn incircle(a: &Point, b: &Point, c: &Point) -> Option<Element> {
+    if is_degenerate(a, b, c) {
+        return None;
+    }
+    let (la, lb, lc) = (b.distance_to(c), c.distance_to(a), a.distance_to(b));
+    Some(Element::CircleCR(CircleCR {
+        c: weighted([a, b, c], [la, lb, lc]),
+        r: area2(a, b, c).abs() / (la + lb + lc),
+    }))
+}
+
+// The excircle opposite to a (touching bc and the extensions of ab and ac); None for collinear
+// vertices
+pub fn excircle(a: &Point, b: &Point, c: &Point) -> Option<Element> {
+    if is_degenerate(a, b, c) {
+        return None;
+    }
+    let (la, lb, lc) = (b.distance_to(c), c.distance_to(a), a.distance_to(b));
+    Some(Element::CircleCR(CircleCR {
+        c: weighted([a, b, c], [la.negate(), lb, lc]),
+        r: area2(a, b, c).abs() / (lb + lc - la),
+    }))
+}
+
+// The tangent to the circle with the given center at the point p of the circle
+pub fn tangent_at(center: &Point, p: &Point) -> Element {
+    Element::LineAV(LineAV {
+        a: *p,
+        v: sub(p, center).rotated_90_pos(),
+    })
+}
+
+// The two tangents from p to the circle; None if p isn't outside of the circle
+pub fn tangents_from_point(p: &Point, center: &Point, r: FInt) -> Option<[Element; 2]> {
+    let d2 = (p.0 - center.0).sqr() + (p.1 - center.1).sqr();
+    if d2.midpoint() <= r.sqr().midpoint() {
+        return None;
+    }
+    // The points of tangency, seen from the center: r^2 / d along cp, r * t / d across it
+    let cp = sub(p, center);
+    let along = scaled(&cp, r.sqr() / d2);
+    let across = scaled(&cp.rotated_90_pos(), r * (d2 - r.sqr()).sqrt() / d2);
+    let touch1 = add(center, &add(&along, &across));
+    let touch2 = add(center, &sub(&along, &across));
+    Some([
+        Element::LineAB(LineAB { a: *p, b: touch1 }),
+        Element::LineAB(LineAB { a: *p, b: touch2 }),
+    ])
+}
+
+// The Fermat (Torricelli) point, minimizing the sum of the distances to the vertices: the
+// vertex with an angle of at least 120 degrees if there's one, otherwise the intersection of
+// the lines from the vertices to the apexes of the outer equilateral triangles on the opposite
+// sides. None for collinear vertices.
+pub fn fermat_point(a: &Point, b: &Point, c: &Point) -> Option<Point> {
+    if is_degenerate(a, b, c) {
+        return None;
+    }
+    for (p, q, r) in [(a, b, c), (b, c, a), (c, a, b)] {
+        let (u, v) = (sub(q, p), sub(r, p));
+        let cos = (u.0 * v.0 + u.1 * v.1) / (q.distance_to(p) * r.distance_to(p));
+        if cos.midpoint() <= -0.5 {
+            return Some(*p);
+        }
+    }
+    // The outer triangles lie to the left of the sides of the clockwise triangle
+    let (a, b, c) = if area2(a, b, c).midpoint() > 0.0 {
+        (a, c, b)
+    } else {
+        (a, b, c)
+    };
+    let apex_a = equilateral_triangle(b, c);
+    let apex_b = equilateral_triangle(c, a);
+    // a + t (apex_a - a) = b + s (apex_b - b)
+    let (u, v) = (sub(&apex_a, a), sub(&apex_b, b));
+    let t = cross(&sub(b, a), &v) / cross(&u, &v);
+    Some(add(a, &scaled(&u, t)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::ShapeTrait;
+
+    fn pt(x: f64, y: f64) -> Point {
+        Point(FInt::new(x), FInt::new(y))
+    }
+
+    // The interval of the value contains the expected one
+    fn contains(x: FInt, value: f64) -> bool {
+        let (lower, upper) = x.get_bounds();
+        lower <= value && value <= upper
+    }
+
+    #[test]
+    fn test_midpoint() {
+        assert!(midpoint(&pt(-1.0, 0.5), &pt(2.0, 1.5)).almost_equals(&pt(0.5, 1.0)));
+    }
+
+    #[test]
+    fn test_reflection() {
+        assert!(reflection(&pt(-1.0, 0.5), &pt(2.0, 1.5)).almost_equals(&pt(5.0, 2.5)));
+    }
+
+    #[test]
+    fn test_point_along() {
+        let point = point_along(&pt(1.0, 1.0), &pt(3.0, -4.0), FInt::new(2.0));
+        assert!(point.almost_equals(&pt(2.2, -0.6)));
+    }
+
+    #[test]
+    fn test_harmonic_mean() {
+        assert!(harmonic_mean(FInt::new(1.0), FInt::new(3.0)).almost_equals(FInt::new(1.5)));
+    }
+
+    #[test]
+    fn test_geometric_mean() {
+        assert!(geometric_mean(FInt::new(2.0), FInt::new(8.0)).almost_equals(FInt::new(4.0)));
+    }
+
+    #[test]
+    fn test_equilateral_triangle() {
+        let (a, b) = (pt(0.0, 0.0), pt(2.0, 0.0));
+        let apex = equilateral_triangle(&a, &b);
+        assert!(apex.almost_equals(&pt(1.0, 3.0f64.sqrt())));
+        // The other side of ba
+        assert!(equilateral_triangle(&b, &a).almost_equals(&pt(1.0, -(3.0f64.sqrt()))));
+    }
+
+    #[test]
+    fn test_regular_polygon() {
+        let octagon = regular_polygon(&pt(0.0, 0.0), &pt(1.0, 0.0), 8);
+        let kh = 0.5f64.sqrt();
+        assert_eq!(octagon.len(), 8);
+        assert!(octagon[2].almost_equals(&pt(1.0 + kh, kh)));
+        assert!(octagon[7].almost_equals(&pt(-kh, kh)));
+    }
+
+    #[test]
+    fn test_square() {
+        let [c, d] = square(&pt(1.0, 1.0), &pt(3.0, 2.0));
+        assert!(c.almost_equals(&pt(2.0, 4.0)));
+        assert!(d.almost_equals(&pt(0.0, 3.0)));
+    }
+
+    #[test]
+    fn test_circumcircle() {
+        let (a, b, c) = (pt(0.0, 1.26), pt(-1.68, 0.0), pt(0.32, 0.0));
+        let Some(Element::CircleCP(circle)) = circumcircle(&a, &b, &c) else {
+            panic!("The circumcircle should be a CircleCP");
+        };
+        let r = circle.c.distance_to(&a);
+        assert!(circle.c.distance_to(&b).almost_equals(r));
+        assert!(circle.c.distance_to(&c).almost_equals(r));
+        assert!(circumcircle(&a, &b, &pt(1.68, 2.52)).is_none());
+    }
+
+    #[test]
+    fn test_incircle() {
+        let (a, b, c) = (pt(0.0, 1.26), pt(-1.68, 0.0), pt(0.32, 0.0));
+        let Some(Element::CircleCR(circle)) = incircle(&a, &b, &c) else {
+            panic!("The incircle should be a CircleCR");
+        };
+        assert!(circle.c.almost_equals(&pt(1.4 - 1.68, 1.4 / 3.0)));
+        assert!(contains(circle.r, 1.4 / 3.0));
+        // Clockwise vertices give the same circle
+        let Some(Element::CircleCR(clockwise)) = incircle(&a, &c, &b) else {
+            panic!("The incircle should be a CircleCR");
+        };
+        assert!(contains(clockwise.r, 1.4 / 3.0));
+        assert!(incircle(&a, &b, &pt(1.68, 2.52)).is_none());
+    }
+
+    #[test]
+    fn test_excircle() {
+        // The 3-4-5 triangle with the right angle at a: the excircle opposite to a has the
+        // radius (3 + 4 + 5) / 2 and its center at (6, 6)
+        let (a, b, c) = (pt(0.0, 0.0), pt(4.0, 0.0), pt(0.0, 3.0));
+        let Some(Element::CircleCR(circle)) = excircle(&a, &b, &c) else {
+            panic!("The excircle should be a CircleCR");
+        };
+        assert!(circle.c.almost_equals(&pt(6.0, 6.0)));
+        assert!(contains(circle.r, 6.0));
+        assert!(excircle(&a, &b, &pt(8.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn test_tangent_at() {
+        let Element::LineAV(tangent) = tangent_at(&pt(1.0, 1.0), &pt(1.0, 3.0)) else {
+            panic!("The tangent should be a LineAV");
+        };
+        assert!(tangent.a.almost_equals(&pt(1.0, 3.0)));
+        // Horizontal
+        assert!(tangent.v.1.almost_equals(FInt::new(0.0)));
+    }
+
+    #[test]
+    fn test_tangents_from_point() {
+        // The tangents touch the circle at a single point
+        let tangents = tangents_from_point(&pt(2.0, 1.0), &pt(0.0, 0.0), FInt::new(1.0)).unwrap();
+        let unit_circle = Element::CircleCR(CircleCR {
+            c: pt(0.0, 0.0),
+            r: FInt::new(1.0),
+        });
+        for tangent in &tangents {
+            let points = tangent
+                .get_shape()
+                .unwrap()
+                .find_intersection_points(&unit_circle.get_shape().unwrap());
+            let [Some(point1), point2] = points else {
+                panic!("The tangent should touch the circle");
+            };
+            assert!(point2.is_none_or(|point2| point2.almost_equals(&point1)));
+        }
+        assert!(tangents_from_point(&pt(0.5, 0.0), &pt(0.0, 0.0), FInt::new(1.0)).is_none());
+    }
+
+    #[test]
+    fn test_fermat_point() {
+        // The vertices are seen from the Fermat point at 120 degrees
+        let k1 = 1.328783;
+        let k2 = 1.787345;
+        let a = pt(0.0, 1.22348);
+        let b = pt(-k1 * 0.75f64.sqrt(), -0.5 * k1);
+        let c = pt(k2 * 0.75f64.sqrt(), -0.5 * k2);
+        assert!(fermat_point(&a, &b, &c)
+            .unwrap()
+            .almost_equals(&pt(0.0, 0.0)));
+        assert!(fermat_point(&a, &c, &b)
+            .unwrap()
+            .almost_equals(&pt(0.0, 0.0)));
+        let obtuse = (pt(0.0, 0.0), pt(2.0, 0.0), pt(-1.0, 0.2));
+        assert!(fermat_point(&obtuse.0, &obtuse.1, &obtuse.2)
+            .unwrap()
+            .almost_equals(&obtuse.0));
+    }
+}
@@ -1,4 +1,5 @@
 use regex::Regex;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
 use std::{
     fmt::{self, Display, Formatter},
     hash::{DefaultHasher, Hasher},
@@ -26,11 +27,30 @@ impl WithTwoHashes for Point {
         self.1.hash2(state);
     }
 }
+impl Serialize for Point {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Point", 2)?;
+        state.serialize_field("x", &self.0)?;
+        state.serialize_field("y", &self.1)?;
+        state.end()
+    }
+}
 impl Display for Point {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "Pt(x={},y={})", self.0, self.1)
     }
 }
+impl FromStr for Point {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Example: Pt(x=0.600,y=-0.800)
+        let regex = Regex::new(r"Pt\(x=([0-9.e-]+),y=([0-9.e-]+)\)").unwrap();
+        let captures = regex.captures(s).ok_or("Wrong format: ".to_string() + s)?;
+        let x: f64 = captures.get(1).unwrap().as_str().parse().unwrap();
+        let y: f64 = captures.get(2).unwrap().as_str().parse().unwrap();
+        Ok(Point(FInt::new(x), FInt::new(y)))
+    }
+}
 impl fmt::Debug for Point {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         fmt::Display::fmt(&self, f)
@@ -65,7 +85,7 @@ impl Point {
         self.0 * point.1 - self.1 * point.0 == FInt::new(0.0)
     }
 
-    fn almost_equals(&self, point: &Point) -> bool {
+    pub fn almost_equals(&self, point: &Point) -> bool {
         self.0.almost_equals(point.0) && self.1.almost_equals(point.1)
     }
 }
@@ -76,7 +96,7 @@ pub trait ShapeTrait: Display {
     fn well_formed(&self) -> bool;
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize)]
 pub struct Line {
     pub nx: FInt,
     pub ny: FInt,
@@ -108,6 +128,7 @@ impl ShapeTrait for Line {
             Shape::Ray(ray) => [ray.intersect_with_line(&self), None],
             Shape::Segment(segment) => [segment.intersect_with_line(&self), None],
             Shape::Circle(circle) => self.intersect_with_circle(&circle),
+            Shape::Point(_point) => [None, None],
         }
     }
 
@@ -203,7 +224,7 @@ impl Line {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize)]
 pub struct Circle {
     pub c: Point,
     pub r2: FInt,
@@ -232,6 +253,7 @@ impl ShapeTrait for Circle {
             Shape::Ray(ray) => ray.intersect_with_circle(self),
             Shape::Segment(segment) => segment.intersect_with_circle(&self),
             Shape::Circle(circle) => self.intersect_with_circle(&circle),
+            Shape::Point(_point) => [None, None],
         }
     }
 
@@ -310,7 +332,7 @@ impl Circle {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize)]
 pub struct Ray {
     pub a: Point,
     pub v: Point,
@@ -339,6 +361,7 @@ impl ShapeTrait for Ray {
             Shape::Ray(ray) => [self.intersect_with_ray(&ray), None],
             Shape::Segment(segment) => [segment.intersect_with_ray(&self), None],
             Shape::Circle(circle) => self.intersect_with_circle(&circle),
+            Shape::Point(_point) => [None, None],
         }
     }
 
@@ -460,7 +483,7 @@ impl Ray {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize)]
 pub struct Segment {
     pub a: Point,
     pub b: Point,
@@ -489,6 +512,7 @@ impl ShapeTrait for Segment {
             Shape::Ray(ray) => [self.intersect_with_ray(&ray), None],
             Shape::Segment(segment) => [self.intersect_with_segment(&segment), None],
             Shape::Circle(circle) => self.intersect_with_circle(&circle),
+            Shape::Point(_point) => [None, None],
         }
     }
 
@@ -602,12 +626,14 @@ impl Segment {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize)]
 pub enum Shape {
     Line(Line),
     Ray(Ray),
     Segment(Segment),
     Circle(Circle),
+    // A point where a shape is expected, e.g. the result of a Point action or of a tool
+    Point(Point),
 }
 impl WithTwoHashes for Shape {
     fn hash1<H: Hasher>(&self, state: &mut H) {
@@ -616,6 +642,7 @@ impl WithTwoHashes for Shape {
             Shape::Ray(ray) => ray.hash1(state),
             Shape::Segment(segment) => segment.hash1(state),
             Shape::Circle(circle) => circle.hash1(state),
+            Shape::Point(point) => point.hash1(state),
         }
     }
 
@@ -625,6 +652,7 @@ impl WithTwoHashes for Shape {
             Shape::Ray(ray) => ray.hash2(state),
             Shape::Segment(segment) => segment.hash2(state),
             Shape::Circle(circle) => circle.hash2(state),
+            Shape::Point(point) => point.hash2(state),
         }
     }
 }
@@ -635,6 +663,7 @@ impl Display for Shape {
             Shape::Ray(ray) => ray.fmt(f),
             Shape::Segment(segment) => segment.fmt(f),
             Shape::Circle(circle) => circle.fmt(f),
+            Shape::Point(point) => point.fmt(f),
         }
     }
 }
@@ -645,6 +674,7 @@ impl ShapeTrait for Shape {
             Shape::Ray(ray) => ray.find_intersection_points(shape),
             Shape::Segment(segment) => segment.find_intersection_points(shape),
             Shape::Circle(circle) => circle.find_intersection_points(shape),
+            Shape::Point(_point) => [None, None],
         }
     }
 
@@ -654,6 +684,7 @@ impl ShapeTrait for Shape {
             Shape::Ray(ray) => ray.contains_point(point),
             Shape::Segment(segment) => segment.contains_point(point),
             Shape::Circle(circle) => circle.contains_point(point),
+            Shape::Point(p) => p == point,
         }
     }
 
@@ -663,6 +694,7 @@ impl ShapeTrait for Shape {
             Shape::Ray(ray) => ray.well_formed(),
             Shape::Segment(segment) => segment.well_formed(),
             Shape::Circle(circle) => circle.well_formed(),
+            Shape::Point(point) => point.well_formed(),
         }
     }
 }
@@ -676,17 +708,29 @@ impl FromStr for Shape {
             "Circle" => Circle::from_str(s).map(|circle| Shape::Circle(circle)),
             "Ray" => Ray::from_str(s).map(|ray| Shape::Ray(ray)),
             "Segment" => Segment::from_str(s).map(|segment| Shape::Segment(segment)),
+            "Pt" => Point::from_str(s).map(Shape::Point),
             _ => Err("Wrong head: {}".to_string() + head),
         }
     }
 }
 impl Shape {
+    pub fn point(point: Point) -> Shape {
+        Shape::Point(point)
+    }
+
+    pub fn as_point(&self) -> Option<Point> {
+        match self {
+            Shape::Point(point) => Some(*point),
+            _ => None,
+        }
+    }
+
     pub fn get_direction(&self) -> Option<Point> {
         match self {
             Shape::Line(line) => line.get_direction(),
             Shape::Ray(ray) => ray.get_direction(),
             Shape::Segment(segment) => segment.get_direction(),
-            Shape::Circle(_circle) => None,
+            Shape::Circle(_) | Shape::Point(_) => None,
         }
     }
 
@@ -695,7 +739,7 @@ impl Shape {
             Shape::Line(_line) => true,
             Shape::Ray(ray) => ray.intersects_with_collinear_ray(point, v),
             Shape::Segment(segment) => segment.intersects_with_collinear_ray(point, v),
-            Shape::Circle(_circle) => false,
+            Shape::Circle(_) | Shape::Point(_) => false,
         }
     }
 
@@ -705,6 +749,7 @@ impl Shape {
             (Shape::Circle(x), Shape::Circle(y)) => x.almost_equals(y),
             (Shape::Ray(x), Shape::Ray(y)) => x.almost_equals(y),
             (Shape::Segment(x), Shape::Segment(y)) => x.almost_equals(y),
+            (Shape::Point(x), Shape::Point(y)) => x.almost_equals(y),
             _ => false,
         }
     }
@@ -739,6 +784,15 @@ mod tests {
         assert_eq!(from_str, circle);
     }
 
+    #[test]
+    fn test_from_str_for_point() {
+        let shape = Shape::point(Point(FInt::new(0.6), FInt::new(-0.8)));
+        let as_str = format!("{}", shape);
+        assert_eq!(as_str, "Pt(x=0.600,y=-0.800)".to_string());
+        assert_eq!(Shape::from_str(as_str.as_str()).unwrap(), shape);
+        assert_eq!(shape.get_direction(), None);
+    }
+
     #[test]
     fn test_line_intersection() {
         let line1 = Line {